@@ -1,14 +1,20 @@
 #![forbid(unsafe_code)]
 
-use druid_shell::piet::Piet;
+use druid_shell::kurbo::{self, Circle, Line, Point, Rect};
+use druid_shell::piet::{Color, Piet, RenderContext, TextLayout};
 use druid_shell::KeyEvent;
 use keyboard_types::Modifiers;
 
+use crate::commands::CommandId;
+use crate::dialog::{FileDialog, FileDialogKind};
 use crate::geometry::Size;
 use crate::layout::Layout;
 use crate::prelude::*;
+use crate::text::text_layout;
 
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::time::Duration;
 
@@ -23,8 +29,13 @@ pub enum On {
     PointerMove,
     PointerEnter,
     PointerLeave,
+    // Sent to the hovered nodes from innermost to outermost, until a callback stops propagation
     PointerWheel,
+    // Sent to the focused node whether a key is pressed or released
     Keyboard,
+    // Sent to the focused node, then each of its ancestors until a callback stops propagation
+    KeyDown,
+    KeyUp,
     Focus,
     Blur,
     WindowFocus,
@@ -33,13 +44,17 @@ pub enum On {
 }
 
 /// A return type for callbacks to signal which render phase to skip to.
+///
+/// Phases are ordered by how much work they redo, and each one includes the phases below it.
 #[must_use]
 #[derive(Debug, Copy, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub enum Phase {
     Idle = 0,
     Draw = 1,
     Layout = 2,
-    Build = 3,
+    /// Apply stylesheets to the existing tree again, without calling the view callback.
+    Style = 3,
+    Build = 4,
 }
 
 impl Phase {
@@ -63,6 +78,37 @@ pub struct DrawCtx<'a, 'b> {
     pub must_draw: bool,
 }
 
+/// Helpers for common drawing. Shapes are filled or stroked with the color they're given, text uses the node's font and color,
+/// and everything respects the current transform and clip.
+impl<'a, 'b> DrawCtx<'a, 'b> {
+    pub fn fill_rect(&mut self, rect: Rect, color: &Color) {
+        self.piet.fill(rect, color);
+    }
+
+    pub fn stroke_rect(&mut self, rect: Rect, color: &Color, width: f64) {
+        self.piet.stroke(rect, color, width);
+    }
+
+    pub fn fill_rounded_rect(&mut self, rect: Rect, radius: f64, color: &Color) {
+        self.piet.fill(rect.to_rounded_rect(radius), color);
+    }
+
+    pub fn draw_line(&mut self, from: impl Into<Point>, to: impl Into<Point>, color: &Color, width: f64) {
+        self.piet.stroke(Line::new(from, to), color, width);
+    }
+
+    pub fn fill_circle(&mut self, center: impl Into<Point>, radius: f64, color: &Color) {
+        self.piet.fill(Circle::new(center, radius), color);
+    }
+
+    /// Draw text with its top-left corner at `pos`, in the node's font and color. Returns the size of the text.
+    pub fn draw_text(&mut self, text: &str, pos: impl Into<Point>) -> kurbo::Size {
+        let layout = text_layout(self.piet.text(), text, self.style, None);
+        self.piet.draw_text(&layout, pos);
+        layout.size()
+    }
+}
+
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum PointerButton {
     None,
@@ -243,6 +289,42 @@ pub struct PointerEvent {
     pub focus: bool,
 }
 
+#[cfg(test)]
+impl RawPointerEvent {
+    // A pointer event at a position in the window, with no buttons pressed
+    pub(crate) fn at(window_pos_x: f64, window_pos_y: f64) -> Self {
+        Self {
+            window_pos_x,
+            window_pos_y,
+            wheel_x: 0.0,
+            wheel_y: 0.0,
+            button: PointerButton::None,
+            buttons: PointerButtons::new(),
+            mods: Modifiers::empty(),
+            count: 0,
+            focus: false,
+        }
+    }
+
+    // The same event, for pressing or releasing `button`
+    pub(crate) fn press(self, button: PointerButton) -> Self {
+        Self {
+            button,
+            buttons: self.buttons.with(button),
+            count: 1,
+            ..self
+        }
+    }
+
+    pub(crate) fn release(self, button: PointerButton) -> Self {
+        Self {
+            button,
+            buttons: self.buttons.without(button),
+            ..self
+        }
+    }
+}
+
 impl From<RawPointerEvent> for PointerEvent {
     fn from(event: RawPointerEvent) -> Self {
         PointerEvent {
@@ -268,18 +350,55 @@ pub enum EventInfo {
     Keyboard(KeyEvent),
 }
 
-pub struct EventCtx<S, H> {
+pub struct EventCtx<S: 'static, H: 'static> {
     pub info: EventInfo,
     pub platform_handle: H,
     pub resource_loader: ResourceLoader,
     pub focus: Option<Key>,
+    pub(crate) capture: Option<Key>,
     pub style: Style,
     pub(crate) layout: Layout,
     pub(crate) anim_tasks: Rc<RefCell<Vec<Box<dyn AnimCallback<S>>>>>,
+    pub(crate) key_rects: Rc<HashMap<Key, Rect>>,
     pub(crate) change: bool,
+    pub(crate) stopped: bool,
+    pub(crate) quit: bool,
+    pub(crate) broadcast: Phase,
+    pub(crate) new_windows: Vec<WindowDesc<S, H>>,
+    pub(crate) commands: Vec<CommandId>,
+    pub(crate) file_dialogs: Vec<FileDialog<S>>,
 }
 
 impl<S, H> EventCtx<S, H> {
+    pub(crate) fn new(
+        info: EventInfo,
+        platform_handle: H,
+        resource_loader: ResourceLoader,
+        focus: Option<Key>,
+        capture: Option<Key>,
+        anim_tasks: Rc<RefCell<Vec<Box<dyn AnimCallback<S>>>>>,
+        key_rects: Rc<HashMap<Key, Rect>>,
+    ) -> Self {
+        Self {
+            info,
+            platform_handle,
+            resource_loader,
+            focus,
+            capture,
+            style: Style::default(),
+            layout: Layout::default(),
+            anim_tasks,
+            key_rects,
+            change: false,
+            stopped: false,
+            quit: false,
+            broadcast: Phase::Idle,
+            new_windows: Vec::new(),
+            commands: Vec::new(),
+            file_dialogs: Vec::new(),
+        }
+    }
+
     #[inline]
     pub fn blur(&mut self) {
         self.focus = None;
@@ -290,16 +409,89 @@ impl<S, H> EventCtx<S, H> {
         self.focus = Some(key);
     }
 
+    /// Send the pointer's move and up events to the node with `key` until the button is released, even if the pointer
+    /// leaves it, such as to keep dragging something that the pointer has moved past. Call it from a `PointerDown` callback.
+    #[inline]
+    pub fn capture_pointer(&mut self, key: Key) {
+        self.capture = Some(key);
+    }
+
+    /// End a capture early, so pointer events go to the nodes under the pointer again.
+    #[inline]
+    pub fn release_pointer(&mut self) {
+        self.capture = None;
+    }
+
     #[inline]
     pub fn start_animation(&mut self, callback: impl Fn(&mut S, Duration) -> (Phase, ShouldStop) + 'static) {
         self.anim_tasks.borrow_mut().push(Box::new(callback));
     }
 
+    /// Ask the app to quit, closing all of its windows. The app's close hook runs once first, and can still cancel it.
+    #[inline]
+    pub fn quit(&mut self) {
+        self.quit = true;
+    }
+
+    /// Queue a redraw in the app's other windows, after changing state that they also display.
+    /// All windows share the same state and run on the main thread, so the change is visible to them right away.
+    #[inline]
+    pub fn broadcast(&mut self, phase: Phase) {
+        self.broadcast.update(phase);
+    }
+
+    /// Open a new window after the event has been handled. The new window shares the app's state.
+    #[inline]
+    pub fn open_window(&mut self, desc: WindowDesc<S, H>) {
+        self.new_windows.push(desc);
+    }
+
+    /// Run a command from the app's `Commands` after the event has been handled. Disabled and unknown commands are ignored.
+    #[inline]
+    pub fn dispatch(&mut self, id: CommandId) {
+        self.commands.push(id);
+    }
+
+    /// Show a dialog for choosing files to open, after the event has been handled. Filters, a starting directory,
+    /// and multiple selection are set with `options`, such as `FileDialogOptions::new().allowed_types(...)`.
+    ///
+    /// The dialog doesn't block, so the result arrives later: `callback` runs with the chosen paths once the user
+    /// closes the dialog, or with no paths if it was cancelled, and the window updates with the phase it returns.
+    #[inline]
+    pub fn open_file_dialog(&mut self, options: FileDialogOptions, callback: impl FnOnce(&mut S, Vec<PathBuf>) -> Phase + 'static) {
+        self.file_dialogs.push(FileDialog::new(FileDialogKind::Open, options, callback));
+    }
+
+    /// Show a dialog for choosing where to save a file, after the event has been handled. The default file name and
+    /// directory are set with `options`. Like `open_file_dialog()`, `callback` runs later with the chosen path, if any.
+    #[inline]
+    pub fn save_file_dialog(&mut self, options: FileDialogOptions, callback: impl FnOnce(&mut S, Vec<PathBuf>) -> Phase + 'static) {
+        self.file_dialogs.push(FileDialog::new(FileDialogKind::Save, options, callback));
+    }
+
+    /// Open a URL in the default browser, such as from a link or an "open website" button. See `open::open_url()`.
+    #[inline]
+    pub fn open_url(&mut self, url: &str) -> Result<(), std::io::Error> {
+        crate::open::open_url(url)
+    }
+
+    /// Open a file or folder with its default app. See `open::open_path()`.
+    #[inline]
+    pub fn open_path(&mut self, path: impl AsRef<std::path::Path>) -> Result<(), std::io::Error> {
+        crate::open::open_path(path)
+    }
+
     #[inline]
     pub fn emit_change(&mut self) {
         self.change = true;
     }
 
+    /// Stop the event from being sent to ancestors of this node.
+    #[inline]
+    pub fn stop_propagation(&mut self) {
+        self.stopped = true;
+    }
+
     #[inline]
     pub fn width(&self) -> f64 {
         self.layout.size.width as f64
@@ -310,6 +502,13 @@ impl<S, H> EventCtx<S, H> {
         self.layout.size.height as f64
     }
 
+    /// The border box of the node with `key` after the last layout, relative to this node, like the position of a pointer event.
+    /// This can be used to hit-test children, such as to find the items under a selection rectangle.
+    pub fn node_rect(&self, key: Key) -> Option<Rect> {
+        let rect = self.key_rects.get(&key)?;
+        Some(*rect - kurbo::Vec2::new(self.layout.position.x as f64, self.layout.position.y as f64))
+    }
+
     #[inline]
     pub fn pointer(&self) -> Option<&PointerEvent> {
         if let EventInfo::Pointer(event) = &self.info {
@@ -345,8 +544,97 @@ impl<F, S, H> EventCallback<S, H> for F where F: 'static + Fn(&mut S, &mut Event
 pub trait LayoutCallback<S>: 'static + Fn(&S, Size) {}
 impl<F, S> LayoutCallback<S> for F where F: 'static + Fn(&S, Size) {}
 
+/// `Fn(&mut S) -> Phase`
+pub trait ShortcutCallback<S>: 'static + Fn(&mut S) -> Phase {}
+impl<F, S> ShortcutCallback<S> for F where F: 'static + Fn(&mut S) -> Phase {}
+
 /// `Fn(&S, &mut Style)`
 pub trait StyleCallback<S>: 'static + Fn(&S, &mut Style) {}
 impl<F, S> StyleCallback<S> for F where F: 'static + Fn(&S, &mut Style) {}
 
+/// `Fn(&mut S, T) -> Phase`
+pub trait WindowCallback<S, T>: 'static + Fn(&mut S, T) -> Phase {}
+impl<F, S, T> WindowCallback<S, T> for F where F: 'static + Fn(&mut S, T) -> Phase {}
+
 pub type ViewCallback<S, H> = fn(&S) -> View<S, H>;
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use druid_shell::piet::{Device, ImageFormat};
+
+    use super::*;
+    use crate::text::measure_text;
+
+    #[derive(Default)]
+    struct State {
+        sizes: Cell<Option<(kurbo::Size, kurbo::Size)>>,
+    }
+
+    fn view(_: &State) -> View<State, ()> {
+        ui!([
+            .inline_style("font-size: 20px")
+            .on_draw(false, |s: &State, ctx: &mut DrawCtx| {
+                let color = ctx.style.color.clone();
+                ctx.fill_rect(Rect::new(0.0, 0.0, 10.0, 10.0), &color);
+                ctx.stroke_rect(Rect::new(0.0, 0.0, 10.0, 10.0), &color, 1.0);
+                ctx.fill_rounded_rect(Rect::new(0.0, 0.0, 10.0, 10.0), 2.0, &color);
+                ctx.draw_line((0.0, 0.0), (10.0, 10.0), &color, 1.0);
+                ctx.fill_circle((5.0, 5.0), 5.0, &color);
+
+                let drawn = ctx.draw_text("Hello", (0.0, 20.0));
+                let measured = measure_text(ctx.piet.text(), "Hello", ctx.style, None);
+                s.sizes.set(Some((drawn, measured)));
+            })
+        ])
+    }
+
+    #[test]
+    fn draw_text_uses_the_node_style() {
+        let state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(100, 100, 1.0).unwrap();
+        let mut piet = target.render_context();
+        viewport.draw(&state, Some(&mut piet)).unwrap();
+        piet.finish().unwrap();
+
+        let (drawn, measured) = state.sizes.get().unwrap();
+        assert_eq!(drawn, measured);
+    }
+
+    #[test]
+    fn shape_helpers_draw_in_the_given_color() {
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(100, 20, 1.0).unwrap();
+        let mut piet = target.render_context();
+        let style = Style::default();
+        let mut ctx = DrawCtx {
+            piet: &mut piet,
+            style: &style,
+            width: 100.0,
+            height: 20.0,
+            must_draw: true,
+        };
+        ctx.fill_rect(Rect::new(0.0, 0.0, 10.0, 10.0), &Color::rgb8(255, 0, 0));
+        ctx.stroke_rect(Rect::new(20.0, 0.0, 30.0, 10.0), &Color::rgb8(0, 255, 0), 2.0);
+        ctx.fill_rounded_rect(Rect::new(40.0, 0.0, 50.0, 10.0), 2.0, &Color::rgb8(0, 0, 255));
+        ctx.draw_line((60.0, 5.0), (70.0, 5.0), &Color::rgb8(255, 255, 0), 2.0);
+        ctx.fill_circle((85.0, 5.0), 5.0, &Color::rgb8(0, 255, 255));
+        piet.finish().unwrap();
+        drop(piet);
+
+        let pixels = target.to_image_buf(ImageFormat::RgbaPremul).unwrap().raw_pixels().to_vec();
+        let pixel = |x: usize, y: usize| &pixels[(y * 100 + x) * 4..(y * 100 + x) * 4 + 4];
+        assert_eq!(pixel(5, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(20, 5), [0, 255, 0, 255]);
+        assert_eq!(pixel(25, 5), [0, 0, 0, 0]);
+        assert_eq!(pixel(45, 5), [0, 0, 255, 255]);
+        assert!(pixel(40, 0)[3] < 255);
+        assert_eq!(pixel(65, 5), [255, 255, 0, 255]);
+        assert_eq!(pixel(85, 5), [0, 255, 255, 255]);
+        assert_eq!(pixel(80, 0), [0, 0, 0, 0]);
+    }
+}
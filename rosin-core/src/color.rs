@@ -0,0 +1,191 @@
+#![forbid(unsafe_code)]
+
+use druid_shell::piet::Color;
+
+/// Helpers for deriving theme colors. Lightness and mixing are done in the OkLab color space,
+/// so steps look even across hues. Use `Color::with_alpha` to change transparency.
+pub trait ColorExt: Sized {
+    /// Raise the OkLab lightness by `amount`, from 0.0 to 1.0.
+    fn lighten(&self, amount: f64) -> Self;
+
+    /// Lower the OkLab lightness by `amount`, from 0.0 to 1.0.
+    fn darken(&self, amount: f64) -> Self;
+
+    /// Interpolate towards `other`, where `t` is 0.0 for `self` and 1.0 for `other`.
+    fn mix(&self, other: &Self, t: f64) -> Self;
+
+    /// Parse a `#rgb`, `#rgba`, `#rrggbb`, or `#rrggbbaa` string. The `#` is optional.
+    fn from_hex(hex: &str) -> Option<Self>;
+
+    /// Format as `#rrggbb`, or `#rrggbbaa` if the color isn't opaque.
+    fn to_hex(&self) -> String;
+}
+
+impl ColorExt for Color {
+    fn lighten(&self, amount: f64) -> Self {
+        let [l, a, b, alpha] = to_oklab(self);
+        from_oklab([(l + amount).clamp(0.0, 1.0), a, b, alpha])
+    }
+
+    fn darken(&self, amount: f64) -> Self {
+        self.lighten(-amount)
+    }
+
+    fn mix(&self, other: &Self, t: f64) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mut result = to_oklab(self);
+        for (value, end) in result.iter_mut().zip(to_oklab(other)) {
+            *value += (end - *value) * t;
+        }
+        from_oklab(result)
+    }
+
+    fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !hex.is_ascii() {
+            return None;
+        }
+
+        let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|d| d * 17);
+        let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+        match hex.len() {
+            3 => Some(Color::rgba8(digit(0)?, digit(1)?, digit(2)?, 255)),
+            4 => Some(Color::rgba8(digit(0)?, digit(1)?, digit(2)?, digit(3)?)),
+            6 => Some(Color::rgba8(pair(0)?, pair(2)?, pair(4)?, 255)),
+            8 => Some(Color::rgba8(pair(0)?, pair(2)?, pair(4)?, pair(6)?)),
+            _ => None,
+        }
+    }
+
+    fn to_hex(&self) -> String {
+        let (r, g, b, a) = self.as_rgba8();
+        if a == 255 {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+        }
+    }
+}
+
+// Refer to https://bottosson.github.io/posts/oklab/
+pub(crate) fn to_oklab(color: &Color) -> [f64; 4] {
+    let linear = |c: f64| {
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+
+    let (r, g, b, alpha) = color.as_rgba();
+    let (r, g, b) = (linear(r), linear(g), linear(b));
+
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        alpha,
+    ]
+}
+
+pub(crate) fn from_oklab([l, a, b, alpha]: [f64; 4]) -> Color {
+    let gamma = |c: f64| {
+        let c = if c <= 0.0031308 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        };
+        c.clamp(0.0, 1.0)
+    };
+
+    let l_ = (l + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m_ = (l - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s_ = (l - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+
+    Color::rgba(
+        gamma(4.0767416621 * l_ - 3.3077115913 * m_ + 0.2309699292 * s_),
+        gamma(-1.2684380046 * l_ + 2.6097574011 * m_ - 0.3413193965 * s_),
+        gamma(-0.0041960863 * l_ - 0.7034186147 * m_ + 1.7076147010 * s_),
+        alpha.clamp(0.0, 1.0),
+    )
+}
+
+// OkLCh is OkLab in polar form, with hue in degrees
+pub(crate) fn to_oklch(color: &Color) -> [f64; 4] {
+    let [l, a, b, alpha] = to_oklab(color);
+    [l, a.hypot(b), b.atan2(a).to_degrees().rem_euclid(360.0), alpha]
+}
+
+pub(crate) fn from_oklch([l, c, h, alpha]: [f64; 4]) -> Color {
+    let h = h.to_radians();
+    from_oklab([l, c * h.cos(), c * h.sin(), alpha])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_round_trip() {
+        assert_eq!(Color::from_hex("#fff").unwrap().as_rgba8(), (255, 255, 255, 255));
+        assert_eq!(Color::from_hex("0f08").unwrap().as_rgba8(), (0, 255, 0, 136));
+        assert_eq!(Color::from_hex("#123456").unwrap().as_rgba8(), (0x12, 0x34, 0x56, 255));
+        assert_eq!(Color::from_hex("#12345678").unwrap().as_rgba8(), (0x12, 0x34, 0x56, 0x78));
+
+        assert_eq!(Color::rgba8(0x12, 0x34, 0x56, 255).to_hex(), "#123456");
+        assert_eq!(Color::rgba8(0xab, 0xcd, 0xef, 0x80).to_hex(), "#abcdef80");
+    }
+
+    #[test]
+    fn invalid_hex() {
+        for hex in ["", "#", "#12", "#12345", "#1234567", "#ggg", "#-12", "#ééé"] {
+            assert!(Color::from_hex(hex).is_none(), "{:?} parsed", hex);
+        }
+    }
+
+    #[test]
+    fn oklab_round_trip() {
+        for (r, g, b) in [(0, 0, 0), (255, 255, 255), (255, 0, 0), (18, 52, 86), (200, 150, 30)] {
+            let color = Color::rgba8(r, g, b, 128);
+            assert_eq!(from_oklab(to_oklab(&color)).as_rgba8(), (r, g, b, 128));
+            assert_eq!(from_oklch(to_oklch(&color)).as_rgba8(), (r, g, b, 128));
+        }
+
+        // White has a lightness of 1 and no chroma
+        let [l, a, b, _] = to_oklab(&Color::WHITE);
+        assert!((l - 1.0).abs() < 1e-6 && a.abs() < 1e-6 && b.abs() < 1e-6);
+    }
+
+    #[test]
+    fn lighten_and_darken() {
+        let gray = Color::grey8(128);
+        let lighter = gray.lighten(0.1);
+        let darker = gray.darken(0.1);
+        assert!((to_oklab(&lighter)[0] - to_oklab(&gray)[0] - 0.1).abs() < 0.01);
+        assert!((to_oklab(&gray)[0] - to_oklab(&darker)[0] - 0.1).abs() < 0.01);
+
+        // Lightness is clamped, and alpha is kept
+        assert_eq!(Color::BLACK.lighten(2.0).as_rgba8(), (255, 255, 255, 255));
+        assert_eq!(Color::WHITE.darken(2.0).as_rgba8(), (0, 0, 0, 255));
+        assert_eq!(Color::rgba8(10, 20, 30, 40).lighten(0.2).as_rgba8().3, 40);
+    }
+
+    #[test]
+    fn mix_between_colors() {
+        let red = Color::rgb8(255, 0, 0);
+        let blue = Color::rgba8(0, 0, 255, 0);
+        assert_eq!(red.mix(&blue, 0.0).as_rgba8(), red.as_rgba8());
+        assert_eq!(red.mix(&blue, 1.0).as_rgba8(), blue.as_rgba8());
+        assert_eq!(red.mix(&blue, 5.0).as_rgba8(), blue.as_rgba8());
+        assert_eq!(red.mix(&blue, 0.5).as_rgba8().3, 128);
+
+        // The midpoint is halfway in OkLab lightness
+        let middle = Color::BLACK.mix(&Color::WHITE, 0.5);
+        assert!((to_oklab(&middle)[0] - 0.5).abs() < 0.01);
+    }
+}
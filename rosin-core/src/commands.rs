@@ -0,0 +1,223 @@
+#![forbid(unsafe_code)]
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::prelude::*;
+
+/// Identifies a command, like `CommandId("file.save")`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CommandId(pub &'static str);
+
+/// An action that menus, toolbars, shortcuts, and command palettes can all refer to by its id.
+pub struct Command<S> {
+    pub id: CommandId,
+    pub title: Arc<str>,
+    pub shortcut: Option<(Modifiers, KbKey)>,
+    enabled: Option<Predicate<S>>,
+    handler: Box<dyn Fn(&mut S) -> Phase>,
+}
+
+// Decides from the state whether a command can run
+type Predicate<S> = Box<dyn Fn(&S) -> bool>;
+
+impl<S> fmt::Debug for Command<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Command")
+            .field("id", &self.id)
+            .field("title", &self.title)
+            .field("shortcut", &self.shortcut)
+            .finish()
+    }
+}
+
+impl<S> Command<S> {
+    pub fn new(id: CommandId, title: impl Into<Arc<str>>, handler: impl Fn(&mut S) -> Phase + 'static) -> Self {
+        Self {
+            id,
+            title: title.into(),
+            shortcut: None,
+            enabled: None,
+            handler: Box::new(handler),
+        }
+    }
+
+    /// Run the command with a keyboard shortcut. Like `Viewport::add_shortcut()`, shortcuts without Ctrl, Alt, or Meta
+    /// only fire when no node has focus.
+    pub fn with_shortcut(mut self, modifiers: Modifiers, key: KbKey) -> Self {
+        self.shortcut = Some((modifiers, key));
+        self
+    }
+
+    /// Only allow the command to run when `predicate` returns `true`, like disabling Save when there are no changes.
+    pub fn with_enabled(mut self, predicate: impl Fn(&S) -> bool + 'static) -> Self {
+        self.enabled = Some(Box::new(predicate));
+        self
+    }
+
+    pub fn is_enabled(&self, state: &S) -> bool {
+        self.enabled.as_ref().map_or(true, |enabled| enabled(state))
+    }
+
+    /// Run the command if it's enabled.
+    pub fn run(&self, state: &mut S) -> Option<Phase> {
+        if self.is_enabled(state) {
+            Some((self.handler)(state))
+        } else {
+            None
+        }
+    }
+}
+
+/// The commands that an app provides. Callbacks run them with `EventCtx::dispatch()`.
+pub struct Commands<S> {
+    commands: Vec<Command<S>>,
+}
+
+impl<S> Default for Commands<S> {
+    fn default() -> Self {
+        Self { commands: Vec::new() }
+    }
+}
+
+impl<S> fmt::Debug for Commands<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.commands).finish()
+    }
+}
+
+impl<S> Commands<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a command, replacing any that has the same id.
+    pub fn add_command(mut self, command: Command<S>) -> Self {
+        self.commands.retain(|c| c.id != command.id);
+        self.commands.push(command);
+        self
+    }
+
+    pub fn get(&self, id: CommandId) -> Option<&Command<S>> {
+        self.commands.iter().find(|c| c.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Command<S>> {
+        self.commands.iter()
+    }
+
+    /// Run a command if it exists and is enabled.
+    pub fn run(&self, id: CommandId, state: &mut S) -> Option<Phase> {
+        self.get(id)?.run(state)
+    }
+
+    /// Find the command with a shortcut. Character keys match regardless of case.
+    pub fn find_shortcut(&self, modifiers: Modifiers, key: &KbKey) -> Option<&Command<S>> {
+        self.commands
+            .iter()
+            .find(|c| matches!(&c.shortcut, Some((m, k)) if *m == modifiers && same_key(k, key)))
+    }
+}
+
+// Holding Shift changes the case of the reported character, so Ctrl+Shift+K arrives as "K" even if the shortcut says "k"
+pub(crate) fn same_key(a: &KbKey, b: &KbKey) -> bool {
+    match (a, b) {
+        (KbKey::Character(a), KbKey::Character(b)) => a.to_lowercase() == b.to_lowercase(),
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use druid_shell::{KeyEvent, KeyState};
+
+    use super::*;
+    use crate::callbacks::{PointerButton, RawPointerEvent};
+
+    const COUNT: CommandId = CommandId("test.count");
+    const LOCKED: CommandId = CommandId("test.locked");
+
+    #[derive(Default)]
+    struct State {
+        count: u32,
+        locked: bool,
+    }
+
+    fn commands() -> Commands<State> {
+        Commands::new()
+            .add_command(
+                Command::new(COUNT, "Count", |s: &mut State| {
+                    s.count += 1;
+                    Phase::Draw
+                })
+                .with_shortcut(Modifiers::CONTROL | Modifiers::SHIFT, KbKey::Character("k".into()))
+                .with_enabled(|s: &State| !s.locked),
+            )
+            .add_command(Command::new(LOCKED, "Lock", |s: &mut State| {
+                s.locked = true;
+                Phase::Idle
+            }))
+    }
+
+    fn view(_: &State) -> View<State, ()> {
+        View::default().event(On::PointerDown, |_, ctx| {
+            ctx.dispatch(COUNT);
+            ctx.dispatch(CommandId("test.unknown"));
+            Some(Phase::Idle)
+        })
+    }
+
+    #[test]
+    fn callbacks_dispatch_enabled_commands() {
+        let mut state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.set_commands(Rc::new(commands()));
+        viewport.draw(&state, None).unwrap();
+
+        let click = RawPointerEvent::at(5.0, 5.0).press(PointerButton::Left);
+        viewport.pointer_down(&mut state, click);
+        assert_eq!(state.count, 1);
+
+        assert_eq!(commands().run(LOCKED, &mut state), Some(Phase::Idle));
+        viewport.pointer_down(&mut state, click);
+        assert_eq!(state.count, 1);
+    }
+
+    #[test]
+    fn shortcuts_run_their_commands() {
+        let mut state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.set_commands(Rc::new(commands()));
+        viewport.draw(&state, None).unwrap();
+
+        // Holding Shift reports an uppercase character
+        let key = KeyEvent {
+            state: KeyState::Down,
+            key: KbKey::Character("K".into()),
+            mods: (Modifiers::CONTROL | Modifiers::SHIFT).into(),
+            ..Default::default()
+        };
+        assert!(viewport.key_event(&mut state, key.clone()));
+        assert_eq!(state.count, 1);
+
+        // Disabled commands let the key through
+        state.locked = true;
+        assert!(!viewport.key_event(&mut state, key));
+        assert_eq!(state.count, 1);
+    }
+
+    #[test]
+    fn adding_replaces_commands_with_the_same_id() {
+        let commands = commands().add_command(Command::new(COUNT, "Count twice", |s: &mut State| {
+            s.count += 2;
+            Phase::Draw
+        }));
+        assert_eq!(commands.iter().count(), 2);
+        assert_eq!(&*commands.get(COUNT).unwrap().title, "Count twice");
+        assert!(commands
+            .find_shortcut(Modifiers::CONTROL | Modifiers::SHIFT, &KbKey::Character("k".into()))
+            .is_none());
+    }
+}
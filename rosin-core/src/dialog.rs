@@ -0,0 +1,103 @@
+#![forbid(unsafe_code)]
+
+use std::fmt;
+use std::path::PathBuf;
+
+use druid_shell::FileDialogOptions;
+
+use crate::prelude::*;
+
+/// Whether a file dialog picks files to open or a place to save.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileDialogKind {
+    Open,
+    Save,
+}
+
+/// A file dialog that a callback asked for with `EventCtx::open_file_dialog()` or `EventCtx::save_file_dialog()`.
+/// The platform layer shows it, and calls `finish()` when the user is done with it.
+pub struct FileDialog<S> {
+    pub kind: FileDialogKind,
+    pub options: FileDialogOptions,
+    callback: DialogCallback<S>,
+}
+
+// Runs once with the chosen paths
+type DialogCallback<S> = Box<dyn FnOnce(&mut S, Vec<PathBuf>) -> Phase>;
+
+impl<S> fmt::Debug for FileDialog<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileDialog")
+            .field("kind", &self.kind)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+impl<S> FileDialog<S> {
+    pub(crate) fn new(
+        kind: FileDialogKind,
+        options: FileDialogOptions,
+        callback: impl FnOnce(&mut S, Vec<PathBuf>) -> Phase + 'static,
+    ) -> Self {
+        Self {
+            kind,
+            options,
+            callback: Box::new(callback),
+        }
+    }
+
+    /// Run the callback with the chosen paths, which are empty if the dialog was cancelled.
+    pub fn finish(self, state: &mut S, paths: Vec<PathBuf>) -> Phase {
+        (self.callback)(state, paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callbacks::{PointerButton, RawPointerEvent};
+
+    #[derive(Default)]
+    struct State {
+        opened: Vec<PathBuf>,
+        saved: Option<PathBuf>,
+    }
+
+    fn view(_: &State) -> View<State, ()> {
+        View::default().event(On::PointerDown, |_, ctx| {
+            ctx.open_file_dialog(FileDialogOptions::new().multi_selection(), |s: &mut State, paths| {
+                s.opened = paths;
+                Phase::Build
+            });
+            ctx.save_file_dialog(FileDialogOptions::new(), |s: &mut State, paths| {
+                s.saved = paths.into_iter().next();
+                Phase::Draw
+            });
+            Some(Phase::Idle)
+        })
+    }
+
+    #[test]
+    fn dialogs_are_queued_until_the_platform_finishes_them() {
+        let mut state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+        viewport.pointer_down(&mut state, RawPointerEvent::at(5.0, 5.0).press(PointerButton::Left));
+
+        let mut dialogs = viewport.take_file_dialogs();
+        assert!(viewport.take_file_dialogs().is_empty());
+        let kinds: Vec<_> = dialogs.iter().map(|dialog| dialog.kind).collect();
+        assert_eq!(kinds, [FileDialogKind::Open, FileDialogKind::Save]);
+
+        // A cancelled dialog runs its callback with no paths
+        let save = dialogs.pop().unwrap();
+        assert_eq!(save.finish(&mut state, Vec::new()), Phase::Draw);
+        assert_eq!(state.saved, None);
+
+        let open = dialogs.pop().unwrap();
+        let paths = vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")];
+        assert_eq!(open.finish(&mut state, paths.clone()), Phase::Build);
+        assert_eq!(state.opened, paths);
+    }
+}
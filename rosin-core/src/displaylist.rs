@@ -0,0 +1,277 @@
+#![forbid(unsafe_code)]
+
+use std::cell::{Cell, RefCell};
+
+use druid_shell::kurbo::{BezPath, Rect, Shape};
+use druid_shell::piet::{Color, RenderContext};
+
+use crate::prelude::*;
+
+#[derive(Debug)]
+struct Item {
+    path: BezPath,
+    bounds: Rect,
+    color: Color,
+    // None for fills
+    stroke_width: Option<f64>,
+}
+
+/// Drawing commands that are recorded once and replayed on every frame until they're invalidated.
+/// This is for custom drawing with lots of shapes that rarely change, like a plot with thousands of points.
+///
+/// The list owns the paths it records. They stay in memory until the list is dropped, or rebuilt after `invalidate()`.
+/// Keep the list somewhere that outlives the tree, like the app state, and share it with the draw callback.
+///
+/// Consecutive shapes with the same opaque color and stroke width are merged into a single path, so they're drawn with one call.
+/// Fills are only merged when the new shape's bounding box doesn't touch the bounds of the path so far, since overlapping
+/// parts of one path can cancel out under the nonzero fill rule. Translucent shapes are never merged, so overlaps blend
+/// the same way as when they're drawn one by one.
+#[derive(Debug, Default)]
+pub struct DisplayList {
+    items: RefCell<Vec<Item>>,
+    valid: Cell<bool>,
+    size: Cell<(f64, f64)>,
+}
+
+/// Records shapes into a `DisplayList`.
+#[derive(Debug)]
+pub struct Recorder<'a> {
+    items: &'a mut Vec<Item>,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl DisplayList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark the list as out of date, so it's recorded again the next time it's drawn.
+    pub fn invalidate(&self) {
+        self.valid.set(false);
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.valid.get()
+    }
+
+    /// The number of paths in the list, after merging.
+    pub fn len(&self) -> usize {
+        self.items.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.borrow().is_empty()
+    }
+
+    /// Draw the list. If it was invalidated, or the node changed size, `record` is called to record it again first.
+    pub fn draw(&self, ctx: &mut DrawCtx, record: impl FnOnce(&mut Recorder)) {
+        if !self.valid.get() || self.size.get() != (ctx.width, ctx.height) {
+            // Record into a new list, so `record` can still look at the old one
+            let mut items = Vec::new();
+            record(&mut Recorder {
+                items: &mut items,
+                width: ctx.width,
+                height: ctx.height,
+            });
+            self.items.replace(items);
+            self.valid.set(true);
+            self.size.set((ctx.width, ctx.height));
+        }
+
+        for item in self.items.borrow().iter() {
+            match item.stroke_width {
+                Some(width) => ctx.piet.stroke(&item.path, &item.color, width),
+                None => ctx.piet.fill(&item.path, &item.color),
+            }
+        }
+    }
+}
+
+impl<'a> Recorder<'a> {
+    pub fn fill(&mut self, shape: impl Shape, color: &Color) {
+        self.push(shape, color, None);
+    }
+
+    pub fn stroke(&mut self, shape: impl Shape, color: &Color, width: f64) {
+        self.push(shape, color, Some(width));
+    }
+
+    fn push(&mut self, shape: impl Shape, color: &Color, stroke_width: Option<f64>) {
+        let bounds = shape.bounding_box();
+        if let Some(last) = self.items.last_mut() {
+            let same = last.stroke_width == stroke_width && last.color.as_rgba_u32() == color.as_rgba_u32();
+            let opaque = color.as_rgba_u32() & 0xff == 0xff;
+            // Strokes can overlap, since stroking a path strokes each of its parts
+            let separate = stroke_width.is_some() || last.bounds.intersect(bounds).area() == 0.0;
+            if same && opaque && separate {
+                for element in shape.path_elements(0.1) {
+                    last.path.push(element);
+                }
+                last.bounds = last.bounds.union(bounds);
+                return;
+            }
+        }
+
+        self.items.push(Item {
+            path: shape.into_path(0.1),
+            bounds,
+            color: color.clone(),
+            stroke_width,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use druid_shell::kurbo::Circle;
+    use druid_shell::piet::{Device, Piet};
+
+    use super::*;
+
+    fn record(f: impl FnOnce(&mut Recorder)) -> Vec<Item> {
+        let mut items = Vec::new();
+        f(&mut Recorder {
+            items: &mut items,
+            width: 100.0,
+            height: 100.0,
+        });
+        items
+    }
+
+    #[test]
+    fn only_separate_opaque_fills_are_merged() {
+        let red = Color::rgb8(255, 0, 0);
+        let items = record(|r| {
+            r.fill(Rect::new(0.0, 0.0, 10.0, 10.0), &red);
+            r.fill(Rect::new(20.0, 0.0, 30.0, 10.0), &red);
+            r.fill(Circle::new((25.0, 5.0), 4.0), &red);
+        });
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].bounds, Rect::new(0.0, 0.0, 30.0, 10.0));
+
+        let translucent = red.with_alpha(0.5);
+        let items = record(|r| {
+            r.fill(Rect::new(0.0, 0.0, 10.0, 10.0), &translucent);
+            r.fill(Rect::new(20.0, 0.0, 30.0, 10.0), &translucent);
+        });
+        assert_eq!(items.len(), 2);
+    }
+
+    #[test]
+    fn opaque_strokes_are_merged_even_when_they_overlap() {
+        let blue = Color::rgb8(0, 0, 255);
+        let items = record(|r| {
+            r.stroke(Rect::new(0.0, 0.0, 10.0, 10.0), &blue, 1.0);
+            r.stroke(Rect::new(5.0, 5.0, 15.0, 15.0), &blue, 1.0);
+            r.stroke(Rect::new(5.0, 5.0, 15.0, 15.0), &blue, 2.0);
+            r.fill(Rect::new(50.0, 50.0, 60.0, 60.0), &blue);
+        });
+        assert_eq!(items.len(), 3);
+    }
+
+    #[derive(Default)]
+    struct State {
+        list: DisplayList,
+        records: Cell<u32>,
+    }
+
+    fn view(_: &State) -> View<State, ()> {
+        ui!([
+            .on_draw(false, |s: &State, ctx: &mut DrawCtx| {
+                s.list.draw(ctx, |r| {
+                    s.records.set(s.records.get() + 1);
+                    // The old list can still be read while recording
+                    let offset = s.list.len() as f64;
+                    r.fill(Rect::new(offset, 0.0, offset + 10.0, 10.0), &Color::BLACK);
+                });
+            })
+        ])
+    }
+
+    fn draw<S>(viewport: &mut Viewport<S, ()>, state: &S, draw: impl FnOnce(&mut Viewport<S, ()>, &S, &mut Piet)) {
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(200, 200, 1.0).unwrap();
+        let mut piet = target.render_context();
+        draw(viewport, state, &mut piet);
+        piet.finish().unwrap();
+    }
+
+    fn redraw(viewport: &mut Viewport<State, ()>, state: &State) {
+        draw(viewport, state, |viewport, state, piet| {
+            viewport.update_phase(Phase::Draw);
+            viewport.draw(state, Some(piet)).unwrap();
+        });
+    }
+
+    #[test]
+    fn records_again_after_invalidating_or_resizing() {
+        let state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+
+        redraw(&mut viewport, &state);
+        redraw(&mut viewport, &state);
+        assert_eq!(state.records.get(), 1);
+        assert_eq!(state.list.len(), 1);
+        assert!(state.list.is_valid());
+
+        state.list.invalidate();
+        redraw(&mut viewport, &state);
+        assert_eq!(state.records.get(), 2);
+
+        viewport.size((150.0, 100.0));
+        redraw(&mut viewport, &state);
+        assert_eq!(state.records.get(), 3);
+        redraw(&mut viewport, &state);
+        assert_eq!(state.records.get(), 3);
+    }
+
+    // Compares replaying a list of 10k shapes with drawing them directly. Run with `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn retained_and_immediate_timing() {
+        const SHAPES: usize = 10_000;
+        const FRAMES: u32 = 20;
+
+        fn shape(i: usize) -> Rect {
+            let (x, y) = ((i % 100) as f64 * 2.0, (i / 100) as f64 * 2.0);
+            Rect::new(x, y, x + 1.0, y + 1.0)
+        }
+
+        struct Bench {
+            retained: bool,
+            list: DisplayList,
+        }
+
+        fn view(_: &Bench) -> View<Bench, ()> {
+            ui!([
+                .on_draw(false, |s: &Bench, ctx: &mut DrawCtx| {
+                    if s.retained {
+                        s.list.draw(ctx, |r| (0..SHAPES).for_each(|i| r.fill(shape(i), &Color::BLACK)));
+                    } else {
+                        (0..SHAPES).for_each(|i| ctx.fill_rect(shape(i), &Color::BLACK));
+                    }
+                })
+            ])
+        }
+
+        for retained in [false, true] {
+            let state = Bench {
+                retained,
+                list: DisplayList::new(),
+            };
+            let mut viewport = Viewport::new(ResourceLoader::default(), view, (200.0, 200.0), ());
+            let start = Instant::now();
+            for _ in 0..FRAMES {
+                draw(&mut viewport, &state, |viewport, state, piet| {
+                    viewport.update_phase(Phase::Draw);
+                    viewport.draw(state, Some(piet)).unwrap();
+                });
+            }
+            let mode = if retained { "retained" } else { "immediate" };
+            eprintln!("{}: {:?} per frame", mode, start.elapsed() / FRAMES);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+#![forbid(unsafe_code)]
+
+/// Match `query` against `candidate` as a subsequence, ignoring case, like the search in a command palette.
+///
+/// Returns `None` if the characters of `query` don't all appear in order in `candidate`. Otherwise returns a score,
+/// where higher is better, and the byte offsets of the matched characters in `candidate`, for highlighting them.
+/// Matches at the start, at the start of words, and runs of consecutive characters score higher, and gaps score lower.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let mut positions = Vec::with_capacity(query.len());
+    let mut score = 0;
+    let mut candidate_chars = candidate.char_indices().enumerate();
+    let mut prev_char: Option<char> = None;
+    let mut prev_index: Option<usize> = None;
+
+    for q in query.chars() {
+        // Find the next character in the candidate that matches, remembering the one before it
+        let (index, offset, c) = loop {
+            let (index, (offset, c)) = candidate_chars.next()?;
+            if eq_ignore_case(c, q) {
+                break (index, offset, c);
+            }
+            prev_char = Some(c);
+        };
+
+        let boundary = match prev_char {
+            None => true,
+            Some(prev) => !prev.is_alphanumeric() || (prev.is_lowercase() && c.is_uppercase()),
+        };
+
+        score += match prev_index {
+            Some(prev) if prev + 1 == index => 8,
+            _ if index == 0 => 10,
+            _ if boundary => 6,
+            _ => 1,
+        };
+        if let Some(prev) = prev_index {
+            // Penalize the gap since the last match, up to a limit
+            score -= (index - prev - 1).min(5) as i64;
+        }
+
+        positions.push(offset);
+        prev_char = Some(c);
+        prev_index = Some(index);
+    }
+
+    Some((score, positions))
+}
+
+fn eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, candidate: &str) -> i64 {
+        fuzzy_match(query, candidate).unwrap().0
+    }
+
+    #[test]
+    fn prefixes_and_runs_rank_higher() {
+        assert!(score("ed", "Edit") > score("ed", "Open Editor"));
+        assert!(score("v", "Split View") > score("v", "Move"));
+        assert!(score("save", "Save All") > score("save", "Show Active View Editor"));
+        assert!(score("fo", "fileOpen") > score("fo", "profound"));
+    }
+
+    #[test]
+    fn positions_are_byte_offsets() {
+        assert_eq!(fuzzy_match("of", "Open File"), Some((12, vec![0, 5])));
+        assert_eq!(fuzzy_match("éf", "café frappé").map(|(_, p)| p), Some(vec![3, 6]));
+        assert_eq!(fuzzy_match("é", "CAFÉ").map(|(_, p)| p), Some(vec![3]));
+        assert_eq!(fuzzy_match("", "anything"), Some((0, vec![])));
+    }
+
+    #[test]
+    fn characters_must_appear_in_order() {
+        assert_eq!(fuzzy_match("fo", "Open File"), None);
+        assert_eq!(fuzzy_match("open", "Ope"), None);
+    }
+}
@@ -75,31 +75,118 @@ pub(crate) fn hit_test(layout: &[Layout], point: Point, result: &mut Vec<usize>)
     }
 }
 
-pub(crate) fn layout<S, H>(temp: &Bump, tree: &[ArrayNode<S, H>], styles: &[Style], root_size: Size, output: &mut [Layout]) {
+pub(crate) fn layout<S, H>(
+    temp: &Bump,
+    tree: &[ArrayNode<S, H>],
+    styles: &[Style],
+    root_size: Size,
+    snap_scale: Option<(f32, f32)>,
+    output: &mut [Layout],
+) {
     layout_inner(temp, tree, styles, 0, root_size, output, Point::default());
     output[0] = Layout {
         size: root_size,
         position: Point::zero(),
     };
-    // TODO - round layout to device pixels
-    //round_layout(tree, output, 0, 0.0, 0.0);
+
+    if let Some(scale) = snap_scale {
+        round_layout(output, scale);
+    }
 }
 
-fn _round_layout<S, H>(tree: &[ArrayNode<S, H>], layout: &mut [Layout], id: usize, abs_x: f32, abs_y: f32) {
-    let abs_x = abs_x + layout[id].position.x;
-    let abs_y = abs_y + layout[id].position.y;
+// Snap edges to device pixels, so borders and other hairlines stay crisp at any scale.
+// Positions are already absolute, so each edge is snapped on its own, and adjacent nodes still line up.
+fn round_layout(layout: &mut [Layout], scale: (f32, f32)) {
+    let snap_x = |x: f32| (x * scale.0).round() / scale.0;
+    let snap_y = |y: f32| (y * scale.1).round() / scale.1;
+
+    for node in layout.iter_mut() {
+        let Point { x, y } = node.position;
+        node.position.x = snap_x(x);
+        node.position.y = snap_y(y);
+        node.size.width = snap_x(x + node.size.width) - node.position.x;
+        node.size.height = snap_y(y + node.size.height) - node.position.y;
+    }
+}
+
+fn preferred_size<S, H>(tree: &[ArrayNode<S, H>], styles: &[Style], id: usize) -> (Option<f32>, Option<f32>) {
+    let style = &styles[id];
+    if style.intrinsic_width.is_none() && style.intrinsic_height.is_none() {
+        return (style.width, style.height);
+    }
 
-    layout[id].position.x = layout[id].position.x.round();
-    layout[id].position.y = layout[id].position.y.round();
+    let (min_content, max_content) = content_sizes(tree, styles, id);
+    resolve_size(style, min_content, max_content)
+}
 
-    layout[id].size.width = (abs_x + layout[id].size.width).round() - abs_x.round();
-    layout[id].size.height = (abs_y + layout[id].size.height).round() - abs_y.round();
+// Resolve any intrinsic sizing keywords against a node's content sizes
+fn resolve_size(style: &Style, min_content: Size, max_content: Size) -> (Option<f32>, Option<f32>) {
+    (
+        style
+            .intrinsic_width
+            .map(|size| {
+                size.resolve(
+                    style.font_size,
+                    style.root_font_size,
+                    style.viewport_size,
+                    min_content.width,
+                    max_content.width,
+                )
+            })
+            .or(style.width),
+        style
+            .intrinsic_height
+            .map(|size| {
+                size.resolve(
+                    style.font_size,
+                    style.root_font_size,
+                    style.viewport_size,
+                    min_content.height,
+                    max_content.height,
+                )
+            })
+            .or(style.height),
+    )
+}
+
+// Measure the min-content and max-content sizes of a node's content box.
+// NOTE - Leaf content isn't measured, so a leaf is only as big as its own styles make it
+fn content_sizes<S, H>(tree: &[ArrayNode<S, H>], styles: &[Style], id: usize) -> (Size, Size) {
+    let dir = styles[id].flex_direction;
+    let wrap = styles[id].flex_wrap != FlexWrap::NoWrap;
+    let mut min_content = Size::zero();
+    let mut max_content = Size::zero();
 
     if let Some(child_ids) = tree[id].child_ids() {
-        for id in child_ids {
-            _round_layout(tree, layout, id, abs_x, abs_y);
+        for child_id in child_ids {
+            let style = &styles[child_id];
+            if style.position == Position::Fixed {
+                continue;
+            }
+
+            let (child_min, child_max) = content_sizes(tree, styles, child_id);
+            let (width, height) = resolve_size(style, child_min, child_max);
+            let extra = (style.border() + style.padding() + style.margin()).size();
+            let outer_min = Size::new(width.unwrap_or(child_min.width), height.unwrap_or(child_min.height))
+                .clamp(style.min_size(), style.max_size())
+                + extra;
+            let outer_max = Size::new(width.unwrap_or(child_max.width), height.unwrap_or(child_max.height))
+                .clamp(style.min_size(), style.max_size())
+                + extra;
+
+            // A wrapping container can put each item on its own line
+            if wrap {
+                min_content.set_main(dir, min_content.main(dir).max(outer_min.main(dir)));
+            } else {
+                min_content.set_main(dir, min_content.main(dir) + outer_min.main(dir));
+            }
+            min_content.set_cross(dir, min_content.cross(dir).max(outer_min.cross(dir)));
+            max_content.set_main(dir, max_content.main(dir) + outer_max.main(dir));
+            max_content.set_cross(dir, max_content.cross(dir).max(outer_max.cross(dir)));
         }
     }
+
+    (min_content, max_content)
 }
 
 fn layout_inner<S, H>(
@@ -134,8 +221,10 @@ fn layout_inner<S, H>(
             let min_size = style.min_size();
             let max_size = style.max_size();
             let border_padding = style.border() + style.padding();
-            let flex_basis = style.flex_basis.unwrap_or_else(|| style.size().main(dir));
-            let hypo_inner_size = style.size().with_main(dir, flex_basis).clamp(min_size, max_size);
+            let (width, height) = preferred_size(tree, styles, id);
+            let size = Size::new(width.unwrap_or(0.0), height.unwrap_or(0.0));
+            let flex_basis = style.flex_basis.unwrap_or_else(|| size.main(dir));
+            let hypo_inner_size = size.with_main(dir, flex_basis).clamp(min_size, max_size);
             let hypo_outer_size = hypo_inner_size + border_padding.size();
 
             FlexItem {
@@ -155,7 +244,7 @@ fn layout_inner<S, H>(
                 auto_main_end: if dir.is_row() { style.margin_right } else { style.margin_bottom }.is_none(),
                 auto_cross_start: if !dir.is_row() { style.margin_left } else { style.margin_top }.is_none(),
                 auto_cross_end: if !dir.is_row() { style.margin_right } else { style.margin_bottom }.is_none(),
-                auto_cross_size: if !dir.is_row() { style.width } else { style.height }.is_none(),
+                auto_cross_size: if !dir.is_row() { width } else { height }.is_none(),
 
                 flex_basis,
                 flex_grow: style.flex_grow,
@@ -603,3 +692,113 @@ fn layout_inner<S, H>(
         flex_lines.iter_mut().for_each(layout_line);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Default)]
+    struct State {
+        sizes: RefCell<Vec<(&'static str, Size)>>,
+    }
+
+    // A node that records its size after layout
+    fn measured(name: &'static str, css: &str) -> View<State, ()> {
+        View::default()
+            .inline_style(css)
+            .on_layout(move |s: &State, size| s.sizes.borrow_mut().push((name, size)))
+    }
+
+    // Lay out a view in a 200 by 200 viewport, and return the sizes of its measured nodes
+    fn sizes(view: ViewCallback<State, ()>) -> Vec<(&'static str, Size)> {
+        let state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (200.0, 200.0), ());
+        viewport.draw(&state, None).unwrap();
+        state.sizes.take()
+    }
+
+    fn width(sizes: &[(&'static str, Size)], name: &str) -> f32 {
+        sizes.iter().find(|(n, _)| *n == name).unwrap().1.width
+    }
+
+    fn node(x: f32, y: f32, width: f32, height: f32) -> Layout {
+        Layout {
+            position: Point { x, y },
+            size: Size { width, height },
+        }
+    }
+
+    #[test]
+    fn round_layout_snaps_absolute_edges() {
+        // A parent and a child that share their left edge, and a sibling right after the child
+        let mut layout = [
+            node(0.0, 0.0, 100.0, 100.0),
+            node(10.3, 5.2, 20.4, 10.0),
+            node(10.3, 5.2, 8.1, 4.4),
+            node(18.4, 5.2, 3.0, 4.4),
+        ];
+        round_layout(&mut layout, (1.5, 2.0));
+
+        for node in &layout {
+            let (left, top) = (node.position.x * 1.5, node.position.y * 2.0);
+            let (right, bottom) = (left + node.size.width * 1.5, top + node.size.height * 2.0);
+            for edge in [left, top, right, bottom] {
+                assert!((edge - edge.round()).abs() < 1e-4, "{} isn't on a device pixel", edge);
+            }
+        }
+
+        // Nodes that shared an edge before snapping still share it
+        assert_eq!(layout[1].position.x, layout[2].position.x);
+        assert!((layout[2].position.x + layout[2].size.width - layout[3].position.x).abs() < 1e-4);
+
+        // Edges move less than a device pixel
+        assert!((layout[1].position.x - 10.3).abs() <= 0.5 / 1.5);
+        assert!((layout[1].position.x + layout[1].size.width - 30.7).abs() <= 0.5 / 1.5);
+    }
+
+    #[test]
+    fn intrinsic_widths() {
+        // Each container wraps a 30px and a 50px item, so its min-content width is 50 and its max-content width is 80
+        fn container(name: &'static str, width: &str) -> View<State, ()> {
+            let css = format!("flex-wrap: wrap; width: {}", width);
+            measured(name, &css)
+                .add_child(View::default().inline_style("width: 30px; height: 10px"))
+                .add_child(View::default().inline_style("width: 50px; height: 10px"))
+        }
+
+        fn view(_: &State) -> View<State, ()> {
+            View::default()
+                .inline_style("flex-direction: column; align-items: flex-start")
+                .add_child(container("min", "min-content"))
+                .add_child(container("max", "max-content"))
+                .add_child(container("fit", "fit-content(60px)"))
+                .add_child(container("fit-small", "fit-content(20px)"))
+                .add_child(container("fit-large", "fit-content(120px)"))
+        }
+
+        let sizes = sizes(view);
+        assert_eq!(width(&sizes, "min"), 50.0);
+        assert_eq!(width(&sizes, "max"), 80.0);
+        assert_eq!(width(&sizes, "fit"), 60.0);
+        assert_eq!(width(&sizes, "fit-small"), 50.0);
+        assert_eq!(width(&sizes, "fit-large"), 80.0);
+    }
+
+    #[test]
+    fn intrinsic_width_includes_margins_and_nested_content() {
+        fn view(_: &State) -> View<State, ()> {
+            let inner = View::default()
+                .inline_style("width: max-content; padding: 5px")
+                .add_child(View::default().inline_style("width: 20px; height: 10px; margin-left: 4px"));
+            View::default()
+                .inline_style("flex-direction: column; align-items: flex-start")
+                .add_child(measured("outer", "width: max-content").add_child(inner))
+        }
+
+        // The inner node is 24px of content plus 10px of padding
+        assert_eq!(width(&sizes(view), "outer"), 34.0);
+    }
+}
@@ -6,12 +6,23 @@ mod properties;
 
 pub mod alloc;
 pub mod callbacks;
+pub mod color;
+pub mod commands;
+pub mod dialog;
+pub mod displaylist;
+pub mod fuzzy;
 pub mod key;
+pub mod open;
+pub mod perf;
+pub mod repeat;
 pub mod resource;
+pub mod selection;
 pub mod style;
 pub mod stylesheet;
+pub mod text;
 pub mod tree;
 pub mod viewport;
+pub mod window;
 
 /// Basic set of widgets
 pub mod widgets;
@@ -20,14 +31,21 @@ pub mod widgets;
 pub mod prelude {
     pub use crate::callbacks::{
         AnimCallback, DrawCallback, DrawCtx, EventCallback, EventCtx, EventInfo, LayoutCallback, On, Phase, PointerButton, PointerButtons,
-        PointerEvent, RawPointerEvent, ShouldStop, StyleCallback, ViewCallback,
+        PointerEvent, RawPointerEvent, ShortcutCallback, ShouldStop, StyleCallback, ViewCallback, WindowCallback,
     };
+    pub use crate::color::ColorExt;
+    pub use crate::commands::{Command, CommandId, Commands};
+    pub use crate::dialog::{FileDialog, FileDialogKind};
+    pub use crate::fuzzy::fuzzy_match;
     pub use crate::key::Key;
-    pub use crate::resource::ResourceLoader;
+    pub use crate::resource::{AssetHandle, ResourceLoader, WakerToken};
+    pub use crate::selection::{ListSelection, SelectMode};
     pub use crate::style::Style;
-    pub use crate::stylesheet::Stylesheet;
+    pub use crate::stylesheet::{ColorScheme, Stylesheet};
     pub use crate::tree::View;
     pub use crate::viewport::Viewport;
+    pub use crate::window::{ViewFn, WindowDesc, WindowId};
     pub use crate::{load_css, ui};
+    pub use druid_shell::{FileDialogOptions, FileSpec, KbKey};
     pub use keyboard_types::Modifiers;
 }
@@ -0,0 +1,155 @@
+#![forbid(unsafe_code)]
+
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::process::Command;
+
+/// The URL schemes that `open_url()` will pass on to the system.
+pub const ALLOWED_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Open a URL in the default browser or mail app.
+///
+/// Only `http`, `https`, and `mailto` URLs are allowed, so a link in untrusted text can't launch other kinds of handlers.
+/// URLs with whitespace or control characters are rejected with `ErrorKind::InvalidInput`.
+/// Returns `ErrorKind::Unsupported` on platforms without a way to open URLs.
+pub fn open_url(url: &str) -> Result<(), Error> {
+    validate_url(url)?;
+    launch(url.as_ref())
+}
+
+/// Open a file or folder with its default app, like double clicking it in the file manager.
+/// The path must exist, or `ErrorKind::NotFound` is returned. Relative paths are resolved against the current directory.
+pub fn open_path(path: impl AsRef<Path>) -> Result<(), Error> {
+    // An absolute path can't be mistaken for an option by the launcher. It's not canonicalized,
+    // since that gives `\\?\` paths on Windows, which some apps can't open.
+    let path = path.as_ref();
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+    std::fs::metadata(&path)?;
+    launch(path.as_os_str())
+}
+
+/// Check that a URL is safe to hand to `open_url()`.
+pub fn validate_url(url: &str) -> Result<(), Error> {
+    let invalid = |reason: &str| Err(Error::new(ErrorKind::InvalidInput, format!("{}: {:?}", reason, url)));
+
+    if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return invalid("URL contains whitespace or control characters");
+    }
+
+    let (scheme, rest) = match url.split_once(':') {
+        Some(parts) => parts,
+        None => return invalid("URL has no scheme"),
+    };
+    let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid_scheme {
+        return invalid("URL has a malformed scheme");
+    }
+
+    let scheme = scheme.to_ascii_lowercase();
+    if !ALLOWED_SCHEMES.contains(&scheme.as_str()) {
+        return invalid("URL scheme isn't allowed");
+    }
+
+    if scheme == "http" || scheme == "https" {
+        let host = rest.strip_prefix("//").and_then(|rest| rest.split(['/', '?', '#']).next());
+        if host.map_or(true, str::is_empty) {
+            return invalid("URL has no host");
+        }
+    } else if rest.is_empty() {
+        return invalid("URL has no address");
+    }
+
+    Ok(())
+}
+
+// Hand the target to the platform's launcher. It's passed as a single argument, and never through a shell.
+fn launch(target: &std::ffi::OsStr) -> Result<(), Error> {
+    let mut command = if cfg!(target_os = "macos") {
+        // Goes through Launch Services, the same as NSWorkspace
+        Command::new("/usr/bin/open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else if cfg!(any(
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )) {
+        Command::new("xdg-open")
+    } else {
+        return Err(Error::new(ErrorKind::Unsupported, "opening URLs isn't supported on this platform"));
+    };
+
+    let mut child = command.arg(target).spawn()?;
+    // Reap the launcher when it exits, without blocking the UI
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(url: &str) -> bool {
+        matches!(validate_url(url), Err(error) if error.kind() == ErrorKind::InvalidInput)
+    }
+
+    #[test]
+    fn allows_web_and_mail_urls() {
+        assert!(validate_url("https://example.com").is_ok());
+        assert!(validate_url("http://example.com/path?query#fragment").is_ok());
+        assert!(validate_url("mailto:someone@example.com").is_ok());
+        assert!(validate_url("HTTPS://example.com").is_ok());
+        assert!(validate_url("MailTo:someone@example.com").is_ok());
+    }
+
+    #[test]
+    fn rejects_urls_without_a_scheme() {
+        assert!(rejected("example.com"));
+        assert!(rejected("//example.com"));
+        assert!(rejected(":example.com"));
+        assert!(rejected("1http://example.com"));
+    }
+
+    #[test]
+    fn rejects_other_schemes() {
+        assert!(rejected("javascript:alert(1)"));
+        assert!(rejected("JavaScript:alert(1)"));
+        assert!(rejected("file:///etc/passwd"));
+        assert!(rejected("FILE:///etc/passwd"));
+        assert!(rejected("ftp://example.com"));
+    }
+
+    #[test]
+    fn rejects_web_urls_without_a_host() {
+        assert!(rejected("http:"));
+        assert!(rejected("http:example.com"));
+        assert!(rejected("http://"));
+        assert!(rejected("https:///path"));
+        assert!(rejected("mailto:"));
+    }
+
+    #[test]
+    fn rejects_whitespace_and_control_characters() {
+        assert!(rejected("https://example.com/a b"));
+        assert!(rejected(" https://example.com"));
+        assert!(rejected("https://example.com\n"));
+        assert!(rejected("https://exa\tmple.com"));
+        assert!(rejected("https://example.com/\u{0}"));
+        assert!(rejected("https://example.com/\u{7f}"));
+    }
+
+    #[test]
+    fn missing_paths_are_not_found() {
+        let error = open_path("this file does not exist.txt").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+}
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use crate::color::*;
 use crate::properties::*;
 use crate::style::*;
 use crate::stylesheet::*;
@@ -13,14 +14,66 @@ pub struct RulesParser;
 
 impl<'i> AtRuleParser<'i> for RulesParser {
     type PreludeNoBlock = ();
-    type PreludeBlock = ();
-    type AtRule = (bool, Rule);
+    type PreludeBlock = ColorScheme;
+    type AtRule = Vec<(bool, Rule)>;
     type Error = ();
+
+    fn parse_prelude<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        parser: &mut Parser<'i, 't>,
+    ) -> Result<AtRuleType<Self::PreludeNoBlock, Self::PreludeBlock>, ParseError<'i, Self::Error>> {
+        match_ignore_ascii_case! { &name,
+            "media" => Ok(AtRuleType::WithBlock(parse_media_query(parser)?)),
+            _ => Err(parser.new_error(BasicParseErrorKind::AtRuleInvalid(name))),
+        }
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        prelude: Self::PreludeBlock,
+        _start: &ParserState,
+        parser: &mut Parser<'i, 't>,
+    ) -> Result<Self::AtRule, ParseError<'i, Self::Error>> {
+        let mut rules = Vec::new();
+
+        for (dynamic, mut rule) in RuleListParser::new_for_nested_rule(parser, RulesParser).flatten().flatten() {
+            // A nested query for the other scheme can never match
+            if rule.color_scheme.map_or(true, |scheme| scheme == prelude) {
+                rule.color_scheme = Some(prelude);
+                rules.push((dynamic, rule));
+            }
+        }
+
+        Ok(rules)
+    }
+}
+
+// Only `(prefers-color-scheme: light | dark)` is supported for now
+fn parse_media_query<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<ColorScheme, ParseError<'i, ()>> {
+    parser.expect_parenthesis_block()?;
+    parser.parse_nested_block(|parser| {
+        let feature = parser.expect_ident()?.clone();
+        if !feature.eq_ignore_ascii_case("prefers-color-scheme") {
+            return Err(parser.new_error_for_next_token());
+        }
+        parser.expect_colon()?;
+
+        let token = parser.next()?;
+        match token {
+            Token::Ident(s) => match_ignore_ascii_case! { s,
+                "light" => Ok(ColorScheme::Light),
+                "dark" => Ok(ColorScheme::Dark),
+                _ => Err(parser.new_error_for_next_token()),
+            },
+            _ => Err(parser.new_error_for_next_token()),
+        }
+    })
 }
 
 impl<'i> QualifiedRuleParser<'i> for RulesParser {
     type Prelude = (bool, u32, Vec<Selector>);
-    type QualifiedRule = (bool, Rule);
+    type QualifiedRule = Vec<(bool, Rule)>;
     type Error = ();
 
     fn parse_prelude<'t>(&mut self, parser: &mut Parser<'i, 't>) -> Result<Self::Prelude, ParseError<'i, Self::Error>> {
@@ -61,11 +114,25 @@ impl<'i> QualifiedRuleParser<'i> for RulesParser {
                     }
 
                     if colon {
+                        // Checked, indeterminate, selected, and empty states are fixed when the tree is built, so they aren't dynamic
                         match_ignore_ascii_case! { s,
-                            "focus" => selector_list.push(Selector::Focus),
-                            "hover" => selector_list.push(Selector::Hover),
+                            "checked" => selector_list.push(Selector::Checked),
+                            "indeterminate" => selector_list.push(Selector::Indeterminate),
+                            "selected" => selector_list.push(Selector::Selected),
+                            "empty" => selector_list.push(Selector::Empty),
+                            "focus" => {
+                                selector_list.push(Selector::Focus);
+                                dynamic = true;
+                            },
+                            "hover" => {
+                                selector_list.push(Selector::Hover);
+                                dynamic = true;
+                            },
                             _ => return Err(parser.new_error_for_next_token()),
                         }
+                    } else if !selector_list.is_empty() && !direct && !whitespace {
+                        // Later parts of a compound selector apply to the same node, like a one-argument :is()
+                        selector_list.push(Selector::Is(vec![Selector::Class(s.to_string())]));
                     } else {
                         selector_list.push(Selector::Class(s.to_string()));
                     }
@@ -81,7 +148,11 @@ impl<'i> QualifiedRuleParser<'i> for RulesParser {
                         selector_list.push(Selector::Children);
                     }
 
-                    selector_list.push(Selector::Id(s.to_string()));
+                    if !selector_list.is_empty() && !direct && !whitespace {
+                        selector_list.push(Selector::Is(vec![Selector::Id(s.to_string())]));
+                    } else {
+                        selector_list.push(Selector::Id(s.to_string()));
+                    }
                     specificity += 100;
 
                     whitespace = false;
@@ -94,7 +165,28 @@ impl<'i> QualifiedRuleParser<'i> for RulesParser {
                 }
                 Token::Colon => {
                     colon = true;
-                    dynamic = true;
+                }
+                Token::Function(s) if colon => {
+                    let negate = match_ignore_ascii_case! { s,
+                        "not" => true,
+                        "is" => false,
+                        _ => return Err(parser.new_error_for_next_token()),
+                    };
+
+                    // Like :hover, these apply to the same node as the rest of the compound selector
+                    let (selectors, max_specificity, has_dynamic) = parser.parse_nested_block(parse_simple_selectors)?;
+                    selector_list.push(if negate {
+                        Selector::Not(selectors)
+                    } else {
+                        Selector::Is(selectors)
+                    });
+
+                    specificity += max_specificity;
+                    dynamic = dynamic || has_dynamic;
+
+                    whitespace = false;
+                    direct = false;
+                    colon = false;
                 }
                 _ => return Err(parser.new_error_for_next_token()),
             }
@@ -106,24 +198,171 @@ impl<'i> QualifiedRuleParser<'i> for RulesParser {
     fn parse_block<'t>(
         &mut self,
         prelude: Self::Prelude,
-        _start: &ParserState,
+        start: &ParserState,
         parser: &mut Parser<'i, 't>,
     ) -> Result<Self::QualifiedRule, ParseError<'i, Self::Error>> {
         let mut property_list = Vec::new();
+        let mut important_list = Vec::new();
+        let mut nested_rules = Vec::new();
 
-        for mut property in DeclarationListParser::new(parser, PropertiesParser).flatten() {
-            property_list.append(&mut property);
+        while !parser.is_exhausted() {
+            // A `{` before the next `;` means this is a nested rule rather than a declaration
+            let parser_state = parser.state();
+            let _ = parser.parse_until_before(Delimiter::Semicolon | Delimiter::CurlyBracketBlock, |parser| {
+                while parser.next().is_ok() {}
+                Ok::<_, ParseError<'i, ()>>(())
+            });
+            let nested = matches!(parser.next(), Ok(Token::CurlyBracketBlock));
+            parser.reset(&parser_state);
+
+            if nested {
+                let nested_prelude =
+                    parser.parse_until_before(Delimiter::CurlyBracketBlock, |parser| parse_nested_prelude(parser, &prelude));
+                parser.expect_curly_bracket_block()?;
+
+                // Invalid nested rules are skipped, like invalid declarations
+                if let Ok(nested_prelude) = nested_prelude {
+                    if let Ok(mut rules) =
+                        parser.parse_nested_block(|parser| QualifiedRuleParser::parse_block(self, nested_prelude, start, parser))
+                    {
+                        nested_rules.append(&mut rules);
+                    }
+                }
+            } else if let Ok((mut properties, important)) = parser.parse_until_after(Delimiter::Semicolon, parse_declaration) {
+                if important {
+                    important_list.append(&mut properties);
+                } else {
+                    property_list.append(&mut properties);
+                }
+            }
         }
 
-        Ok((
+        let mut rules = Vec::new();
+        if !important_list.is_empty() {
+            // Important declarations are kept in their own rule, so the cascade can apply them last
+            rules.push((
+                prelude.0,
+                Rule {
+                    specificity: prelude.1,
+                    important: true,
+                    color_scheme: None,
+                    selectors: prelude.2.clone(),
+                    properties: important_list,
+                },
+            ));
+        }
+        rules.push((
             prelude.0,
             Rule {
                 specificity: prelude.1,
+                important: false,
+                color_scheme: None,
                 selectors: prelude.2,
                 properties: property_list,
             },
-        ))
+        ));
+        rules.append(&mut nested_rules);
+        Ok(rules)
+    }
+}
+
+// Parse the argument list of `:is()` or `:not()`, which only supports simple selectors for now.
+// Returns the selectors, the highest specificity among them, and whether any are dynamic.
+fn parse_simple_selectors<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<(Vec<Selector>, u32, bool), ParseError<'i, ()>> {
+    let arguments = parser.parse_comma_separated(|parser| {
+        let token = parser.next()?;
+        match token {
+            Token::Delim('*') => Ok((Selector::Wildcard, 0, false)),
+            Token::Delim('.') => Ok((Selector::Class(parser.expect_ident()?.to_string()), 10, false)),
+            Token::IDHash(s) | Token::Hash(s) => Ok((Selector::Id(s.to_string()), 100, false)),
+            Token::Colon => {
+                let name = parser.expect_ident()?.clone();
+                match_ignore_ascii_case! { &name,
+                    "checked" => Ok((Selector::Checked, 10, false)),
+                    "indeterminate" => Ok((Selector::Indeterminate, 10, false)),
+                    "selected" => Ok((Selector::Selected, 10, false)),
+                    "empty" => Ok((Selector::Empty, 10, false)),
+                    "focus" => Ok((Selector::Focus, 10, true)),
+                    "hover" => Ok((Selector::Hover, 10, true)),
+                    _ => Err(parser.new_error_for_next_token()),
+                }
+            }
+            _ => Err(parser.new_error_for_next_token()),
+        }
+    })?;
+
+    let specificity = arguments.iter().map(|argument| argument.1).max().unwrap_or(0);
+    let dynamic = arguments.iter().any(|argument| argument.2);
+    Ok((arguments.into_iter().map(|argument| argument.0).collect(), specificity, dynamic))
+}
+
+// Parse a declaration, and whether it's marked `!important`
+fn parse_declaration<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<(Vec<Property>, bool), ParseError<'i, ()>> {
+    let name = parser.expect_ident()?.clone();
+    parser.expect_colon()?;
+    let properties = parser.parse_until_before(Delimiter::Bang, |parser| PropertiesParser.parse_value(name, parser))?;
+
+    let important = !parser.is_exhausted();
+    if important {
+        parser.expect_delim('!')?;
+        parser.expect_ident_matching("important")?;
     }
+
+    Ok((properties, important))
+}
+
+// Parse a list of declarations from an inline style. Invalid declarations are skipped, and `!important` has no effect.
+pub(crate) fn parse_inline_style(text: &str) -> Vec<Property> {
+    let mut input = ParserInput::new(text);
+    let mut parser = Parser::new(&mut input);
+    let mut properties = Vec::new();
+
+    while !parser.is_exhausted() {
+        if let Ok((mut declaration, _)) = parser.parse_until_after(Delimiter::Semicolon, parse_declaration) {
+            properties.append(&mut declaration);
+        }
+    }
+
+    properties
+}
+
+// Parse the prelude of a nested rule, and join it to the parent's selectors.
+// `&` is only supported at the start. Without it, the rule selects descendants of the parent.
+// Class and id selectors right after `&` are wrapped in `:is()`, which checks the same node, so they join the parent's last compound selector.
+fn parse_nested_prelude<'i, 't>(
+    parser: &mut Parser<'i, 't>,
+    parent: &(bool, u32, Vec<Selector>),
+) -> Result<(bool, u32, Vec<Selector>), ParseError<'i, ()>> {
+    parser.skip_whitespace();
+
+    let mut descendant = true;
+    if parser.try_parse(|parser| parser.expect_delim('&')).is_ok() {
+        // `&:hover` and `&.active` apply to the parent itself, but `& .title` doesn't
+        let parser_state = parser.state();
+        descendant = matches!(parser.next_including_whitespace(), Ok(Token::WhiteSpace(_)));
+        if !descendant {
+            parser.reset(&parser_state);
+        }
+        parser.skip_whitespace();
+    }
+
+    let (dynamic, specificity, mut selectors) = QualifiedRuleParser::parse_prelude(&mut RulesParser, parser)?;
+
+    let mut result = parent.2.clone();
+    if descendant && !matches!(selectors.first(), Some(Selector::DirectChildren)) {
+        result.push(Selector::Children);
+    } else if !descendant {
+        for selector in selectors.iter_mut() {
+            match selector {
+                Selector::Id(_) | Selector::Class(_) => *selector = Selector::Is(vec![selector.clone()]),
+                Selector::Children | Selector::DirectChildren => break,
+                _ => {}
+            }
+        }
+    }
+    result.extend(selectors);
+
+    Ok((parent.0 || dynamic, parent.1 + specificity, result))
 }
 
 // ---------- Properties Parser ----------
@@ -150,31 +389,32 @@ impl<'i> DeclarationParser<'i> for PropertiesParser {
             "align-content" => parse_align_content(parser),
             "align-items" => parse_align_items(parser),
             "align-self" => parse_align_self(parser),
-            "background-color" => Ok(vec![Property::BackgroundColor(PropertyValue::Exact(cssparser::Color::parse(parser)?))]),
+            "background-color" => Ok(vec![Property::BackgroundColor(PropertyValue::Exact(parse_color(parser)?))]),
             "background-image" => parse_background_image(parser),
             "border" => parse_border(parser),
             "border-bottom" => parse_border_bottom(parser),
-            "border-bottom-color" => Ok(vec![Property::BorderBottomColor(PropertyValue::Exact(cssparser::Color::parse(parser)?))]),
+            "border-bottom-color" => Ok(vec![Property::BorderBottomColor(PropertyValue::Exact(parse_color(parser)?))]),
             "border-bottom-left-radius" => Ok(vec![Property::BorderBottomLeftRadius(parse_length(parser)?)]),
             "border-bottom-right-radius" => Ok(vec![Property::BorderBottomRightRadius(parse_length(parser)?)]),
             "border-bottom-width" => Ok(vec![Property::BorderBottomWidth(parse_length(parser)?)]),
             "border-color" => parse_border_color(parser),
             "border-left" => parse_border_left(parser),
-            "border-left-color" => Ok(vec![Property::BorderLeftColor(PropertyValue::Exact(cssparser::Color::parse(parser)?))]),
+            "border-left-color" => Ok(vec![Property::BorderLeftColor(PropertyValue::Exact(parse_color(parser)?))]),
             "border-left-width" => Ok(vec![Property::BorderLeftWidth(parse_length(parser)?)]),
             "border-radius" => parse_border_radius(parser),
             "border-right" => parse_border_right(parser),
-            "border-right-color" => Ok(vec![Property::BorderRightColor(PropertyValue::Exact(cssparser::Color::parse(parser)?))]),
+            "border-right-color" => Ok(vec![Property::BorderRightColor(PropertyValue::Exact(parse_color(parser)?))]),
             "border-right-width" => Ok(vec![Property::BorderRightWidth(parse_length(parser)?)]),
             "border-top" => parse_border_top(parser),
-            "border-top-color" => Ok(vec![Property::BorderTopColor(PropertyValue::Exact(cssparser::Color::parse(parser)?))]),
+            "border-top-color" => Ok(vec![Property::BorderTopColor(PropertyValue::Exact(parse_color(parser)?))]),
             "border-top-left-radius" => Ok(vec![Property::BorderTopLeftRadius(parse_length(parser)?)]),
             "border-top-right-radius" => Ok(vec![Property::BorderTopRightRadius(parse_length(parser)?)]),
             "border-top-width" => Ok(vec![Property::BorderTopWidth(parse_length(parser)?)]),
             "border-width" => parse_border_width(parser),
             "bottom" => Ok(vec![Property::Bottom(parse_length(parser)?)]),
             "box-shadow" => parse_box_shadow(parser),
-            "color" => Ok(vec![Property::Color(PropertyValue::Exact(cssparser::Color::parse(parser)?))]),
+            "clip-path" => parse_clip_path(parser),
+            "color" => Ok(vec![Property::Color(PropertyValue::Exact(parse_color(parser)?))]),
             "cursor" => parse_cursor(parser),
             "flex" => parse_flex(parser),
             "flex-basis" => Ok(vec![Property::FlexBasis(parse_length(parser)?)]),
@@ -187,7 +427,7 @@ impl<'i> DeclarationParser<'i> for PropertiesParser {
             "font-family" => parse_font_family(parser),
             "font-size" => Ok(vec![Property::FontSize(parse_length(parser)?)]),
             "font-weight" => Ok(vec![Property::FontWeight(parse_u32(parser)?)]),
-            "height" => Ok(vec![Property::Height(parse_length(parser)?)]),
+            "height" => parse_size(parser, Property::Height, Property::IntrinsicHeight),
             "justify-content" => parse_justify_content(parser),
             "left" => Ok(vec![Property::Left(parse_length(parser)?)]),
             "margin" => parse_margin(parser),
@@ -209,7 +449,7 @@ impl<'i> DeclarationParser<'i> for PropertiesParser {
             "position" => parse_position(parser),
             "right" => Ok(vec![Property::Right(parse_length(parser)?)]),
             "top" => Ok(vec![Property::Top(parse_length(parser)?)]),
-            "width" => Ok(vec![Property::Width(parse_length(parser)?)]),
+            "width" => parse_size(parser, Property::Width, Property::IntrinsicWidth),
             "z-index" => Ok(vec![Property::ZIndex(parse_i32(parser)?)]),
             _ => Err(parser.new_error_for_next_token()),
         }
@@ -292,17 +532,280 @@ fn parse_length<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<PropertyValue<Len
     }
 }
 
+// Parses a color, including the relative color syntax that cssparser doesn't support
+fn parse_color<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<cssparser::Color, cssparser::ParseError<'i, ()>> {
+    let parser_state = parser.state();
+    if let Token::Function(s) = parser.next()? {
+        let space = match_ignore_ascii_case! { s,
+            "rgb" | "rgba" => Some(RelativeSpace::Rgb),
+            "oklch" => Some(RelativeSpace::Oklch),
+            _ => None,
+        };
+
+        if let Some(space) = space {
+            let relative = parser.parse_nested_block(|parser| {
+                parser.expect_ident_matching("from")?;
+                parse_relative_color(parser, space)
+            });
+
+            if let Ok(color) = relative {
+                return Ok(color);
+            }
+        } else if s.eq_ignore_ascii_case("color-mix") {
+            return parser.parse_nested_block(parse_color_mix);
+        }
+    }
+
+    parser.reset(&parser_state);
+    Ok(cssparser::Color::parse(parser)?)
+}
+
+#[derive(Clone, Copy)]
+enum RelativeSpace {
+    Rgb,
+    Oklch,
+}
+
+// Refer to https://developer.mozilla.org/en-US/docs/Web/CSS/CSS_colors/Relative_colors
+fn parse_relative_color<'i, 't>(
+    parser: &mut Parser<'i, 't>,
+    space: RelativeSpace,
+) -> Result<cssparser::Color, cssparser::ParseError<'i, ()>> {
+    // The origin has to be resolved here, so it can't be currentColor
+    let origin = match parse_color(parser)? {
+        cssparser::Color::RGBA(rgba) => piet::Color::rgba8(rgba.red, rgba.green, rgba.blue, rgba.alpha),
+        cssparser::Color::CurrentColor => return Err(parser.new_error_for_next_token()),
+    };
+
+    // Channel names, the origin's channels, and what 100% means for each channel
+    let (names, channels, percent) = match space {
+        RelativeSpace::Rgb => {
+            let (r, g, b, alpha) = origin.as_rgba();
+            (
+                ["r", "g", "b", "alpha"],
+                [r * 255.0, g * 255.0, b * 255.0, alpha],
+                [255.0, 255.0, 255.0, 1.0],
+            )
+        }
+        RelativeSpace::Oklch => (["l", "c", "h", "alpha"], to_oklch(&origin), [1.0, 0.4, 360.0, 1.0]),
+    };
+
+    let mut values = channels;
+    for (i, value) in values.iter_mut().enumerate() {
+        if i == 3 {
+            // Alpha is optional, and keeps the origin's alpha if it's left out
+            if parser.is_exhausted() {
+                break;
+            }
+            parser.expect_delim('/')?;
+        }
+
+        let token = parser.next()?;
+        *value = match token {
+            Token::Ident(s) => match names.iter().position(|name| s.eq_ignore_ascii_case(name)) {
+                Some(channel) => channels[channel],
+                None => return Err(parser.new_error_for_next_token()),
+            },
+            Token::Number { value, .. } => *value as f64,
+            Token::Percentage { unit_value, .. } => *unit_value as f64 * percent[i],
+            Token::Dimension { value, unit, .. } if unit.eq_ignore_ascii_case("deg") => *value as f64,
+            _ => return Err(parser.new_error_for_next_token()),
+        };
+    }
+
+    let color = match space {
+        RelativeSpace::Rgb => piet::Color::rgba(values[0] / 255.0, values[1] / 255.0, values[2] / 255.0, values[3]),
+        RelativeSpace::Oklch => from_oklch(values),
+    };
+    let (r, g, b, a) = color.as_rgba8();
+    Ok(cssparser::Color::RGBA(cssparser::RGBA::new(r, g, b, a)))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MixSpace {
+    Srgb,
+    Oklab,
+    Oklch,
+}
+
+// Refer to https://developer.mozilla.org/en-US/docs/Web/CSS/color_value/color-mix
+fn parse_color_mix<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<cssparser::Color, cssparser::ParseError<'i, ()>> {
+    parser.expect_ident_matching("in")?;
+    let token = parser.next()?;
+    let space = match token {
+        Token::Ident(s) => match_ignore_ascii_case! { s,
+            "srgb" => MixSpace::Srgb,
+            "oklab" => MixSpace::Oklab,
+            "oklch" => MixSpace::Oklch,
+            _ => return Err(parser.new_error_for_next_token()),
+        },
+        _ => return Err(parser.new_error_for_next_token()),
+    };
+
+    parser.expect_comma()?;
+    let (start, start_percent) = parse_mix_component(parser)?;
+    parser.expect_comma()?;
+    let (end, end_percent) = parse_mix_component(parser)?;
+
+    // A missing percentage is whatever is left from 100%
+    let (start_percent, end_percent) = match (start_percent, end_percent) {
+        (None, None) => (0.5, 0.5),
+        (Some(start), None) => (start, 1.0 - start),
+        (None, Some(end)) => (1.0 - end, end),
+        (Some(start), Some(end)) => (start, end),
+    };
+
+    let sum = start_percent + end_percent;
+    if start_percent < 0.0 || end_percent < 0.0 || sum <= 0.0 {
+        return Err(parser.new_error_for_next_token());
+    }
+    let t = end_percent / sum;
+
+    let channels = |color: &piet::Color| match space {
+        MixSpace::Srgb => {
+            let (r, g, b, alpha) = color.as_rgba();
+            [r, g, b, alpha]
+        }
+        MixSpace::Oklab => to_oklab(color),
+        MixSpace::Oklch => to_oklch(color),
+    };
+    let start = channels(&start);
+    let mut end = channels(&end);
+
+    // Hue takes the shorter way around the circle
+    if space == MixSpace::Oklch {
+        if end[2] - start[2] > 180.0 {
+            end[2] -= 360.0;
+        } else if end[2] - start[2] < -180.0 {
+            end[2] += 360.0;
+        }
+    }
+
+    // Mix with premultiplied alpha, and fade the result if the percentages add up to less than 100%
+    let alpha = start[3] + (end[3] - start[3]) * t;
+    let mut result = [0.0, 0.0, 0.0, alpha * sum.min(1.0)];
+    for (i, value) in result.iter_mut().enumerate().take(3) {
+        *value = if space == MixSpace::Oklch && i == 2 {
+            start[i] + (end[i] - start[i]) * t
+        } else if alpha > 0.0 {
+            (start[i] * start[3] + (end[i] * end[3] - start[i] * start[3]) * t) / alpha
+        } else {
+            0.0
+        };
+    }
+
+    let color = match space {
+        MixSpace::Srgb => piet::Color::rgba(result[0], result[1], result[2], result[3]),
+        MixSpace::Oklab => from_oklab(result),
+        MixSpace::Oklch => from_oklch(result),
+    };
+    let (r, g, b, a) = color.as_rgba8();
+    Ok(cssparser::Color::RGBA(cssparser::RGBA::new(r, g, b, a)))
+}
+
+// A color with an optional percentage on either side
+fn parse_mix_component<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<(piet::Color, Option<f64>), cssparser::ParseError<'i, ()>> {
+    let mut percent = None;
+
+    let parser_state = parser.state();
+    if let Token::Percentage { unit_value, .. } = parser.next()? {
+        percent = Some(*unit_value as f64);
+    } else {
+        parser.reset(&parser_state);
+    }
+
+    // Mixing happens at parse time, so currentColor isn't available
+    let color = match parse_color(parser)? {
+        cssparser::Color::RGBA(rgba) => piet::Color::rgba8(rgba.red, rgba.green, rgba.blue, rgba.alpha),
+        cssparser::Color::CurrentColor => return Err(parser.new_error_for_next_token()),
+    };
+
+    if percent.is_none() && !parser.is_exhausted() {
+        let parser_state = parser.state();
+        if let Token::Percentage { unit_value, .. } = parser.next()? {
+            percent = Some(*unit_value as f64);
+        } else {
+            parser.reset(&parser_state);
+        }
+    }
+
+    Ok((color, percent))
+}
+
+// Parses a width or height, which may be an intrinsic sizing keyword instead of a length
+fn parse_size<'i, 't>(
+    parser: &mut Parser<'i, 't>,
+    length: fn(PropertyValue<Length>) -> Property,
+    intrinsic: fn(PropertyValue<Option<IntrinsicSize>>) -> Property,
+) -> Result<Vec<Property>, cssparser::ParseError<'i, ()>> {
+    let parser_state = parser.state();
+    let token = parser.next()?;
+    let keyword = match token {
+        Token::Ident(s) => match_ignore_ascii_case! { s,
+            "min-content" => Some(IntrinsicSize::MinContent),
+            "max-content" => Some(IntrinsicSize::MaxContent),
+            _ => None,
+        },
+        Token::Function(s) if s.eq_ignore_ascii_case("fit-content") => Some(parser.parse_nested_block(|parser| {
+            let token = parser.next()?;
+            if let Some(length) = parse_length_token(token) {
+                Ok(IntrinsicSize::FitContent(length))
+            } else {
+                Err(parser.new_error_for_next_token())
+            }
+        })?),
+        _ => None,
+    };
+
+    if let Some(keyword) = keyword {
+        // The keyword takes the place of the length, so leave it auto
+        return Ok(vec![length(PropertyValue::Auto), intrinsic(PropertyValue::Exact(Some(keyword)))]);
+    }
+
+    parser.reset(&parser_state);
+    let value = parse_length(parser)?;
+    let keyword = match value {
+        PropertyValue::Initial => PropertyValue::Initial,
+        PropertyValue::Inherit => PropertyValue::Inherit,
+        _ => PropertyValue::Exact(None),
+    };
+    Ok(vec![length(value), intrinsic(keyword)])
+}
+
 fn parse_length_token(token: &Token) -> Option<Length> {
     match token {
         Token::Number { value, .. } => Some(Length::Px(*value as f32)),
         Token::Dimension { value, unit, .. } => match unit.as_ref() {
             "em" => Some(Length::Em(*value)),
+            "rem" => Some(Length::Rem(*value)),
+            "vw" => Some(Length::Vw(*value)),
+            "vh" => Some(Length::Vh(*value)),
+            "vmin" => Some(Length::Vmin(*value)),
+            "vmax" => Some(Length::Vmax(*value)),
             _ => Some(Length::Px(*value)),
         },
         _ => None,
     }
 }
 
+fn parse_length_percentage_token(token: &Token) -> Option<LengthPercentage> {
+    match token {
+        Token::Percentage { unit_value, .. } => Some(LengthPercentage::Percent(*unit_value)),
+        _ => parse_length_token(token).map(LengthPercentage::Length),
+    }
+}
+
+// Expand 1-4 values into top, right, bottom, left order
+fn expand_quad<T: Copy>(values: &[T]) -> Option<[T; 4]> {
+    match *values {
+        [all] => Some([all, all, all, all]),
+        [vertical, horizontal] => Some([vertical, horizontal, vertical, horizontal]),
+        [top, horizontal, bottom] => Some([top, horizontal, bottom, horizontal]),
+        [top, right, bottom, left] => Some([top, right, bottom, left]),
+        _ => None,
+    }
+}
+
 fn parse_quad<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<PropertyValue<Length>>, cssparser::ParseError<'i, ()>> {
     let mut sizes = Vec::with_capacity(4);
 
@@ -400,9 +903,9 @@ fn parse_background_image<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Pro
             Token::Function(s) => match_ignore_ascii_case! { s,
                 "linear-gradient" => result.push(parser.parse_nested_block(|parser| {
                     // Refer to https://developer.mozilla.org/en-US/docs/Web/CSS/gradient/linear-gradient#formal_syntax
-                    let mut gradient_stops: Vec<(f32, Option<piet::Color>)> = Vec::new();
+                    let mut gradient_stops: Vec<(f32, Option<GradientColor>)> = Vec::new();
                     let mut angle = GradientAngle::Bottom;
-                    let mut color: Option<piet::Color> = None;
+                    let mut color: Option<GradientColor> = None;
                     let mut first = true;
                     let mut prev_comma = false; // Did we just see a comma?
                     let mut prev_color = false; // Did we just see a color?
@@ -419,8 +922,8 @@ fn parse_background_image<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Pro
                                     return Err(parser.new_error_for_next_token());
                                 }
                                 parser.reset(&parser_state);
-                                if let Ok(cssparser::Color::RGBA(rgba)) = cssparser::Color::parse(parser) {
-                                    color = Some(piet::Color::rgba8(rgba.red, rgba.green, rgba.blue, rgba.alpha));
+                                if let Ok(value) = parse_color(parser) {
+                                    color = Some(GradientColor::from(value));
                                 } else {
                                     return Err(parser.new_error_for_next_token());
                                 }
@@ -453,8 +956,8 @@ fn parse_background_image<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Pro
                                 if !prev_comma && !first {
                                     return Err(parser.new_error_for_next_token());
                                 }
-                                if let Ok(cssparser::Color::RGBA(rgba)) = cssparser::Color::parse_hash(hash.as_bytes()) {
-                                    color = Some(piet::Color::rgba8(rgba.red, rgba.green, rgba.blue, rgba.alpha));
+                                if let Ok(value) = cssparser::Color::parse_hash(hash.as_bytes()) {
+                                    color = Some(GradientColor::from(value));
                                 } else {
                                     return Err(parser.new_error_for_next_token());
                                 };
@@ -491,8 +994,8 @@ fn parse_background_image<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Pro
                                 } else if !prev_comma {
                                     return Err(parser.new_error_for_next_token());
                                 }
-                                if let Ok(cssparser::Color::RGBA(rgba)) = cssparser::parse_color_keyword(s) {
-                                    color = Some(piet::Color::rgba8(rgba.red, rgba.green, rgba.blue, rgba.alpha));
+                                if let Ok(value) = cssparser::parse_color_keyword(s) {
+                                    color = Some(GradientColor::from(value));
                                 } else {
                                     return Err(parser.new_error_for_next_token());
                                 };
@@ -566,7 +1069,7 @@ fn parse_background_image<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Pro
                         gradient_stops[0].0 = 0.0;
                     }
                     let last_idx = gradient_stops.len() - 1;
-                    if gradient_stops[last_idx].1.is_none() {
+                    if gradient_stops[0].1.is_none() || gradient_stops[last_idx].1.is_none() {
                         return Err(parser.new_error_for_next_token());
                     }
                     if gradient_stops[last_idx].0.is_nan() {
@@ -591,25 +1094,14 @@ fn parse_background_image<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Pro
                             }
                         }
                     }
-                    // Calculate colors of mid-point adjustment stops
+                    // Mid-point adjustment stops need a color on either side
                     for i in 1..gradient_stops.len() - 1 {
-                        if gradient_stops[i].1.is_none() {
-                            if let (Some(l), Some(r)) = (&gradient_stops[i - 1].1, &gradient_stops[i + 1].1) {
-                                let l = l.as_rgba8();
-                                let r = r.as_rgba8();
-                                gradient_stops[i].1 = Some(piet::Color::rgba8(
-                                    (l.0 / 2) + (r.0 / 2),
-                                    (l.1 / 2) + (r.1 / 2),
-                                    (l.2 / 2) + (r.2 / 2),
-                                    (l.3 / 2) + (r.3 / 2)));
-                            } else {
-                                return Err(parser.new_error_for_next_token());
-                            }
+                        if gradient_stops[i].1.is_none() && (gradient_stops[i - 1].1.is_none() || gradient_stops[i + 1].1.is_none()) {
+                            return Err(parser.new_error_for_next_token());
                         }
                     }
-                    // Convert to piet gradient stops
                     let gradient_stops = gradient_stops.into_iter().map(|stop| {
-                        piet::GradientStop { pos: stop.0, color: stop.1.unwrap() }
+                        (stop.0, stop.1.unwrap_or(GradientColor::Hint))
                     }).collect();
                     Ok(LinearGradient {angle, gradient_stops })
                 })?),
@@ -656,7 +1148,7 @@ fn parse_border<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Property>, cs
                     result.push(Property::BorderTopColor(PropertyValue::Inherit));
                 },
                 _ => {
-                    let color = cssparser::Color::parse(parser)?;
+                    let color = parse_color(parser)?;
 
                     result.push(Property::BorderBottomColor(PropertyValue::Exact(color)));
                     result.push(Property::BorderLeftColor(PropertyValue::Exact(color)));
@@ -688,7 +1180,7 @@ fn parse_border_bottom<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Proper
                 "initial" => result.push(Property::BorderBottomColor(PropertyValue::Initial)),
                 "inherit" => result.push(Property::BorderBottomColor(PropertyValue::Inherit)),
                 _ => {
-                    let color = cssparser::Color::parse(parser)?;
+                    let color = parse_color(parser)?;
                     result.push(Property::BorderBottomColor(PropertyValue::Exact(color)));
                 },
             },
@@ -704,17 +1196,22 @@ fn parse_border_color<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Propert
     let mut colors: Vec<PropertyValue<Color>> = Vec::with_capacity(4);
 
     while !parser.is_exhausted() {
-        let token = parser.next()?;
-        match token {
+        let parser_state = parser.state();
+        let keyword = match parser.next()? {
             Token::Ident(s) => match_ignore_ascii_case! { s,
-                "initial" => colors.push(PropertyValue::Initial),
-                "inherit" => colors.push(PropertyValue::Inherit),
-                _ => {
-                    let color = cssparser::Color::parse(parser)?;
-                    colors.push(PropertyValue::Exact(color));
-                },
+                "initial" => Some(PropertyValue::Initial),
+                "inherit" => Some(PropertyValue::Inherit),
+                _ => None,
             },
-            _ => return Err(parser.new_error_for_next_token()),
+            _ => None,
+        };
+
+        if let Some(keyword) = keyword {
+            colors.push(keyword);
+        } else {
+            // Colors can be more than one token, so parse them from the start
+            parser.reset(&parser_state);
+            colors.push(PropertyValue::Exact(parse_color(parser)?));
         }
     }
 
@@ -766,7 +1263,7 @@ fn parse_border_left<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Property
                 "initial" => result.push(Property::BorderLeftColor(PropertyValue::Initial)),
                 "inherit" => result.push(Property::BorderLeftColor(PropertyValue::Inherit)),
                 _ => {
-                    let color = cssparser::Color::parse(parser)?;
+                    let color = parse_color(parser)?;
                     result.push(Property::BorderLeftColor(PropertyValue::Exact(color)));
                 },
             },
@@ -794,7 +1291,7 @@ fn parse_border_right<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Propert
                 "initial" => result.push(Property::BorderRightColor(PropertyValue::Initial)),
                 "inherit" => result.push(Property::BorderRightColor(PropertyValue::Inherit)),
                 _ => {
-                    let color = cssparser::Color::parse(parser)?;
+                    let color = parse_color(parser)?;
                     result.push(Property::BorderRightColor(PropertyValue::Exact(color)));
                 },
             },
@@ -822,7 +1319,7 @@ fn parse_border_top<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Property>
                 "initial" => result.push(Property::BorderTopColor(PropertyValue::Initial)),
                 "inherit" => result.push(Property::BorderTopColor(PropertyValue::Inherit)),
                 _ => {
-                    let color = cssparser::Color::parse(parser)?;
+                    let color = parse_color(parser)?;
                     result.push(Property::BorderTopColor(PropertyValue::Exact(color)));
                 },
             },
@@ -940,18 +1437,16 @@ fn parse_box_shadow<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Property>
                     "initial" => return Ok(vec![Property::BoxShadow(PropertyValue::Initial)]),
                     "inherit" => return Ok(vec![Property::BoxShadow(PropertyValue::Inherit)]),
                     "inset" => inset = true,
-                    _ => {
-                        if let Ok(cssparser::Color::RGBA(rgba)) = cssparser::parse_color_keyword(s) {
-                            color = Some(piet::Color::rgba8(rgba.red, rgba.green, rgba.blue, rgba.alpha));
-                        } else {
-                            return Err(parser.new_error_for_next_token());
-                        };
+                    _ => match cssparser::parse_color_keyword(s) {
+                        Ok(cssparser::Color::RGBA(rgba)) => color = Some(piet::Color::rgba8(rgba.red, rgba.green, rgba.blue, rgba.alpha)),
+                        Ok(cssparser::Color::CurrentColor) => color = None, // Resolved when styled
+                        Err(_) => return Err(parser.new_error_for_next_token()),
                     },
                 }
             }
             Token::Function(_) => {
                 parser.reset(&parser_state);
-                if let Ok(cssparser::Color::RGBA(rgba)) = cssparser::Color::parse(parser) {
+                if let Ok(cssparser::Color::RGBA(rgba)) = parse_color(parser) {
                     color = Some(piet::Color::rgba8(rgba.red, rgba.green, rgba.blue, rgba.alpha));
                 } else {
                     return Err(parser.new_error_for_next_token());
@@ -1034,6 +1529,163 @@ fn parse_box_shadow<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Property>
     Ok(vec![Property::BoxShadow(PropertyValue::Exact(Some(Arc::new(result))))])
 }
 
+fn parse_clip_path<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Property>, cssparser::ParseError<'i, ()>> {
+    let token = parser.next()?;
+    match token {
+        Token::Ident(s) => Ok(vec![Property::ClipPath(match_ignore_ascii_case! { s,
+            "none" => PropertyValue::Exact(None),
+            "initial" => PropertyValue::Initial,
+            "inherit" => PropertyValue::Inherit,
+            _ => return Err(parser.new_error_for_next_token()),
+        })]),
+        Token::Function(s) => {
+            // Refer to https://developer.mozilla.org/en-US/docs/Web/CSS/basic-shape
+            let clip_path = match_ignore_ascii_case! { s,
+                "inset" => parser.parse_nested_block(parse_inset)?,
+                "circle" => parser.parse_nested_block(parse_circle)?,
+                "ellipse" => parser.parse_nested_block(parse_ellipse)?,
+                _ => return Err(parser.new_error_for_next_token()),
+            };
+            Ok(vec![Property::ClipPath(PropertyValue::Exact(Some(clip_path)))])
+        }
+        _ => Err(parser.new_error_for_next_token()),
+    }
+}
+
+fn parse_inset<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<ClipPath, cssparser::ParseError<'i, ()>> {
+    let mut offsets = Vec::with_capacity(4);
+    let mut radii = Vec::with_capacity(4);
+    let mut round = false; // Has the `round` keyword been seen?
+
+    while !parser.is_exhausted() {
+        let token = parser.next()?;
+        match token {
+            Token::Ident(s) if !round && s.eq_ignore_ascii_case("round") => round = true,
+            _ => {
+                if let Some(length) = parse_length_percentage_token(token) {
+                    if round {
+                        radii.push(length);
+                    } else {
+                        offsets.push(length);
+                    }
+                } else {
+                    return Err(parser.new_error_for_next_token());
+                }
+            }
+        }
+    }
+
+    let [top, right, bottom, left] = if let Some(offsets) = expand_quad(&offsets) {
+        offsets
+    } else {
+        return Err(parser.new_error_for_next_token());
+    };
+
+    let radii = if !round {
+        [LengthPercentage::Length(Length::default()); 4]
+    } else if let Some(radii) = expand_quad(&radii) {
+        radii
+    } else {
+        return Err(parser.new_error_for_next_token());
+    };
+
+    Ok(ClipPath::Inset {
+        top,
+        right,
+        bottom,
+        left,
+        radii,
+    })
+}
+
+fn parse_circle<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<ClipPath, cssparser::ParseError<'i, ()>> {
+    let radius = parse_shape_radius(parser).unwrap_or(ShapeRadius::ClosestSide);
+    let (x, y) = parse_shape_position(parser)?;
+    Ok(ClipPath::Circle { radius, x, y })
+}
+
+fn parse_ellipse<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<ClipPath, cssparser::ParseError<'i, ()>> {
+    let (radius_x, radius_y) = if let Some(radius_x) = parse_shape_radius(parser) {
+        if let Some(radius_y) = parse_shape_radius(parser) {
+            (radius_x, radius_y)
+        } else {
+            return Err(parser.new_error_for_next_token());
+        }
+    } else {
+        (ShapeRadius::ClosestSide, ShapeRadius::ClosestSide)
+    };
+    let (x, y) = parse_shape_position(parser)?;
+    Ok(ClipPath::Ellipse { radius_x, radius_y, x, y })
+}
+
+// Parse an optional `<shape-radius>`, leaving the parser untouched if there isn't one
+fn parse_shape_radius(parser: &mut Parser) -> Option<ShapeRadius> {
+    let parser_state = parser.state();
+    let radius = match parser.next() {
+        Ok(Token::Ident(s)) => match_ignore_ascii_case! { s,
+            "closest-side" => Some(ShapeRadius::ClosestSide),
+            "farthest-side" => Some(ShapeRadius::FarthestSide),
+            _ => None,
+        },
+        Ok(token) => parse_length_percentage_token(token).map(ShapeRadius::Length),
+        Err(_) => None,
+    };
+    if radius.is_none() {
+        parser.reset(&parser_state);
+    }
+    radius
+}
+
+// Parse an optional `at <position>`, which defaults to the center of the box
+fn parse_shape_position<'i, 't>(
+    parser: &mut Parser<'i, 't>,
+) -> Result<(LengthPercentage, LengthPercentage), cssparser::ParseError<'i, ()>> {
+    let center = LengthPercentage::Percent(0.5);
+    if parser.is_exhausted() {
+        return Ok((center, center));
+    }
+
+    parser.expect_ident_matching("at")?;
+
+    let mut x = None;
+    let mut y = None;
+    while !parser.is_exhausted() {
+        let token = parser.next()?;
+        match token {
+            Token::Ident(s) => match_ignore_ascii_case! { s,
+                "left" => x = Some(LengthPercentage::Percent(0.0)),
+                "right" => x = Some(LengthPercentage::Percent(1.0)),
+                "top" => y = Some(LengthPercentage::Percent(0.0)),
+                "bottom" => y = Some(LengthPercentage::Percent(1.0)),
+                "center" => {
+                    if x.is_none() {
+                        x = Some(center);
+                    } else {
+                        y = Some(center);
+                    }
+                },
+                _ => return Err(parser.new_error_for_next_token()),
+            },
+            _ => {
+                let value = if let Some(value) = parse_length_percentage_token(token) {
+                    value
+                } else {
+                    return Err(parser.new_error_for_next_token());
+                };
+                if x.is_none() {
+                    x = Some(value);
+                } else if y.is_none() {
+                    y = Some(value);
+                } else {
+                    return Err(parser.new_error_for_next_token());
+                }
+            }
+        }
+    }
+
+    Ok((x.unwrap_or(center), y.unwrap_or(center)))
+}
+
 fn parse_cursor<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Property>, cssparser::ParseError<'i, ()>> {
     let token = parser.next()?;
     match token {
@@ -1094,12 +1746,9 @@ fn parse_flex<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Property>, cssp
                 2 => result.push(Property::FlexBasis(PropertyValue::Exact(Length::Px(*value)))),
                 _ => return Err(parser.new_error_for_next_token()),
             },
-            Token::Dimension { unit, value, .. } => {
-                if result.len() == 2 {
-                    match unit.as_ref() {
-                        "em" => result.push(Property::FlexBasis(PropertyValue::Exact(Length::Em(*value)))),
-                        _ => result.push(Property::FlexBasis(PropertyValue::Exact(Length::Px(*value)))),
-                    };
+            Token::Dimension { .. } => {
+                if let (2, Some(length)) = (result.len(), parse_length_token(token)) {
+                    result.push(Property::FlexBasis(PropertyValue::Exact(length)));
                 } else {
                     return Err(parser.new_error_for_next_token());
                 }
@@ -1316,3 +1965,266 @@ fn parse_position<'i, 't>(parser: &mut Parser<'i, 't>) -> Result<Vec<Property>,
         _ => return Err(parser.new_error_for_next_token()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Parse a stylesheet into its rules, and whether each one is dynamic
+    fn parse(text: &str) -> Vec<(bool, Rule)> {
+        let mut input = ParserInput::new(text);
+        let mut parser = Parser::new(&mut input);
+        RuleListParser::new_for_stylesheet(&mut parser, RulesParser)
+            .filter_map(Result::ok)
+            .flatten()
+            .collect()
+    }
+
+    fn class(name: &str) -> Selector {
+        Selector::Class(name.to_string())
+    }
+
+    #[test]
+    fn nested_ampersand_compound() {
+        let rules = parse(".card { &.active { width: 1px; } }");
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[1].1.selectors, vec![class("card"), Selector::Is(vec![class("active")])]);
+        assert_eq!(rules[1].1.specificity, 20);
+        assert!(!rules[1].0);
+    }
+
+    #[test]
+    fn nested_ampersand_descendant() {
+        let rules = parse(".card { & .title { width: 1px; } }");
+        assert_eq!(rules[1].1.selectors, vec![class("card"), Selector::Children, class("title")]);
+
+        let rules = parse(".card { .title { width: 1px; } }");
+        assert_eq!(rules[1].1.selectors, vec![class("card"), Selector::Children, class("title")]);
+    }
+
+    #[test]
+    fn nested_ampersand_pseudo_class() {
+        let rules = parse(".card { &:hover { width: 1px; } }");
+        assert_eq!(rules[1].1.selectors, vec![class("card"), Selector::Hover]);
+        assert!(rules[1].0);
+        assert!(!rules[0].0);
+    }
+
+    #[test]
+    fn nested_ampersand_compound_then_descendant() {
+        let rules = parse(".card { &.active .title { width: 1px; } }");
+        assert_eq!(
+            rules[1].1.selectors,
+            vec![
+                class("card"),
+                Selector::Is(vec![class("active")]),
+                Selector::Children,
+                class("title")
+            ]
+        );
+    }
+
+    #[test]
+    fn is_and_not_specificity() {
+        let rules = parse(".a:is(.b, #c) { width: 1px; } .a:not(.b) { width: 1px; } :not(:hover) { width: 1px; }");
+        assert_eq!(
+            rules[0].1.selectors,
+            vec![class("a"), Selector::Is(vec![class("b"), Selector::Id("c".into())])]
+        );
+        assert_eq!(rules[0].1.specificity, 110);
+        assert_eq!(rules[1].1.selectors, vec![class("a"), Selector::Not(vec![class("b")])]);
+        assert_eq!(rules[1].1.specificity, 20);
+        assert!(!rules[1].0);
+        assert_eq!(rules[2].1.specificity, 10);
+        assert!(rules[2].0);
+    }
+
+    #[test]
+    fn is_and_not_only_take_simple_selectors() {
+        assert!(parse(".a:is(.b .c) { width: 1px; }").is_empty());
+        assert!(parse(".a:not(.b > .c) { width: 1px; }").is_empty());
+        assert!(parse(".a:has(.b) { width: 1px; }").is_empty());
+    }
+
+    #[test]
+    fn id_selectors() {
+        let rules = parse("#main { width: 1px; } .a #main.b { width: 1px; }");
+        assert_eq!(rules[0].1.selectors, vec![Selector::Id("main".into())]);
+        assert_eq!(rules[0].1.specificity, 100);
+        assert_eq!(
+            rules[1].1.selectors,
+            vec![
+                class("a"),
+                Selector::Children,
+                Selector::Id("main".into()),
+                Selector::Is(vec![class("b")])
+            ]
+        );
+        assert_eq!(rules[1].1.specificity, 120);
+    }
+
+    #[test]
+    fn state_pseudo_classes_are_static() {
+        let rules = parse(".a:checked { width: 1px; } .a:selected { width: 1px; } .a:indeterminate { width: 1px; }");
+        assert_eq!(rules[0].1.selectors, vec![class("a"), Selector::Checked]);
+        assert_eq!(rules[1].1.selectors, vec![class("a"), Selector::Selected]);
+        assert_eq!(rules[2].1.selectors, vec![class("a"), Selector::Indeterminate]);
+        assert!(rules.iter().all(|(dynamic, rule)| !dynamic && rule.specificity == 20));
+    }
+
+    // Parse an inline `clip-path` declaration
+    fn clip_path(css: &str) -> ClipPath {
+        match &parse_inline_style(css)[..] {
+            [Property::ClipPath(PropertyValue::Exact(Some(clip_path)))] => *clip_path,
+            other => panic!("{:?} didn't parse to a clip path: {:?}", css, other),
+        }
+    }
+
+    #[test]
+    fn clip_path_inset() {
+        let px = |value| LengthPercentage::Length(Length::Px(value));
+        assert_eq!(
+            clip_path("clip-path: inset(10px 20%)"),
+            ClipPath::Inset {
+                top: px(10.0),
+                right: LengthPercentage::Percent(0.2),
+                bottom: px(10.0),
+                left: LengthPercentage::Percent(0.2),
+                radii: [px(0.0); 4],
+            }
+        );
+        assert_eq!(
+            clip_path("clip-path: inset(0 round 5px 50%)"),
+            ClipPath::Inset {
+                top: px(0.0),
+                right: px(0.0),
+                bottom: px(0.0),
+                left: px(0.0),
+                radii: [px(5.0), LengthPercentage::Percent(0.5), px(5.0), LengthPercentage::Percent(0.5)],
+            }
+        );
+        assert!(parse_inline_style("clip-path: inset(round 5px)").is_empty());
+        assert!(parse_inline_style("clip-path: inset(1px 2px 3px 4px 5px)").is_empty());
+    }
+
+    #[test]
+    fn clip_path_circle_and_ellipse() {
+        let center = LengthPercentage::Percent(0.5);
+        assert_eq!(
+            clip_path("clip-path: circle()"),
+            ClipPath::Circle {
+                radius: ShapeRadius::ClosestSide,
+                x: center,
+                y: center,
+            }
+        );
+        assert_eq!(
+            clip_path("clip-path: circle(40% at 10px 20%)"),
+            ClipPath::Circle {
+                radius: ShapeRadius::Length(LengthPercentage::Percent(0.4)),
+                x: LengthPercentage::Length(Length::Px(10.0)),
+                y: LengthPercentage::Percent(0.2),
+            }
+        );
+        assert_eq!(
+            clip_path("clip-path: ellipse(farthest-side 5px)"),
+            ClipPath::Ellipse {
+                radius_x: ShapeRadius::FarthestSide,
+                radius_y: ShapeRadius::Length(LengthPercentage::Length(Length::Px(5.0))),
+                x: center,
+                y: center,
+            }
+        );
+    }
+
+    #[test]
+    fn clip_path_percentage_radius_uses_the_smaller_side() {
+        use druid_shell::kurbo::Shape;
+
+        let path = clip_path("clip-path: inset(10% 20% round 50%)").resolve(16.0, 16.0, (0.0, 0.0), 100.0, 50.0);
+        assert_eq!(path.bounding_box().round(), druid_shell::kurbo::Rect::new(20.0, 5.0, 80.0, 45.0));
+        // The inset box is 60 by 40, so the corners are circles with a radius of 20
+        assert!(!path.contains((22.0, 7.0).into()));
+        assert!(path.contains((40.0, 25.0).into()));
+        assert!(path.contains((60.0, 25.0).into()));
+    }
+
+    // Parse a color into its RGBA components, or `None` if it's invalid or currentColor
+    fn rgba(text: &str) -> Option<(u8, u8, u8, u8)> {
+        let mut input = ParserInput::new(text);
+        let mut parser = Parser::new(&mut input);
+        match parse_color(&mut parser) {
+            Ok(cssparser::Color::RGBA(rgba)) if parser.is_exhausted() => Some((rgba.red, rgba.green, rgba.blue, rgba.alpha)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn relative_rgb_colors() {
+        assert_eq!(rgba("rgb(from red r g b)"), Some((255, 0, 0, 255)));
+        assert_eq!(rgba("rgb(from #123456 b g r)"), Some((0x56, 0x34, 0x12, 255)));
+        assert_eq!(rgba("rgb(from red 0 g 50%)"), Some((0, 0, 128, 255)));
+        assert_eq!(rgba("rgba(from red r g b / 50%)"), Some((255, 0, 0, 128)));
+        assert_eq!(rgba("rgb(from rgb(0 0 255 / 0.5) r g b)"), Some((0, 0, 255, 128)));
+        assert_eq!(rgba("rgb(from rgb(from red g r b) r g b / alpha)"), Some((0, 255, 0, 255)));
+    }
+
+    #[test]
+    fn relative_oklch_colors() {
+        assert_eq!(rgba("oklch(from #808080 l c h)"), Some((128, 128, 128, 255)));
+        assert_eq!(rgba("oklch(from #123456 l c h / 0.5)"), Some((0x12, 0x34, 0x56, 128)));
+
+        // Dropping the chroma leaves a gray
+        let (r, g, b, _) = rgba("oklch(from blue l 0 h)").unwrap();
+        assert!(r == g && g == b);
+        assert_eq!(rgba("oklch(from white 0% c h)"), Some((0, 0, 0, 255)));
+    }
+
+    #[test]
+    fn invalid_relative_colors() {
+        assert_eq!(rgba("rgb(from currentColor r g b)"), None);
+        assert_eq!(rgba("rgb(from red x g b)"), None);
+        assert_eq!(rgba("rgb(from red r g)"), None);
+        assert_eq!(rgba("hsl(from red h s l)"), None);
+
+        // Other colors still go through cssparser
+        assert_eq!(rgba("rgb(1, 2, 3)"), Some((1, 2, 3, 255)));
+        assert_eq!(rgba("#0000ff80"), Some((0, 0, 255, 128)));
+    }
+
+    #[test]
+    fn color_mix_in_srgb() {
+        assert_eq!(rgba("color-mix(in srgb, red, blue)"), Some((128, 0, 128, 255)));
+        assert_eq!(rgba("color-mix(in srgb, red 25%, blue)"), Some((64, 0, 191, 255)));
+        assert_eq!(rgba("color-mix(in srgb, 25% red, blue)"), Some((64, 0, 191, 255)));
+        assert_eq!(rgba("color-mix(in srgb, red, blue 75%)"), Some((64, 0, 191, 255)));
+
+        // Percentages that add up to less than 100% fade the result
+        assert_eq!(rgba("color-mix(in srgb, red 20%, blue 20%)"), Some((128, 0, 128, 102)));
+        // Alpha is premultiplied, so a transparent color doesn't darken the other one
+        assert_eq!(rgba("color-mix(in srgb, red, transparent)"), Some((255, 0, 0, 128)));
+    }
+
+    #[test]
+    fn color_mix_in_oklab_and_oklch() {
+        let gray = piet::Color::BLACK.mix(&piet::Color::WHITE, 0.5).as_rgba8();
+        assert_eq!(rgba("color-mix(in oklab, black, white)"), Some(gray));
+
+        // Hue goes the shorter way around, from 350 through 0 to 10 degrees
+        let mixed = rgba("color-mix(in oklch, oklch(from red 0.6 0.1 350), oklch(from red 0.6 0.1 10))").unwrap();
+        let expected = rgba("oklch(from red 0.6 0.1 0)").unwrap();
+        for (a, b) in [(mixed.0, expected.0), (mixed.1, expected.1), (mixed.2, expected.2)] {
+            assert!((a as i32 - b as i32).abs() <= 1, "{:?} isn't {:?}", mixed, expected);
+        }
+    }
+
+    #[test]
+    fn invalid_color_mix() {
+        assert_eq!(rgba("color-mix(in hsl, red, blue)"), None);
+        assert_eq!(rgba("color-mix(srgb, red, blue)"), None);
+        assert_eq!(rgba("color-mix(in srgb, red)"), None);
+        assert_eq!(rgba("color-mix(in srgb, red 0%, blue 0%)"), None);
+        assert_eq!(rgba("color-mix(in srgb, red -10%, blue)"), None);
+        assert_eq!(rgba("color-mix(in srgb, currentColor, blue)"), None);
+    }
+}
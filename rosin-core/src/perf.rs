@@ -0,0 +1,198 @@
+#![forbid(unsafe_code)]
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// Timings for a single call to `Viewport::draw()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameStats {
+    /// When the frame started, relative to when recording started.
+    pub start: Duration,
+    /// Time spent rebuilding the tree and applying static styles. Zero if neither was needed.
+    pub build: Duration,
+    /// Time spent applying hover/focus styles and running style callbacks.
+    pub style: Duration,
+    pub layout: Duration,
+    pub draw: Duration,
+    pub node_count: usize,
+}
+
+/// Keeps timings for the most recent frames, so they can be exported for offline analysis.
+#[derive(Debug)]
+pub struct FrameRecorder {
+    epoch: Instant,
+    capacity: usize,
+    frames: VecDeque<FrameStats>,
+}
+
+impl FrameRecorder {
+    /// Create a recorder that keeps up to `capacity` frames, dropping the oldest ones first.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            epoch: Instant::now(),
+            capacity,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn frames(&self) -> impl Iterator<Item = &FrameStats> {
+        self.frames.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    pub(crate) fn epoch(&self) -> Instant {
+        self.epoch
+    }
+
+    pub(crate) fn push(&mut self, frame: FrameStats) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+
+    /// Export the frames as a JSON array. Times are in microseconds.
+    pub fn to_json(&self) -> String {
+        let mut result = String::from("[");
+        for (i, frame) in self.frames.iter().enumerate() {
+            if i != 0 {
+                result.push(',');
+            }
+            write!(
+                result,
+                r#"{{"start":{},"build":{},"style":{},"layout":{},"draw":{},"nodes":{}}}"#,
+                frame.start.as_micros(),
+                frame.build.as_micros(),
+                frame.style.as_micros(),
+                frame.layout.as_micros(),
+                frame.draw.as_micros(),
+                frame.node_count,
+            )
+            .unwrap();
+        }
+        result.push(']');
+        result
+    }
+
+    /// Export the frames in the Chrome trace event format, which can be opened in `chrome://tracing` or Perfetto.
+    pub fn to_chrome_trace(&self) -> String {
+        let mut result = String::from(r#"{"traceEvents":["#);
+        let mut first = true;
+        for frame in &self.frames {
+            let mut ts = frame.start;
+            for (name, dur) in [
+                ("build", frame.build),
+                ("style", frame.style),
+                ("layout", frame.layout),
+                ("draw", frame.draw),
+            ] {
+                if !first {
+                    result.push(',');
+                }
+                first = false;
+                write!(
+                    result,
+                    r#"{{"name":"{}","ph":"X","pid":0,"tid":0,"ts":{},"dur":{},"args":{{"nodes":{}}}}}"#,
+                    name,
+                    ts.as_micros(),
+                    dur.as_micros(),
+                    frame.node_count,
+                )
+                .unwrap();
+                ts += dur;
+            }
+        }
+        result.push_str("]}");
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    fn frame(start: u64, node_count: usize) -> FrameStats {
+        FrameStats {
+            start: Duration::from_micros(start),
+            build: Duration::from_micros(10),
+            style: Duration::from_micros(20),
+            layout: Duration::from_micros(30),
+            draw: Duration::from_micros(40),
+            node_count,
+        }
+    }
+
+    #[test]
+    fn keeps_the_most_recent_frames() {
+        let mut recorder = FrameRecorder::new(2);
+        recorder.push(frame(0, 1));
+        recorder.push(frame(100, 2));
+        recorder.push(frame(200, 3));
+        let nodes: Vec<usize> = recorder.frames().map(|frame| frame.node_count).collect();
+        assert_eq!(nodes, [2, 3]);
+
+        let mut recorder = FrameRecorder::new(0);
+        recorder.push(frame(0, 1));
+        assert!(recorder.is_empty());
+    }
+
+    #[test]
+    fn exports_json_and_chrome_traces() {
+        let mut recorder = FrameRecorder::new(4);
+        assert_eq!(recorder.to_json(), "[]");
+        assert_eq!(recorder.to_chrome_trace(), r#"{"traceEvents":[]}"#);
+
+        recorder.push(frame(1000, 5));
+        assert_eq!(
+            recorder.to_json(),
+            r#"[{"start":1000,"build":10,"style":20,"layout":30,"draw":40,"nodes":5}]"#
+        );
+
+        // Each phase starts where the previous one ended
+        let trace = recorder.to_chrome_trace();
+        assert!(trace.contains(r#"{"name":"build","ph":"X","pid":0,"tid":0,"ts":1000,"dur":10,"args":{"nodes":5}}"#));
+        assert!(trace.contains(r#""name":"layout","ph":"X","pid":0,"tid":0,"ts":1030,"dur":30"#));
+        assert!(trace.contains(r#""name":"draw","ph":"X","pid":0,"tid":0,"ts":1060,"dur":40"#));
+        assert_eq!(trace.matches(r#""ph":"X""#).count(), 4);
+    }
+
+    #[test]
+    fn viewport_records_each_draw() {
+        fn view(_: &()) -> View<(), ()> {
+            View::default().add_child(View::default()).add_child(View::default())
+        }
+
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&(), None).unwrap();
+        assert!(viewport.frame_recorder().is_none());
+
+        viewport.record_frames(8);
+        viewport.update_phase(Phase::Build);
+        viewport.draw(&(), None).unwrap();
+        viewport.update_phase(Phase::Draw);
+        viewport.draw(&(), None).unwrap();
+
+        let recorder = viewport.stop_recording().unwrap();
+        let frames: Vec<&FrameStats> = recorder.frames().collect();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].node_count, 3);
+        assert!(frames[0].start <= frames[1].start);
+        assert!(viewport.frame_recorder().is_none());
+    }
+}
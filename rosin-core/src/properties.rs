@@ -20,7 +20,7 @@ pub enum Property {
     AlignItems(PropertyValue<AlignItems>),
     AlignSelf(PropertyValue<AlignItems>),
     BackgroundColor(PropertyValue<cssparser::Color>),
-    BackgroundImage(PropertyValue<Option<Arc<Vec<LinearGradient>>>>),
+    BackgroundImage(PropertyValue<Option<Arc<Vec<LinearGradient<GradientColor>>>>>),
     BorderBottomColor(PropertyValue<cssparser::Color>),
     BorderBottomLeftRadius(PropertyValue<Length>),
     BorderBottomRightRadius(PropertyValue<Length>),
@@ -34,7 +34,8 @@ pub enum Property {
     BorderTopRightRadius(PropertyValue<Length>),
     BorderTopWidth(PropertyValue<Length>),
     Bottom(PropertyValue<Length>),
-    BoxShadow(PropertyValue<Option<Arc<Vec<BoxShadow>>>>),
+    BoxShadow(PropertyValue<Option<Arc<Vec<BoxShadow<Option<piet::Color>>>>>>),
+    ClipPath(PropertyValue<Option<ClipPath>>),
     Color(PropertyValue<cssparser::Color>),
     Cursor(PropertyValue<Cursor>),
     FlexBasis(PropertyValue<Length>),
@@ -46,6 +47,8 @@ pub enum Property {
     FontSize(PropertyValue<Length>),
     FontWeight(PropertyValue<u32>),
     Height(PropertyValue<Length>),
+    IntrinsicHeight(PropertyValue<Option<IntrinsicSize>>),
+    IntrinsicWidth(PropertyValue<Option<IntrinsicSize>>),
     JustifyContent(PropertyValue<JustifyContent>),
     Left(PropertyValue<Length>),
     MarginBottom(PropertyValue<Length>),
@@ -119,7 +122,7 @@ macro_rules! apply {
             }
         }
     };
-    (@clone_opt, $value:expr, $style:expr, $parent_style:ident, $attr:ident) => {
+    (@current_color, $value:expr, $style:expr, $parent_style:ident, $attr:ident) => {
         match $value {
             PropertyValue::Initial => {
                 $style.$attr = Style::default().$attr;
@@ -136,8 +139,9 @@ macro_rules! apply {
             PropertyValue::Exact(None) => {
                 $style.$attr = None;
             }
-            PropertyValue::Exact(Some(value)) => {
-                $style.$attr = Some(Arc::clone(value));
+            PropertyValue::Exact(Some(values)) => {
+                let color = $style.color.clone();
+                $style.$attr = Some(Arc::new(values.iter().map(|value| value.resolve_color(&color)).collect()));
             }
             _ => {
                 if cfg!(debug_assertions) {
@@ -196,17 +200,37 @@ macro_rules! apply {
                     $style.$attr = parent.$attr;
                 }
             }
-            PropertyValue::Exact(value) => match value {
-                Length::Em(value) => {
-                    $style.$attr = $style.font_size * value;
+            PropertyValue::Exact(value) => {
+                $style.$attr = value.resolve($style.font_size, $style.root_font_size, $style.viewport_size) as f32;
+            }
+            _ => {
+                if cfg!(debug_assertions) {
+                    eprintln!("[Rosin] Failed to apply CSS property: {}", stringify!($attr));
                 }
-                Length::Px(value) => {
-                    $style.$attr = *value;
+            }
+        }
+    };
+    (@font_size, $value:expr, $style:expr, $parent_style:ident) => {
+        match $value {
+            PropertyValue::Initial => {
+                $style.font_size = Style::default().font_size;
+            }
+            PropertyValue::Inherit => {
+                if let Some(parent) = &$parent_style {
+                    $style.font_size = parent.font_size;
                 }
-            },
+            }
+            // Em is relative to the parent's font size, since the node's own is the one being set
+            PropertyValue::Exact(Length::Em(value)) => {
+                let basis = $parent_style.as_ref().map_or(Style::default().font_size, |parent| parent.font_size);
+                $style.font_size = basis * value;
+            }
+            PropertyValue::Exact(value) => {
+                $style.font_size = value.resolve($style.font_size, $style.root_font_size, $style.viewport_size) as f32;
+            }
             _ => {
                 if cfg!(debug_assertions) {
-                    eprintln!("[Rosin] Failed to apply CSS property: {}", stringify!($attr));
+                    eprintln!("[Rosin] Failed to apply CSS property: font_size");
                 }
             }
         }
@@ -222,14 +246,9 @@ macro_rules! apply {
                     $style.$attr = parent.$attr;
                 }
             }
-            PropertyValue::Exact(value) => match value {
-                Length::Em(value) => {
-                    $style.$attr = Some($style.font_size * value);
-                }
-                Length::Px(value) => {
-                    $style.$attr = Some(*value);
-                }
-            },
+            PropertyValue::Exact(value) => {
+                $style.$attr = Some(value.resolve($style.font_size, $style.root_font_size, $style.viewport_size) as f32);
+            }
         }
     };
     (@length_max, $value:expr, $style:expr, $parent_style:ident, $attr:ident) => {
@@ -243,14 +262,9 @@ macro_rules! apply {
                     $style.$attr = parent.$attr;
                 }
             }
-            PropertyValue::Exact(value) => match value {
-                Length::Em(value) => {
-                    $style.$attr = $style.font_size * value;
-                }
-                Length::Px(value) => {
-                    $style.$attr = *value;
-                }
-            },
+            PropertyValue::Exact(value) => {
+                $style.$attr = value.resolve($style.font_size, $style.root_font_size, $style.viewport_size) as f32;
+            }
         }
     };
     (@length_min, $value:expr, $style:expr, $parent_style:ident, $attr:ident) => {
@@ -264,14 +278,9 @@ macro_rules! apply {
                     $style.$attr = parent.$attr;
                 }
             }
-            PropertyValue::Exact(value) => match value {
-                Length::Em(value) => {
-                    $style.$attr = $style.font_size * value;
-                }
-                Length::Px(value) => {
-                    $style.$attr = *value;
-                }
-            },
+            PropertyValue::Exact(value) => {
+                $style.$attr = value.resolve($style.font_size, $style.root_font_size, $style.viewport_size) as f32;
+            }
         }
     };
     (@str, $value:expr, $style:expr, $parent_style:ident, $attr:ident) => {
@@ -294,6 +303,49 @@ macro_rules! apply {
 }
 
 impl Property {
+    // Whether the property uses viewport units, so it has to be applied again when the viewport is resized
+    pub(crate) fn is_viewport_relative(&self) -> bool {
+        match self {
+            Property::BorderBottomLeftRadius(PropertyValue::Exact(length))
+            | Property::BorderBottomRightRadius(PropertyValue::Exact(length))
+            | Property::BorderBottomWidth(PropertyValue::Exact(length))
+            | Property::BorderLeftWidth(PropertyValue::Exact(length))
+            | Property::BorderRightWidth(PropertyValue::Exact(length))
+            | Property::BorderTopLeftRadius(PropertyValue::Exact(length))
+            | Property::BorderTopRightRadius(PropertyValue::Exact(length))
+            | Property::BorderTopWidth(PropertyValue::Exact(length))
+            | Property::Bottom(PropertyValue::Exact(length))
+            | Property::FlexBasis(PropertyValue::Exact(length))
+            | Property::FontSize(PropertyValue::Exact(length))
+            | Property::Height(PropertyValue::Exact(length))
+            | Property::IntrinsicHeight(PropertyValue::Exact(Some(IntrinsicSize::FitContent(length))))
+            | Property::IntrinsicWidth(PropertyValue::Exact(Some(IntrinsicSize::FitContent(length))))
+            | Property::Left(PropertyValue::Exact(length))
+            | Property::MarginBottom(PropertyValue::Exact(length))
+            | Property::MarginLeft(PropertyValue::Exact(length))
+            | Property::MarginRight(PropertyValue::Exact(length))
+            | Property::MarginTop(PropertyValue::Exact(length))
+            | Property::MaxHeight(PropertyValue::Exact(length))
+            | Property::MaxWidth(PropertyValue::Exact(length))
+            | Property::MinHeight(PropertyValue::Exact(length))
+            | Property::MinWidth(PropertyValue::Exact(length))
+            | Property::PaddingBottom(PropertyValue::Exact(length))
+            | Property::PaddingLeft(PropertyValue::Exact(length))
+            | Property::PaddingRight(PropertyValue::Exact(length))
+            | Property::PaddingTop(PropertyValue::Exact(length))
+            | Property::Right(PropertyValue::Exact(length))
+            | Property::Top(PropertyValue::Exact(length))
+            | Property::Width(PropertyValue::Exact(length)) => length.is_viewport_relative(),
+            Property::BoxShadow(PropertyValue::Exact(Some(shadows))) => shadows.iter().any(|shadow| {
+                [shadow.offset_x, shadow.offset_y, shadow.blur, shadow.spread]
+                    .iter()
+                    .any(Length::is_viewport_relative)
+            }),
+            Property::ClipPath(PropertyValue::Exact(Some(clip_path))) => clip_path.is_viewport_relative(),
+            _ => false,
+        }
+    }
+
     #[allow(clippy::assign_op_pattern)]
     pub fn apply(&self, style: &mut Style, parent_style: &Option<Style>) {
         match self {
@@ -301,7 +353,7 @@ impl Property {
             Property::AlignItems(value) => apply!(@generic, value, style, parent_style, align_items),
             Property::AlignSelf(value) => apply!(@generic, value, style, parent_style, align_self),
             Property::BackgroundColor(value) => apply!(@color, value, style, parent_style, background_color),
-            Property::BackgroundImage(value) => apply!(@clone_opt, value, style, parent_style, background_image),
+            Property::BackgroundImage(value) => apply!(@current_color, value, style, parent_style, background_image),
             Property::BorderBottomColor(value) => apply!(@color, value, style, parent_style, border_bottom_color),
             Property::BorderBottomLeftRadius(value) => apply!(@length, value, style, parent_style, border_bottom_left_radius),
             Property::BorderBottomRightRadius(value) => apply!(@length, value, style, parent_style, border_bottom_right_radius),
@@ -315,7 +367,8 @@ impl Property {
             Property::BorderTopRightRadius(value) => apply!(@length, value, style, parent_style, border_top_right_radius),
             Property::BorderTopWidth(value) => apply!(@length, value, style, parent_style, border_top_width),
             Property::Bottom(value) => apply!(@length_opt, value, style, parent_style, bottom),
-            Property::BoxShadow(value) => apply!(@clone_opt, value, style, parent_style, box_shadow),
+            Property::BoxShadow(value) => apply!(@current_color, value, style, parent_style, box_shadow),
+            Property::ClipPath(value) => apply!(@generic, value, style, parent_style, clip_path),
             Property::Cursor(value) => apply!(@generic, value, style, parent_style, cursor),
             Property::Color(value) => apply!(@color, value, style, parent_style, color),
             Property::FlexBasis(value) => apply!(@length_opt, value, style, parent_style, flex_basis),
@@ -324,9 +377,11 @@ impl Property {
             Property::FlexShrink(value) => apply!(@generic, value, style, parent_style, flex_shrink),
             Property::FlexWrap(value) => apply!(@generic, value, style, parent_style, flex_wrap),
             Property::FontFamily(value) => apply!(@clone, value, style, parent_style, font_family),
-            Property::FontSize(value) => apply!(@length, value, style, parent_style, font_size),
+            Property::FontSize(value) => apply!(@font_size, value, style, parent_style),
             Property::FontWeight(value) => apply!(@generic, value, style, parent_style, font_weight),
             Property::Height(value) => apply!(@length_opt, value, style, parent_style, height),
+            Property::IntrinsicHeight(value) => apply!(@generic, value, style, parent_style, intrinsic_height),
+            Property::IntrinsicWidth(value) => apply!(@generic, value, style, parent_style, intrinsic_width),
             Property::JustifyContent(value) => apply!(@generic, value, style, parent_style, justify_content),
             Property::Left(value) => apply!(@length_opt, value, style, parent_style, left),
             Property::MarginBottom(value) => apply!(@length_opt, value, style, parent_style, margin_bottom),
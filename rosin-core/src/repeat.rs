@@ -0,0 +1,143 @@
+#![forbid(unsafe_code)]
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::prelude::*;
+
+/// Repeats an action while a key or button is held, with an initial delay and then a steady rate,
+/// instead of the operating system's key repeat settings.
+///
+/// Call `press()` when the key goes down, after doing the action once, and `release()` when it goes up.
+/// Keyboard events that the OS generates while a key is held have `repeat` set, so they can be ignored while using this.
+#[derive(Debug, Clone)]
+pub struct KeyRepeat {
+    delay: Duration,
+    interval: Duration,
+    // Increased on each press and release, so tasks from earlier presses stop
+    generation: Rc<Cell<u64>>,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(400), Duration::from_millis(50))
+    }
+}
+
+impl KeyRepeat {
+    pub fn new(delay: Duration, interval: Duration) -> Self {
+        Self {
+            delay,
+            interval: interval.max(Duration::from_millis(1)),
+            generation: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Start repeating `action`, first after the delay, and then once per interval until `release()` is called.
+    /// Pressing again restarts the delay.
+    pub fn press<S, H>(&self, ctx: &mut EventCtx<S, H>, action: impl Fn(&mut S) -> Phase + 'static) {
+        let generation = self.generation.get() + 1;
+        self.generation.set(generation);
+
+        let current = self.generation.clone();
+        let (delay, interval) = (self.delay, self.interval);
+        let elapsed = Cell::new(Duration::ZERO);
+        let fired = Cell::new(0u32);
+
+        ctx.start_animation(move |state, dt| {
+            if current.get() != generation {
+                return (Phase::Idle, ShouldStop::Yes);
+            }
+
+            // Catch up on any repeats that were due since the last frame
+            elapsed.set(elapsed.get() + dt);
+            let mut phase = Phase::Idle;
+            while elapsed.get() >= delay + interval * fired.get() {
+                fired.set(fired.get() + 1);
+                phase.update(action(state));
+            }
+            (phase, ShouldStop::No)
+        });
+    }
+
+    /// Stop repeating.
+    pub fn release(&self) {
+        self.generation.set(self.generation.get() + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callbacks::{PointerButton, RawPointerEvent};
+
+    struct State {
+        repeat: KeyRepeat,
+        count: u32,
+    }
+
+    fn view(_: &State) -> View<State, ()> {
+        View::default()
+            .event(On::PointerDown, |s: &mut State, ctx| {
+                s.repeat.press(ctx, |s: &mut State| {
+                    s.count += 1;
+                    Phase::Draw
+                });
+                Some(Phase::Idle)
+            })
+            .event(On::PointerUp, |s: &mut State, _| {
+                s.repeat.release();
+                Some(Phase::Idle)
+            })
+    }
+
+    fn frame(viewport: &mut Viewport<State, ()>, state: &mut State, millis: u64) {
+        viewport.animation_frame(state, Duration::from_millis(millis));
+    }
+
+    #[test]
+    fn repeats_after_the_delay_until_released() {
+        let mut state = State {
+            repeat: KeyRepeat::new(Duration::from_millis(400), Duration::from_millis(50)),
+            count: 0,
+        };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        let down = RawPointerEvent::at(5.0, 5.0).press(PointerButton::Left);
+        viewport.pointer_down(&mut state, down);
+        frame(&mut viewport, &mut state, 300);
+        assert_eq!(state.count, 0);
+        frame(&mut viewport, &mut state, 100);
+        assert_eq!(state.count, 1);
+
+        // A slow frame catches up on the repeats it missed
+        frame(&mut viewport, &mut state, 120);
+        assert_eq!(state.count, 3);
+
+        viewport.pointer_up(&mut state, down.release(PointerButton::Left));
+        frame(&mut viewport, &mut state, 500);
+        assert_eq!(state.count, 3);
+        assert!(!viewport.has_anim_tasks());
+    }
+
+    #[test]
+    fn pressing_again_restarts_the_delay() {
+        let mut state = State {
+            repeat: KeyRepeat::default(),
+            count: 0,
+        };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        let down = RawPointerEvent::at(5.0, 5.0).press(PointerButton::Left);
+        viewport.pointer_down(&mut state, down);
+        frame(&mut viewport, &mut state, 300);
+        viewport.pointer_down(&mut state, down);
+        frame(&mut viewport, &mut state, 300);
+        assert_eq!(state.count, 0);
+        frame(&mut viewport, &mut state, 100);
+        assert_eq!(state.count, 1);
+    }
+}
@@ -1,9 +1,12 @@
 #![forbid(unsafe_code)]
 
 use std::{
-    collections::HashMap,
-    fs,
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt, fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    thread,
     time::SystemTime,
 };
 
@@ -29,14 +32,112 @@ pub(crate) struct Resource<T> {
     pub data: T,
 }
 
+/// Identifies an asset requested with [`ResourceLoader::load_async`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AssetHandle(usize);
+
+/// Identifies a callback added with [`ResourceLoader::add_waker`], so it can be removed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WakerToken(u64);
+
+// The default size cap for loaded assets, in bytes
+const DEFAULT_CACHE_LIMIT: usize = 64 * 1024 * 1024;
+
+// Called on the loading thread when an asset finishes loading
+type Waker = Arc<dyn Fn() + Send + Sync>;
+
+#[derive(Debug)]
+struct Asset {
+    path: PathBuf,
+    data: Option<Arc<[u8]>>,
+    hash: u64,
+    loading: bool,
+    // Incremented for every load, so a slow load can't overwrite a newer one
+    generation: u64,
+    last_used: u64,
+}
+
+/// Loads and caches stylesheets and assets.
+///
+/// Assets are loaded on a background thread and kept in memory until the total size of the cache goes over its limit
+/// (64 MiB by default, see [`set_cache_limit`](Self::set_cache_limit)). Then the least recently used assets are evicted,
+/// where an asset is used when it's loaded or passed to [`get`](Self::get). The most recently loaded asset is never evicted,
+/// even if it's larger than the limit on its own. Handles stay valid after eviction, and calling
+/// [`load_async`](Self::load_async) with the same path loads the asset again.
 #[derive(Debug, Default, Clone)]
 pub struct ResourceLoader {
     inner: Arc<Mutex<ResourceLoaderInner>>,
 }
 
-#[derive(Debug, Default)]
 struct ResourceLoaderInner {
     style_sheets: HashMap<&'static str, Resource<Stylesheet>>,
+    assets: Vec<Asset>,
+    asset_paths: HashMap<PathBuf, AssetHandle>,
+    cache_size: usize,
+    cache_limit: usize,
+    clock: u64,
+    wakers: Vec<(WakerToken, Waker)>,
+    next_waker: u64,
+}
+
+impl Default for ResourceLoaderInner {
+    fn default() -> Self {
+        Self {
+            style_sheets: HashMap::new(),
+            assets: Vec::new(),
+            asset_paths: HashMap::new(),
+            cache_size: 0,
+            cache_limit: DEFAULT_CACHE_LIMIT,
+            clock: 0,
+            wakers: Vec::new(),
+            next_waker: 0,
+        }
+    }
+}
+
+impl fmt::Debug for ResourceLoaderInner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResourceLoaderInner")
+            .field("style_sheets", &self.style_sheets)
+            .field("assets", &self.assets)
+            .field("cache_size", &self.cache_size)
+            .field("cache_limit", &self.cache_limit)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ResourceLoaderInner {
+    // Mark an asset as the most recently used
+    fn touch(&mut self, handle: AssetHandle) {
+        self.clock += 1;
+        self.assets[handle.0].last_used = self.clock;
+    }
+
+    fn set_data(&mut self, handle: AssetHandle, data: Option<Arc<[u8]>>) {
+        let asset = &mut self.assets[handle.0];
+        self.cache_size -= asset.data.as_ref().map_or(0, |data| data.len());
+        self.cache_size += data.as_ref().map_or(0, |data| data.len());
+        asset.data = data;
+    }
+
+    // Drop the least recently used assets until the cache fits its limit
+    fn evict(&mut self, keep: Option<AssetHandle>) {
+        while self.cache_size > self.cache_limit {
+            let oldest = self
+                .assets
+                .iter()
+                .enumerate()
+                .filter(|(i, asset)| asset.data.is_some() && Some(AssetHandle(*i)) != keep)
+                .min_by_key(|(_, asset)| asset.last_used)
+                .map(|(i, _)| AssetHandle(i));
+
+            if let Some(handle) = oldest {
+                self.set_data(handle, None);
+            } else {
+                break;
+            }
+        }
+    }
 }
 
 impl ResourceLoader {
@@ -75,6 +176,146 @@ impl ResourceLoader {
         stylesheet
     }
 
+    // Force a resource to be reloaded from disk, even if it hasn't been modified. Assets are reloaded in the background
+    pub fn invalidate(&mut self, path: &str) -> Result<bool, std::io::Error> {
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(style_sheet) = inner.style_sheets.get_mut(path) {
+            // Static resources are compiled in, so there's nothing to reload
+            if style_sheet.last_modified.is_some() {
+                let contents = fs::read_to_string(path)?;
+                style_sheet.last_modified = Some(fs::metadata(path)?.modified()?);
+                style_sheet.data.reparse(&contents);
+                return Ok(true);
+            }
+        }
+
+        if let Some(&handle) = inner.asset_paths.get(Path::new(path)) {
+            drop(inner);
+            self.spawn_load(handle);
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Start loading a file on a background thread, and return a handle to get its contents once it's loaded.
+    ///
+    /// If the file is already cached, or currently loading, it isn't read again. When a load finishes,
+    /// the callbacks added with [`add_waker`](Self::add_waker) are called.
+    pub fn load_async(&mut self, path: impl AsRef<Path>) -> AssetHandle {
+        let path = path.as_ref();
+        let mut inner = self.inner.lock().unwrap();
+
+        let handle = if let Some(&handle) = inner.asset_paths.get(path) {
+            inner.touch(handle);
+            let asset = &inner.assets[handle.0];
+            if asset.data.is_some() || asset.loading {
+                return handle;
+            }
+            handle
+        } else {
+            let handle = AssetHandle(inner.assets.len());
+            inner.assets.push(Asset {
+                path: path.to_owned(),
+                data: None,
+                hash: 0,
+                loading: false,
+                generation: 0,
+                last_used: 0,
+            });
+            inner.asset_paths.insert(path.to_owned(), handle);
+            inner.touch(handle);
+            handle
+        };
+
+        drop(inner);
+        self.spawn_load(handle);
+        handle
+    }
+
+    /// Get the contents of an asset, or `None` if it's still loading, failed to load, or was evicted from the cache.
+    pub fn get(&self, handle: AssetHandle) -> Option<Arc<[u8]>> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.touch(handle);
+        inner.assets[handle.0].data.clone()
+    }
+
+    pub fn is_loading(&self, handle: AssetHandle) -> bool {
+        self.inner.lock().unwrap().assets[handle.0].loading
+    }
+
+    /// Set the maximum size of the asset cache in bytes, evicting assets if it's already over the limit.
+    pub fn set_cache_limit(&mut self, bytes: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.cache_limit = bytes;
+        inner.evict(None);
+    }
+
+    /// Add a callback that's called from the loading thread whenever an asset finishes loading.
+    /// Pass the returned token to [`remove_waker`](Self::remove_waker) once it's no longer needed.
+    pub fn add_waker(&mut self, waker: impl Fn() + Send + Sync + 'static) -> WakerToken {
+        let mut inner = self.inner.lock().unwrap();
+        let token = WakerToken(inner.next_waker);
+        inner.next_waker += 1;
+        inner.wakers.push((token, Arc::new(waker)));
+        token
+    }
+
+    pub fn remove_waker(&mut self, token: WakerToken) {
+        self.inner.lock().unwrap().wakers.retain(|(t, _)| *t != token);
+    }
+
+    fn spawn_load(&self, handle: AssetHandle) {
+        let (path, generation) = {
+            let mut inner = self.inner.lock().unwrap();
+            let asset = &mut inner.assets[handle.0];
+            asset.loading = true;
+            asset.generation += 1;
+            (asset.path.clone(), asset.generation)
+        };
+
+        let loader = self.inner.clone();
+        thread::spawn(move || {
+            let result = fs::read(&path);
+
+            let wakers = {
+                let mut inner = loader.lock().unwrap();
+                let asset = &mut inner.assets[handle.0];
+
+                // A newer load was started while this one was running
+                if asset.generation != generation {
+                    return;
+                }
+                asset.loading = false;
+
+                match result {
+                    Ok(bytes) => {
+                        let mut hasher = DefaultHasher::new();
+                        bytes.hash(&mut hasher);
+                        let hash = hasher.finish();
+
+                        // Keep the cached data if the contents are the same
+                        if asset.data.is_none() || asset.hash != hash {
+                            asset.hash = hash;
+                            inner.set_data(handle, Some(bytes.into()));
+                            inner.evict(Some(handle));
+                        }
+                    }
+                    Err(err) => {
+                        eprintln!("[Rosin] Failed to load {}: {}", path.display(), err);
+                    }
+                }
+
+                inner.wakers.iter().map(|(_, waker)| waker.clone()).collect::<Vec<Waker>>()
+            };
+
+            for waker in wakers {
+                waker();
+            }
+        });
+    }
+
     // Reload resources if they've been modified
     pub fn poll(&mut self) -> Result<bool, std::io::Error> {
         let style_sheets = &mut self.inner.lock().unwrap().style_sheets;
@@ -95,3 +336,169 @@ impl ResourceLoader {
         Ok(reloaded)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    struct State {
+        sheet: Stylesheet,
+        key: Key,
+    }
+
+    fn view(state: &State) -> View<State, ()> {
+        View::default()
+            .use_style_sheet(Some(state.sheet.clone()))
+            .add_child(View::default().key(state.key).add_classes("a"))
+    }
+
+    // The width that the stylesheet gives a node with the class `a`
+    fn width(sheet: &Stylesheet) -> Option<f32> {
+        let state = State {
+            sheet: sheet.clone(),
+            key: Key::new(),
+        };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+        viewport.get_style(state.key).unwrap().width
+    }
+
+    #[test]
+    fn invalidate_reloads_dynamic_css() {
+        let path = std::env::temp_dir().join(format!("rosin-invalidate-{}.css", std::process::id()));
+        fs::write(&path, ".a { width: 10px; }").unwrap();
+        let path: &'static str = Box::leak(path.to_string_lossy().into_owned().into_boxed_str());
+
+        let mut loader = ResourceLoader::default();
+        let sheet = loader.new_dynamic_css(path).unwrap();
+        assert_eq!(width(&sheet), Some(10.0));
+
+        // The same stylesheet is updated, even if the modified time didn't change
+        fs::write(path, ".a { width: 20px; }").unwrap();
+        assert!(loader.invalidate(path).unwrap());
+        assert_eq!(width(&sheet), Some(20.0));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalidate_skips_static_and_unknown_css() {
+        let mut loader = ResourceLoader::default();
+        let sheet = loader.new_static_css("static.css", ".a { width: 10px; }");
+
+        assert!(!loader.invalidate("static.css").unwrap());
+        assert!(!loader.invalidate("unknown.css").unwrap());
+        assert_eq!(width(&sheet), Some(10.0));
+    }
+
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rosin-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn wait(loader: &ResourceLoader, handle: AssetHandle) {
+        let start = std::time::Instant::now();
+        while loader.is_loading(handle) {
+            assert!(start.elapsed().as_secs() < 5, "asset took too long to load");
+            thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn load_async_hits_the_cache_until_invalidated() {
+        let path = temp_file("asset.txt", "first");
+        let mut loader = ResourceLoader::default();
+        let loads = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = loads.clone();
+        loader.add_waker(move || {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        });
+
+        let handle = loader.load_async(&path);
+        wait(&loader, handle);
+        assert_eq!(loader.get(handle).as_deref(), Some(&b"first"[..]));
+        assert_eq!(loads.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // A hit returns the cached data without reading the file again
+        fs::write(&path, "second").unwrap();
+        assert_eq!(loader.load_async(&path), handle);
+        assert!(!loader.is_loading(handle));
+        assert_eq!(loader.get(handle).as_deref(), Some(&b"first"[..]));
+
+        // After invalidating, the next load misses
+        assert!(loader.invalidate(path.to_str().unwrap()).unwrap());
+        wait(&loader, handle);
+        assert_eq!(loader.get(handle).as_deref(), Some(&b"second"[..]));
+        assert_eq!(loads.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_async_evicts_the_least_recently_used_asset() {
+        let paths = [temp_file("a.txt", "aaaa"), temp_file("b.txt", "bbbb"), temp_file("c.txt", "cccc")];
+        let mut loader = ResourceLoader::default();
+        loader.set_cache_limit(10);
+
+        let a = loader.load_async(&paths[0]);
+        wait(&loader, a);
+        let b = loader.load_async(&paths[1]);
+        wait(&loader, b);
+
+        // Use `a`, so `b` is the oldest when `c` goes over the limit
+        assert!(loader.get(a).is_some());
+        let c = loader.load_async(&paths[2]);
+        wait(&loader, c);
+
+        assert_eq!(loader.get(a).as_deref(), Some(&b"aaaa"[..]));
+        assert_eq!(loader.get(b), None);
+        assert_eq!(loader.get(c).as_deref(), Some(&b"cccc"[..]));
+
+        // Loading an evicted asset again reuses its handle
+        assert_eq!(loader.load_async(&paths[1]), b);
+        wait(&loader, b);
+        assert_eq!(loader.get(b).as_deref(), Some(&b"bbbb"[..]));
+        assert_eq!(loader.get(a), None);
+
+        for path in paths {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn removed_wakers_are_not_called() {
+        let path = temp_file("waker.txt", "data");
+        let mut loader = ResourceLoader::default();
+        let kept = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let removed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let (counter1, counter2) = (kept.clone(), removed.clone());
+        loader.add_waker(move || {
+            counter1.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        });
+        let token = loader.add_waker(move || {
+            counter2.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        });
+        loader.remove_waker(token);
+
+        let handle = loader.load_async(&path);
+        wait(&loader, handle);
+        let start = std::time::Instant::now();
+        while kept.load(std::sync::atomic::Ordering::SeqCst) == 0 {
+            assert!(start.elapsed().as_secs() < 5, "waker took too long to be called");
+            thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert_eq!(removed.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_async_reports_missing_files() {
+        let mut loader = ResourceLoader::default();
+        let handle = loader.load_async(std::env::temp_dir().join("rosin-missing-asset.txt"));
+        wait(&loader, handle);
+        assert_eq!(loader.get(handle), None);
+    }
+}
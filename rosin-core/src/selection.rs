@@ -0,0 +1,315 @@
+#![forbid(unsafe_code)]
+
+use std::collections::BTreeSet;
+
+use druid_shell::KbKey;
+use keyboard_types::Modifiers;
+
+/// How a selection gesture combines with what was already selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectMode {
+    /// Select only the new items.
+    Replace,
+    /// Add the new items to the selection, like holding Shift.
+    Extend,
+    /// Flip whether each new item is selected, like holding Ctrl, or Cmd on macOS.
+    Toggle,
+}
+
+impl SelectMode {
+    /// The mode for the modifiers held during a click or drag.
+    pub fn from_modifiers(mods: Modifiers) -> Self {
+        if mods.intersects(Modifiers::CONTROL | Modifiers::META) {
+            SelectMode::Toggle
+        } else if mods.contains(Modifiers::SHIFT) {
+            SelectMode::Extend
+        } else {
+            SelectMode::Replace
+        }
+    }
+
+    /// Combine the selection from before the gesture with the items it covers. Keeps the order of `initial`,
+    /// followed by any new items in the order of `items`.
+    pub fn apply<T: Copy + PartialEq>(self, initial: &[T], items: &[T]) -> Vec<T> {
+        match self {
+            SelectMode::Replace => items.to_vec(),
+            SelectMode::Extend => {
+                let mut result = initial.to_vec();
+                result.extend(items.iter().filter(|item| !initial.contains(item)));
+                result
+            }
+            SelectMode::Toggle => {
+                let mut result: Vec<T> = initial.iter().copied().filter(|item| !items.contains(item)).collect();
+                result.extend(items.iter().filter(|item| !initial.contains(item)));
+                result
+            }
+        }
+    }
+}
+
+/// The selected items of a list, by index, that follows the usual desktop rules for mouse and keyboard selection.
+///
+/// The anchor is where a range starts, and the lead is the item that has keyboard focus. Clicking selects one item
+/// and moves both there, Ctrl or Cmd clicking toggles an item, and Shift clicking selects the range from the anchor
+/// to the clicked item, along with anything that was selected with Ctrl before the anchor was set.
+/// The arrow keys, Home, and End move the lead the same way: on their own they select the new item, with Shift
+/// they extend the range, and with Ctrl or Cmd they only move the lead, so Ctrl+Space can toggle it.
+///
+/// Keep one in the state, call `click()` and `key()` from event callbacks, and read `selected()` when they return `true`.
+/// There's no change callback, since the selection is already in the state that the event callback has; return
+/// `Phase::Build` from it, or pass `selected()` on to the widget's own callback.
+#[derive(Debug, Clone, Default)]
+pub struct ListSelection {
+    len: usize,
+    selected: BTreeSet<usize>,
+    anchor: Option<usize>,
+    lead: Option<usize>,
+    // What stays selected when the range from the anchor changes
+    base: BTreeSet<usize>,
+}
+
+impl ListSelection {
+    pub fn new(len: usize) -> Self {
+        Self { len, ..Default::default() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Change the number of items, dropping any selection past the end.
+    pub fn set_len(&mut self, len: usize) {
+        self.len = len;
+        self.selected.retain(|&i| i < len);
+        self.base.retain(|&i| i < len);
+        self.anchor = self.anchor.filter(|&i| i < len);
+        self.lead = self.lead.filter(|&i| i < len);
+    }
+
+    /// The selected indices, in order.
+    pub fn selected(&self) -> impl Iterator<Item = usize> + '_ {
+        self.selected.iter().copied()
+    }
+
+    pub fn is_selected(&self, index: usize) -> bool {
+        self.selected.contains(&index)
+    }
+
+    pub fn count(&self) -> usize {
+        self.selected.len()
+    }
+
+    /// Where the next Shift range starts.
+    pub fn anchor(&self) -> Option<usize> {
+        self.anchor
+    }
+
+    /// The item with keyboard focus, which is the last one clicked or moved to.
+    pub fn lead(&self) -> Option<usize> {
+        self.lead
+    }
+
+    /// Select only `index`, and make it the anchor and lead.
+    pub fn select(&mut self, index: usize) -> bool {
+        if index >= self.len {
+            return false;
+        }
+        let old = std::mem::take(&mut self.selected);
+        self.selected.insert(index);
+        self.base.clear();
+        self.anchor = Some(index);
+        self.lead = Some(index);
+        old != self.selected
+    }
+
+    /// Flip whether `index` is selected, and make it the anchor and lead.
+    pub fn toggle(&mut self, index: usize) -> bool {
+        if index >= self.len {
+            return false;
+        }
+        if !self.selected.remove(&index) {
+            self.selected.insert(index);
+        }
+        self.base = self.selected.clone();
+        self.base.remove(&index);
+        self.anchor = Some(index);
+        self.lead = Some(index);
+        true
+    }
+
+    /// Select the range from the anchor to `index`, and make `index` the lead. The anchor stays where it is.
+    pub fn extend_to(&mut self, index: usize) -> bool {
+        if index >= self.len {
+            return false;
+        }
+        let anchor = *self.anchor.get_or_insert(index);
+        let range = anchor.min(index)..=anchor.max(index);
+
+        let old = std::mem::take(&mut self.selected);
+        self.selected = self.base.iter().copied().chain(range).collect();
+        self.lead = Some(index);
+        old != self.selected
+    }
+
+    /// Move the lead to `index` without changing the selection.
+    pub fn set_lead(&mut self, index: usize) {
+        if index < self.len {
+            self.lead = Some(index);
+        }
+    }
+
+    /// Select every item. Later Shift ranges start from the lead, and replace the selection instead of adding to it.
+    pub fn select_all(&mut self) -> bool {
+        let old = self.selected.len();
+        self.selected = (0..self.len).collect();
+        self.base.clear();
+        if self.len > 0 {
+            let lead = *self.lead.get_or_insert(0);
+            self.anchor = Some(lead);
+        }
+        old != self.selected.len()
+    }
+
+    pub fn clear(&mut self) -> bool {
+        self.base.clear();
+        self.anchor = None;
+        !std::mem::take(&mut self.selected).is_empty()
+    }
+
+    /// Handle a click on the item at `index`. Returns `true` if the selection changed.
+    pub fn click(&mut self, index: usize, mods: Modifiers) -> bool {
+        if mods.contains(Modifiers::SHIFT) {
+            self.extend_to(index)
+        } else if mods.intersects(Modifiers::CONTROL | Modifiers::META) {
+            self.toggle(index)
+        } else {
+            self.select(index)
+        }
+    }
+
+    /// Handle a key press. Returns `true` if the selection changed. Keys that don't move the lead are ignored.
+    pub fn key(&mut self, key: &KbKey, mods: Modifiers) -> bool {
+        if self.len == 0 {
+            return false;
+        }
+        let command = mods.intersects(Modifiers::CONTROL | Modifiers::META);
+        let last = self.len - 1;
+
+        let target = match key {
+            KbKey::ArrowUp | KbKey::ArrowLeft => self.lead.map_or(0, |lead| lead.saturating_sub(1)),
+            KbKey::ArrowDown | KbKey::ArrowRight => self.lead.map_or(0, |lead| (lead + 1).min(last)),
+            KbKey::Home => 0,
+            KbKey::End => last,
+            KbKey::Character(c) if command && c.eq_ignore_ascii_case("a") => return self.select_all(),
+            KbKey::Character(c) if command && c == " " => return self.toggle(self.lead.unwrap_or(0)),
+            _ => return false,
+        };
+
+        if mods.contains(Modifiers::SHIFT) {
+            self.extend_to(target)
+        } else if command {
+            self.set_lead(target);
+            false
+        } else {
+            self.select(target)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selected(selection: &ListSelection) -> Vec<usize> {
+        selection.selected().collect()
+    }
+
+    #[test]
+    fn shift_range_grows_and_shrinks_around_the_anchor() {
+        let mut selection = ListSelection::new(10);
+        selection.click(3, Modifiers::empty());
+
+        assert!(selection.click(5, Modifiers::SHIFT));
+        assert_eq!(selected(&selection), [3, 4, 5]);
+        assert!(selection.key(&KbKey::ArrowUp, Modifiers::SHIFT));
+        assert_eq!(selected(&selection), [3, 4]);
+        assert!(selection.click(1, Modifiers::SHIFT));
+        assert_eq!(selected(&selection), [1, 2, 3]);
+        assert!(selection.key(&KbKey::Home, Modifiers::SHIFT));
+        assert_eq!(selected(&selection), [0, 1, 2, 3]);
+
+        assert_eq!(selection.anchor(), Some(3));
+        assert_eq!(selection.lead(), Some(0));
+    }
+
+    #[test]
+    fn ctrl_toggle_keeps_the_rest_through_shift_ranges() {
+        let mut selection = ListSelection::new(10);
+        selection.click(1, Modifiers::empty());
+        assert!(selection.click(4, Modifiers::CONTROL));
+        assert_eq!(selected(&selection), [1, 4]);
+
+        // The range from the toggled item replaces itself, but keeps what was selected before it
+        selection.click(6, Modifiers::SHIFT);
+        assert_eq!(selected(&selection), [1, 4, 5, 6]);
+        selection.click(2, Modifiers::SHIFT);
+        assert_eq!(selected(&selection), [1, 2, 3, 4]);
+
+        assert!(selection.click(1, Modifiers::META));
+        assert_eq!(selected(&selection), [2, 3, 4]);
+        assert_eq!(selection.anchor(), Some(1));
+    }
+
+    #[test]
+    fn ctrl_arrows_only_move_the_lead() {
+        let mut selection = ListSelection::new(5);
+        selection.click(2, Modifiers::empty());
+
+        assert!(!selection.key(&KbKey::ArrowDown, Modifiers::CONTROL));
+        assert_eq!(selection.lead(), Some(3));
+        assert_eq!(selected(&selection), [2]);
+
+        assert!(selection.key(&KbKey::Character(" ".into()), Modifiers::CONTROL));
+        assert_eq!(selected(&selection), [2, 3]);
+
+        assert!(selection.key(&KbKey::ArrowDown, Modifiers::empty()));
+        assert_eq!(selected(&selection), [4]);
+    }
+
+    #[test]
+    fn select_all_resets_the_range() {
+        let mut selection = ListSelection::new(8);
+        selection.click(2, Modifiers::empty());
+        selection.click(5, Modifiers::CONTROL);
+
+        assert!(selection.key(&KbKey::Character("A".into()), Modifiers::CONTROL));
+        assert_eq!(selection.count(), 8);
+        assert_eq!(selection.anchor(), Some(5));
+
+        // Toggled items from before don't come back
+        selection.click(6, Modifiers::SHIFT);
+        assert_eq!(selected(&selection), [5, 6]);
+    }
+
+    #[test]
+    fn set_len_drops_items_past_the_end() {
+        let mut selection = ListSelection::new(10);
+        selection.click(1, Modifiers::empty());
+        selection.click(4, Modifiers::CONTROL);
+        selection.click(8, Modifiers::SHIFT);
+
+        selection.set_len(6);
+        assert_eq!(selected(&selection), [1, 4, 5]);
+        assert_eq!(selection.anchor(), Some(4));
+        assert_eq!(selection.lead(), None);
+
+        // The range still starts from the anchor, and keeps the toggled item
+        selection.click(2, Modifiers::SHIFT);
+        assert_eq!(selected(&selection), [1, 2, 3, 4]);
+        assert!(!selection.click(9, Modifiers::empty()));
+    }
+}
@@ -2,9 +2,12 @@
 
 use crate::geometry::*;
 
-use druid_shell::piet::{self, UnitPoint};
+use druid_shell::{
+    kurbo::{self, Shape},
+    piet::{self, UnitPoint},
+};
 
-use std::{f32::consts::TAU, sync::Arc};
+use std::{f32::consts::TAU, f64::consts::SQRT_2, sync::Arc};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AlignContent {
@@ -24,16 +27,136 @@ pub enum AlignItems {
     FlexEnd,
 }
 
+/// A box shadow. Its color is concrete once it's in a `Style`, while a declared shadow has `None` for currentColor.
 #[derive(Debug, Default, Clone)]
-pub struct BoxShadow {
+pub struct BoxShadow<C = piet::Color> {
     pub offset_x: Length,
     pub offset_y: Length,
     pub blur: Length,
     pub spread: Length,
-    pub color: Option<piet::Color>,
+    pub color: C,
     pub inset: bool,
 }
 
+impl BoxShadow<Option<piet::Color>> {
+    // Use the node's color for currentColor
+    pub(crate) fn resolve_color(&self, current_color: &piet::Color) -> BoxShadow {
+        BoxShadow {
+            offset_x: self.offset_x,
+            offset_y: self.offset_y,
+            blur: self.blur,
+            spread: self.spread,
+            color: self.color.clone().unwrap_or_else(|| current_color.clone()),
+            inset: self.inset,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClipPath {
+    Inset {
+        top: LengthPercentage,
+        right: LengthPercentage,
+        bottom: LengthPercentage,
+        left: LengthPercentage,
+        // Top left, top right, bottom right, bottom left. Percentages are of the inset box's smaller side,
+        // since the corners are always circular.
+        radii: [LengthPercentage; 4],
+    },
+    Circle {
+        radius: ShapeRadius,
+        x: LengthPercentage,
+        y: LengthPercentage,
+    },
+    Ellipse {
+        radius_x: ShapeRadius,
+        radius_y: ShapeRadius,
+        x: LengthPercentage,
+        y: LengthPercentage,
+    },
+}
+
+impl ClipPath {
+    /// Whether any length in the shape depends on the size of the viewport.
+    pub fn is_viewport_relative(&self) -> bool {
+        match self {
+            ClipPath::Inset {
+                top,
+                right,
+                bottom,
+                left,
+                radii,
+            } => [top, right, bottom, left]
+                .into_iter()
+                .chain(radii)
+                .any(LengthPercentage::is_viewport_relative),
+            ClipPath::Circle { radius, x, y } => radius.is_viewport_relative() || x.is_viewport_relative() || y.is_viewport_relative(),
+            ClipPath::Ellipse { radius_x, radius_y, x, y } => {
+                radius_x.is_viewport_relative() || radius_y.is_viewport_relative() || x.is_viewport_relative() || y.is_viewport_relative()
+            }
+        }
+    }
+
+    // Build the clipping path for a box with the given size
+    pub fn resolve(&self, font_size: f32, root_font_size: f32, viewport_size: (f32, f32), width: f32, height: f32) -> kurbo::BezPath {
+        let width = width as f64;
+        let height = height as f64;
+
+        match self {
+            ClipPath::Inset {
+                top,
+                right,
+                bottom,
+                left,
+                radii,
+            } => {
+                let x0 = left.resolve(font_size, root_font_size, viewport_size, width);
+                let y0 = top.resolve(font_size, root_font_size, viewport_size, height);
+                let x1 = (width - right.resolve(font_size, root_font_size, viewport_size, width)).max(x0);
+                let y1 = (height - bottom.resolve(font_size, root_font_size, viewport_size, height)).max(y0);
+                let basis = (x1 - x0).min(y1 - y0);
+                let radii = (
+                    radii[0].resolve(font_size, root_font_size, viewport_size, basis),
+                    radii[1].resolve(font_size, root_font_size, viewport_size, basis),
+                    radii[2].resolve(font_size, root_font_size, viewport_size, basis),
+                    radii[3].resolve(font_size, root_font_size, viewport_size, basis),
+                );
+                kurbo::RoundedRect::new(x0, y0, x1, y1, radii).to_path(0.1)
+            }
+            ClipPath::Circle { radius, x, y } => {
+                let cx = x.resolve(font_size, root_font_size, viewport_size, width);
+                let cy = y.resolve(font_size, root_font_size, viewport_size, height);
+                let closest = cx.min(width - cx).min(cy).min(height - cy).abs();
+                let farthest = cx.max(width - cx).max(cy).max(height - cy).abs();
+                let basis = width.hypot(height) / SQRT_2;
+                let radius = radius.resolve(font_size, root_font_size, viewport_size, basis, closest, farthest);
+                kurbo::Circle::new((cx, cy), radius).to_path(0.1)
+            }
+            ClipPath::Ellipse { radius_x, radius_y, x, y } => {
+                let cx = x.resolve(font_size, root_font_size, viewport_size, width);
+                let cy = y.resolve(font_size, root_font_size, viewport_size, height);
+                let radius_x = radius_x.resolve(
+                    font_size,
+                    root_font_size,
+                    viewport_size,
+                    width,
+                    cx.min(width - cx).abs(),
+                    cx.max(width - cx).abs(),
+                );
+                let radius_y = radius_y.resolve(
+                    font_size,
+                    root_font_size,
+                    viewport_size,
+                    height,
+                    cy.min(height - cy).abs(),
+                    cy.max(height - cy).abs(),
+                );
+                kurbo::Ellipse::new((cx, cy), (radius_x, radius_y), 0.0).to_path(0.1)
+            }
+        }
+    }
+}
+
 #[allow(non_camel_case_types)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Cursor {
@@ -115,6 +238,23 @@ pub enum JustifyContent {
     SpaceEvenly,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum GradientColor {
+    Color(piet::Color),
+    CurrentColor,
+    // Color hints take the color halfway between their neighbors
+    Hint,
+}
+
+impl From<cssparser::Color> for GradientColor {
+    fn from(color: cssparser::Color) -> Self {
+        match color {
+            cssparser::Color::CurrentColor => GradientColor::CurrentColor,
+            cssparser::Color::RGBA(rgba) => GradientColor::Color(piet::Color::rgba8(rgba.red, rgba.green, rgba.blue, rgba.alpha)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum GradientAngle {
     Top,
@@ -129,10 +269,37 @@ pub enum GradientAngle {
     Radians(f32),
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum IntrinsicSize {
+    MinContent,
+    MaxContent,
+    FitContent(Length),
+}
+
+impl IntrinsicSize {
+    /// Pick a size between the min-content and max-content sizes of a node.
+    #[inline]
+    pub fn resolve(&self, font_size: f32, root_font_size: f32, viewport_size: (f32, f32), min_content: f32, max_content: f32) -> f32 {
+        match self {
+            IntrinsicSize::MinContent => min_content,
+            IntrinsicSize::MaxContent => max_content,
+            IntrinsicSize::FitContent(limit) => (limit.resolve(font_size, root_font_size, viewport_size) as f32)
+                .max(min_content)
+                .min(max_content),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Length {
     Px(f32),
     Em(f32),
+    Rem(f32),
+    // Viewport units are percentages of the viewport's logical size, so 1vw is 1% of its width
+    Vw(f32),
+    Vh(f32),
+    Vmin(f32),
+    Vmax(f32),
 }
 
 impl Default for Length {
@@ -143,18 +310,83 @@ impl Default for Length {
 
 impl Length {
     #[inline]
-    pub fn resolve(&self, font_size: f32) -> f64 {
+    pub fn resolve(&self, font_size: f32, root_font_size: f32, viewport_size: (f32, f32)) -> f64 {
         match self {
-            Length::Em(value) => (font_size * value) as f64,
             Length::Px(value) => *value as f64,
+            Length::Em(value) => (font_size * value) as f64,
+            Length::Rem(value) => (root_font_size * value) as f64,
+            Length::Vw(value) => (viewport_size.0 * value / 100.0) as f64,
+            Length::Vh(value) => (viewport_size.1 * value / 100.0) as f64,
+            Length::Vmin(value) => (viewport_size.0.min(viewport_size.1) * value / 100.0) as f64,
+            Length::Vmax(value) => (viewport_size.0.max(viewport_size.1) * value / 100.0) as f64,
+        }
+    }
+
+    /// Whether this is a viewport unit, so it changes when the viewport is resized.
+    pub fn is_viewport_relative(&self) -> bool {
+        matches!(self, Length::Vw(_) | Length::Vh(_) | Length::Vmin(_) | Length::Vmax(_))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LengthPercentage {
+    Length(Length),
+    Percent(f32),
+}
+
+impl LengthPercentage {
+    #[inline]
+    pub fn resolve(&self, font_size: f32, root_font_size: f32, viewport_size: (f32, f32), basis: f64) -> f64 {
+        match self {
+            LengthPercentage::Length(length) => length.resolve(font_size, root_font_size, viewport_size),
+            LengthPercentage::Percent(value) => basis * *value as f64,
         }
     }
+
+    pub fn is_viewport_relative(&self) -> bool {
+        matches!(self, LengthPercentage::Length(length) if length.is_viewport_relative())
+    }
 }
 
+/// A linear gradient. Its stops have concrete colors once it's in a `Style`, while a declared gradient can have
+/// currentColor and color hints.
 #[derive(Debug, Clone)]
-pub struct LinearGradient {
+pub struct LinearGradient<C = piet::Color> {
     pub angle: GradientAngle,
-    pub gradient_stops: Vec<piet::GradientStop>,
+    pub gradient_stops: Vec<(f32, C)>,
+}
+
+impl LinearGradient<GradientColor> {
+    // Use the node's color for currentColor, and give hints the color halfway between their neighbors
+    pub(crate) fn resolve_color(&self, current_color: &piet::Color) -> LinearGradient {
+        let mut gradient_stops: Vec<(f32, piet::Color)> = self
+            .gradient_stops
+            .iter()
+            .map(|(pos, color)| match color {
+                GradientColor::Color(color) => (*pos, color.clone()),
+                GradientColor::CurrentColor | GradientColor::Hint => (*pos, current_color.clone()),
+            })
+            .collect();
+
+        // The parser makes sure hints are never the first or last stop
+        for i in 1..gradient_stops.len() - 1 {
+            if self.gradient_stops[i].1 == GradientColor::Hint {
+                let l = gradient_stops[i - 1].1.as_rgba8();
+                let r = gradient_stops[i + 1].1.as_rgba8();
+                gradient_stops[i].1 = piet::Color::rgba8(
+                    (l.0 / 2) + (r.0 / 2),
+                    (l.1 / 2) + (r.1 / 2),
+                    (l.2 / 2) + (r.2 / 2),
+                    (l.3 / 2) + (r.3 / 2),
+                );
+            }
+        }
+
+        LinearGradient {
+            angle: self.angle.clone(),
+            gradient_stops,
+        }
+    }
 }
 
 impl LinearGradient {
@@ -244,7 +476,16 @@ impl LinearGradient {
             }
         }
 
-        piet::LinearGradient::new(start_point, end_point, &*self.gradient_stops)
+        let gradient_stops: Vec<piet::GradientStop> = self
+            .gradient_stops
+            .iter()
+            .map(|(pos, color)| piet::GradientStop {
+                pos: *pos,
+                color: color.clone(),
+            })
+            .collect();
+
+        piet::LinearGradient::new(start_point, end_point, &*gradient_stops)
     }
 }
 
@@ -255,6 +496,28 @@ pub enum Position {
     Fixed,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ShapeRadius {
+    Length(LengthPercentage),
+    ClosestSide,
+    FarthestSide,
+}
+
+impl ShapeRadius {
+    #[inline]
+    pub fn resolve(&self, font_size: f32, root_font_size: f32, viewport_size: (f32, f32), basis: f64, closest: f64, farthest: f64) -> f64 {
+        match self {
+            ShapeRadius::Length(length) => length.resolve(font_size, root_font_size, viewport_size, basis).max(0.0),
+            ShapeRadius::ClosestSide => closest,
+            ShapeRadius::FarthestSide => farthest,
+        }
+    }
+
+    pub fn is_viewport_relative(&self) -> bool {
+        matches!(self, ShapeRadius::Length(length) if length.is_viewport_relative())
+    }
+}
+
 /// Computed style properties of a Node.
 #[derive(Debug, Clone)]
 pub struct Style {
@@ -277,6 +540,7 @@ pub struct Style {
     pub border_top_width: f32,
     pub bottom: Option<f32>,
     pub box_shadow: Option<Arc<Vec<BoxShadow>>>,
+    pub clip_path: Option<ClipPath>,
     pub color: piet::Color,
     pub cursor: Cursor,
     pub flex_basis: Option<f32>,
@@ -286,8 +550,12 @@ pub struct Style {
     pub flex_wrap: FlexWrap,
     pub font_family: Option<Arc<str>>,
     pub font_size: f32,
+    pub root_font_size: f32,
+    pub viewport_size: (f32, f32),
     pub font_weight: u32,
     pub height: Option<f32>,
+    pub intrinsic_height: Option<IntrinsicSize>,
+    pub intrinsic_width: Option<IntrinsicSize>,
     pub justify_content: JustifyContent,
     pub left: Option<f32>,
     pub margin_bottom: Option<f32>,
@@ -333,6 +601,7 @@ impl Default for Style {
             border_top_width: 0.0,
             bottom: None,
             box_shadow: None,
+            clip_path: None,
             color: piet::Color::rgba8(0, 0, 0, 255),
             cursor: Cursor::Default,
             flex_basis: None,
@@ -342,8 +611,12 @@ impl Default for Style {
             flex_wrap: FlexWrap::NoWrap,
             font_family: None,
             font_size: 0.0,
+            root_font_size: 0.0,
+            viewport_size: (0.0, 0.0),
             font_weight: 400,
             height: None,
+            intrinsic_height: None,
+            intrinsic_width: None,
             justify_content: JustifyContent::FlexStart,
             left: None,
             margin_bottom: Some(0.0),
@@ -15,7 +15,7 @@ use std::cmp::Ordering;
 use std::sync::Arc;
 use std::sync::RwLock;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Selector {
     // Represents a `*` selector
     Wildcard,
@@ -37,6 +37,24 @@ pub enum Selector {
 
     // Represents a `:focus` selector
     Focus,
+
+    // Represents a `:checked` selector
+    Checked,
+
+    // Represents an `:indeterminate` selector
+    Indeterminate,
+
+    // Represents a `:selected` selector
+    Selected,
+
+    // Represents an `:empty` selector
+    Empty,
+
+    // Represents a `:is()` selector with a list of simple selectors
+    Is(Vec<Selector>),
+
+    // Represents a `:not()` selector with a list of simple selectors
+    Not(Vec<Selector>),
 }
 
 impl Selector {
@@ -44,15 +62,39 @@ impl Selector {
     pub(crate) fn check<S, H>(&self, node: &ArrayNode<S, H>) -> bool {
         match self {
             Selector::Wildcard => true,
-            Selector::Id(selector) | Selector::Class(selector) => node.classes.iter().any(|class| class == selector),
+            Selector::Id(selector) => node.id == Some(selector.as_str()),
+            Selector::Class(selector) => node.classes.iter().any(|class| class == selector),
+            Selector::Checked => node.checked,
+            Selector::Indeterminate => node.indeterminate,
+            Selector::Selected => node.selected,
+            Selector::Empty => node.num_children == 0,
             _ => false,
         }
     }
+
+    // Check if any simple selector in a `:is()` or `:not()` list applies to a node
+    pub(crate) fn check_any<S, H>(selectors: &[Selector], node: &ArrayNode<S, H>, hovered: bool, focused: bool) -> bool {
+        selectors.iter().any(|selector| match selector {
+            Selector::Hover => hovered,
+            Selector::Focus => focused,
+            _ => selector.check(node),
+        })
+    }
+}
+
+/// The light or dark appearance used to match `@media (prefers-color-scheme)` rules.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum ColorScheme {
+    #[default]
+    Light,
+    Dark,
 }
 
 #[derive(Debug, Clone)]
 pub struct Rule {
     pub specificity: u32,
+    pub important: bool,
+    pub color_scheme: Option<ColorScheme>,
     pub selectors: Vec<Selector>,
     pub properties: Vec<Property>,
 }
@@ -81,6 +123,8 @@ impl PartialOrd for Rule {
 struct StylesheetInner {
     dynamic_rules: Vec<Rule>,
     static_rules: Vec<Rule>,
+    // Does any rule use viewport units?
+    viewport_relative: bool,
 }
 
 #[derive(Default, Clone)]
@@ -95,8 +139,14 @@ impl std::fmt::Debug for Stylesheet {
 }
 
 impl Stylesheet {
+    /// Parse a stylesheet from UTF-8 bytes, such as a file loaded from an archive.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, std::str::Utf8Error> {
+        Ok(Self::parse(std::str::from_utf8(bytes)?))
+    }
+
     // Parse CSS text into rule list
     pub(crate) fn parse(text: &str) -> Self {
+        let text = strip_bom(text);
         let mut input = ParserInput::new(text);
         let mut parser = Parser::new(&mut input);
         let mut dynamic_rules = Vec::new();
@@ -104,11 +154,14 @@ impl Stylesheet {
 
         for result in RuleListParser::new_for_stylesheet(&mut parser, RulesParser) {
             match result {
-                Ok((true, rule)) => {
-                    dynamic_rules.push(rule);
-                }
-                Ok((false, rule)) => {
-                    static_rules.push(rule);
+                Ok(rules) => {
+                    for (dynamic, rule) in rules {
+                        if dynamic {
+                            dynamic_rules.push(rule);
+                        } else {
+                            static_rules.push(rule);
+                        }
+                    }
                 }
                 Err((_, _error)) => {
                     // TODO - print parsing errors when in debug mode
@@ -118,17 +171,20 @@ impl Stylesheet {
 
         dynamic_rules.sort();
         static_rules.sort();
+        let viewport_relative = is_viewport_relative(&dynamic_rules) || is_viewport_relative(&static_rules);
 
         Self {
             inner: Arc::new(RwLock::new(StylesheetInner {
                 dynamic_rules,
                 static_rules,
+                viewport_relative,
             })),
         }
     }
 
     pub(crate) fn reparse(&mut self, text: &str) {
         if let Ok(mut data) = self.inner.try_write() {
+            let text = strip_bom(text);
             let mut input = ParserInput::new(text);
             let mut parser = Parser::new(&mut input);
 
@@ -137,11 +193,14 @@ impl Stylesheet {
 
             for result in RuleListParser::new_for_stylesheet(&mut parser, RulesParser) {
                 match result {
-                    Ok((true, rule)) => {
-                        data.dynamic_rules.push(rule);
-                    }
-                    Ok((false, rule)) => {
-                        data.static_rules.push(rule);
+                    Ok(rules) => {
+                        for (dynamic, rule) in rules {
+                            if dynamic {
+                                data.dynamic_rules.push(rule);
+                            } else {
+                                data.static_rules.push(rule);
+                            }
+                        }
                     }
                     Err((_, _error)) => {
                         // TODO - print parsing errors when in debug mode
@@ -151,17 +210,41 @@ impl Stylesheet {
 
             data.dynamic_rules.sort();
             data.static_rules.sort();
+            data.viewport_relative = is_viewport_relative(&data.dynamic_rules) || is_viewport_relative(&data.static_rules);
         }
     }
+
+    // Whether any rule uses viewport units, so nodes using the sheet have to be restyled when the viewport is resized
+    pub(crate) fn is_viewport_relative(&self) -> bool {
+        self.inner.read().unwrap().viewport_relative
+    }
+}
+
+fn is_viewport_relative(rules: &[Rule]) -> bool {
+    rules.iter().any(|rule| rule.properties.iter().any(Property::is_viewport_relative))
+}
+
+// Some editors save a byte order mark, which would otherwise break the first rule
+fn strip_bom(text: &str) -> &str {
+    text.strip_prefix('\u{feff}').unwrap_or(text)
 }
 
 // Perform selector matching and apply styles to a tree
-pub(crate) fn apply_static_styles<S, H>(temp: &Bump, tree: &[ArrayNode<S, H>], styles: &mut BumpVec<'static, Style>) {
+pub(crate) fn apply_static_styles<S, H>(
+    temp: &Bump,
+    tree: &[ArrayNode<S, H>],
+    color_scheme: ColorScheme,
+    viewport_size: (f32, f32),
+    styles: &mut BumpVec<'static, Style>,
+) {
     let mut sheets = BumpVec::new_in(temp);
     let mut parent_id = usize::MAX;
 
     for id in 0..tree.len() {
-        styles.push(Style::default());
+        styles.push(Style {
+            viewport_size,
+            ..Style::default()
+        });
 
         // Re-use ancestor sheets from siblings to reduce walks up the tree
         if parent_id != tree[id].parent {
@@ -178,6 +261,10 @@ pub(crate) fn apply_static_styles<S, H>(temp: &Bump, tree: &[ArrayNode<S, H>], s
 
         // Find matching rules
         let rule_filter = |rule: &&Rule| {
+            if rule.color_scheme.map_or(false, |scheme| scheme != color_scheme) {
+                return false;
+            }
+
             let mut direct = false;
             let mut cmp_node = id;
             let mut first = true;
@@ -215,6 +302,27 @@ pub(crate) fn apply_static_styles<S, H>(temp: &Bump, tree: &[ArrayNode<S, H>], s
                             // Hover and Focus styles aren't applied in this step
                             return false;
                         }
+                        Selector::Checked | Selector::Indeterminate | Selector::Selected | Selector::Empty => {
+                            if selector.check(&tree[cmp_node]) {
+                                break; // Next selector
+                            } else {
+                                return false;
+                            }
+                        }
+                        Selector::Is(selectors) => {
+                            if Selector::check_any(selectors, &tree[cmp_node], false, false) {
+                                break; // Next selector
+                            } else {
+                                return false;
+                            }
+                        }
+                        Selector::Not(selectors) => {
+                            if !Selector::check_any(selectors, &tree[cmp_node], false, false) {
+                                break; // Next selector
+                            } else {
+                                return false;
+                            }
+                        }
                     }
                 }
             }
@@ -228,100 +336,120 @@ pub(crate) fn apply_static_styles<S, H>(temp: &Bump, tree: &[ArrayNode<S, H>], s
         let mut font_family_set = false;
         let mut color_set = false;
 
-        tree[id].style_sheet.as_ref().iter().chain(sheets.iter()).rev().for_each(|sheet| {
-            if font_size_set && font_family_set && color_set {
-                return;
-            }
-
-            let rule_action = |rule: &Rule| {
-                for property in rule.properties.iter().rev() {
-                    if font_size_set && font_family_set && color_set {
-                        return;
-                    }
-                    match property {
-                        Property::FontSize(value) => {
-                            if font_size_set {
-                                continue;
+        let mut properties_action = |properties: &[Property]| {
+            for property in properties.iter().rev() {
+                if font_size_set && font_family_set && color_set {
+                    return;
+                }
+                match property {
+                    Property::FontSize(value) => {
+                        if font_size_set {
+                            continue;
+                        }
+                        match value {
+                            PropertyValue::Inherit => {
+                                if let Some(parent) = &parent_style {
+                                    styles[id].font_size = parent.font_size;
+                                }
                             }
-                            match value {
-                                PropertyValue::Inherit => {
+                            PropertyValue::Exact(size) => match size {
+                                Length::Px(value) => {
+                                    styles[id].font_size = *value;
+                                }
+                                Length::Em(value) => {
                                     if let Some(parent) = &parent_style {
-                                        styles[id].font_size = parent.font_size;
+                                        styles[id].font_size = parent.font_size * value;
+                                    } else {
+                                        styles[id].font_size *= value;
                                     }
                                 }
-                                PropertyValue::Exact(size) => match size {
-                                    Length::Px(value) => {
-                                        styles[id].font_size = *value;
-                                    }
-                                    Length::Em(value) => {
-                                        if let Some(parent) = &parent_style {
-                                            styles[id].font_size = parent.font_size * value;
-                                        } else {
-                                            styles[id].font_size *= value;
-                                        }
+                                Length::Rem(value) => {
+                                    // On the root node, rem is relative to the initial font size
+                                    if let Some(parent) = &parent_style {
+                                        styles[id].font_size = parent.root_font_size * value;
+                                    } else {
+                                        styles[id].font_size *= value;
                                     }
-                                },
-                                _ => {}
-                            };
-                            font_size_set = true;
+                                }
+                                length => {
+                                    styles[id].font_size =
+                                        length.resolve(styles[id].font_size, styles[id].root_font_size, styles[id].viewport_size) as f32;
+                                }
+                            },
+                            _ => {}
+                        };
+                        font_size_set = true;
+                    }
+                    Property::FontFamily(value) => {
+                        if font_family_set {
+                            continue;
                         }
-                        Property::FontFamily(value) => {
-                            if font_family_set {
-                                continue;
+                        match value {
+                            PropertyValue::Exact(family) => {
+                                styles[id].font_family = Some(family.clone());
                             }
-                            match value {
-                                PropertyValue::Exact(family) => {
-                                    styles[id].font_family = Some(family.clone());
-                                }
-                                _ => {
-                                    // Inherited by default
-                                    if let Some(parent) = &parent_style {
-                                        styles[id].font_family = parent.font_family.clone();
-                                    }
+                            _ => {
+                                // Inherited by default
+                                if let Some(parent) = &parent_style {
+                                    styles[id].font_family = parent.font_family.clone();
                                 }
                             }
-                            font_family_set = true;
                         }
-                        Property::Color(value) => {
-                            if color_set {
-                                continue;
-                            }
-                            match value {
-                                PropertyValue::Initial => styles[id].color = Style::default().color,
-                                PropertyValue::Exact(color) => {
-                                    if let cssparser::Color::RGBA(rgba) = color {
-                                        styles[id].color = piet::Color::rgba8(rgba.red, rgba.green, rgba.blue, rgba.alpha);
-                                    }
+                        font_family_set = true;
+                    }
+                    Property::Color(value) => {
+                        if color_set {
+                            continue;
+                        }
+                        match value {
+                            PropertyValue::Initial => styles[id].color = Style::default().color,
+                            PropertyValue::Exact(color) => {
+                                if let cssparser::Color::RGBA(rgba) = color {
+                                    styles[id].color = piet::Color::rgba8(rgba.red, rgba.green, rgba.blue, rgba.alpha);
                                 }
-                                _ => {
-                                    // Inherited by default
-                                    if let Some(parent) = &parent_style {
-                                        styles[id].color = parent.color.clone();
-                                    }
+                            }
+                            _ => {
+                                // Inherited by default
+                                if let Some(parent) = &parent_style {
+                                    styles[id].color = parent.color.clone();
                                 }
                             }
-                            color_set = true;
                         }
-                        _ => {}
+                        color_set = true;
                     }
+                    _ => {}
                 }
-            };
+            }
+        };
 
-            sheet
-                .inner
-                .read()
-                .unwrap()
-                .static_rules
-                .iter()
-                .filter(rule_filter)
-                .for_each(rule_action);
-        });
+        // The first value found wins, so check important rules first
+        for important in [true, false] {
+            // Inline styles win over normal rules, but not important ones
+            if !important {
+                properties_action(&tree[id].inline_style);
+            }
+
+            tree[id].style_sheet.as_ref().iter().chain(sheets.iter()).rev().for_each(|sheet| {
+                sheet
+                    .inner
+                    .read()
+                    .unwrap()
+                    .static_rules
+                    .iter()
+                    .rev() // Later and more specific rules win
+                    .filter(|rule| rule.important == important)
+                    .filter(rule_filter)
+                    .for_each(|rule| properties_action(&rule.properties));
+            });
+        }
 
         if !font_size_set {
             if let Some(parent) = &parent_style {
                 styles[id].font_size = parent.font_size;
             }
         }
+        // Rem lengths resolve against the root's font size, so intermediate font sizes don't affect them
+        styles[id].root_font_size = parent_style.as_ref().map_or(styles[id].font_size, |parent| parent.root_font_size);
         if !font_family_set {
             if let Some(parent) = &parent_style {
                 styles[id].font_family = parent.font_family.clone();
@@ -333,31 +461,48 @@ pub(crate) fn apply_static_styles<S, H>(temp: &Bump, tree: &[ArrayNode<S, H>], s
             }
         }
 
-        tree[id].style_sheet.as_ref().iter().chain(sheets.iter()).for_each(|sheet| {
-            sheet
-                .inner
-                .read()
-                .unwrap()
-                .static_rules
-                .iter()
-                .filter(rule_filter)
-                .for_each(|rule| {
-                    for property in &rule.properties {
-                        match property {
-                            Property::FontSize(_) | Property::Color(_) | Property::FontFamily(_) => continue,
-                            _ => {}
-                        }
-                        property.apply(&mut styles[id], &parent_style);
+        // Important rules are applied last, so they override normal rules from any sheet
+        for important in [false, true] {
+            if important {
+                for property in &tree[id].inline_style {
+                    match property {
+                        Property::FontSize(_) | Property::Color(_) | Property::FontFamily(_) => continue,
+                        _ => {}
                     }
-                });
-        });
+                    property.apply(&mut styles[id], &parent_style);
+                }
+            }
+
+            tree[id].style_sheet.as_ref().iter().chain(sheets.iter()).for_each(|sheet| {
+                sheet
+                    .inner
+                    .read()
+                    .unwrap()
+                    .static_rules
+                    .iter()
+                    .filter(|rule| rule.important == important)
+                    .filter(rule_filter)
+                    .for_each(|rule| {
+                        for property in &rule.properties {
+                            match property {
+                                Property::FontSize(_) | Property::Color(_) | Property::FontFamily(_) => continue,
+                                _ => {}
+                            }
+                            property.apply(&mut styles[id], &parent_style);
+                        }
+                    });
+            });
+        }
     }
 }
 
-// Perform selector matching and apply dynamic styles to a tree
+// Perform selector matching and apply dynamic styles to a tree.
+// Normal `:hover` and `:focus` rules override static styles, inline styles override those, and `!important` rules,
+// static or dynamic, override everything, including inline styles.
 pub(crate) fn apply_dynamic_styles<S, H>(
     temp: &Bump,
     tree: &[ArrayNode<S, H>],
+    color_scheme: ColorScheme,
     focused_node: Option<Key>,
     hot_nodes: &[usize],
     styles: &mut [Style],
@@ -382,6 +527,10 @@ pub(crate) fn apply_dynamic_styles<S, H>(
 
         // Find matching rules
         let rule_filter = |rule: &&Rule| {
+            if rule.color_scheme.map_or(false, |scheme| scheme != color_scheme) {
+                return false;
+            }
+
             let mut direct = false;
             let mut cmp_node = id;
             let mut first = true;
@@ -433,6 +582,23 @@ pub(crate) fn apply_dynamic_styles<S, H>(
                                 return false;
                             }
                         }
+                        Selector::Checked | Selector::Indeterminate | Selector::Selected | Selector::Empty => {
+                            if selector.check(&tree[cmp_node]) {
+                                break; // Next selector
+                            } else {
+                                return false;
+                            }
+                        }
+                        Selector::Is(selectors) | Selector::Not(selectors) => {
+                            let hovered = hot_nodes.contains(&cmp_node);
+                            let focused = tree[cmp_node].key.is_some() && tree[cmp_node].key == focused_node;
+                            let matched = Selector::check_any(selectors, &tree[cmp_node], hovered, focused);
+                            if matched == matches!(selector, Selector::Is(_)) {
+                                break; // Next selector
+                            } else {
+                                return false;
+                            }
+                        }
                     }
                 }
             }
@@ -441,6 +607,7 @@ pub(crate) fn apply_dynamic_styles<S, H>(
 
         let parent_style: Option<Style> = if id == 0 { None } else { Some(styles[tree[id].parent].clone()) };
 
+        let mut restyled = false; // Did any dynamic rules apply to this node?
         tree[id].style_sheet.as_ref().iter().chain(sheets.iter()).for_each(|sheet| {
             sheet
                 .inner
@@ -448,8 +615,9 @@ pub(crate) fn apply_dynamic_styles<S, H>(
                 .unwrap()
                 .dynamic_rules
                 .iter()
+                .filter(|rule| !rule.important)
                 .filter(rule_filter)
-                .for_each(|rule| {
+                .for_each(|_| {
                     if let Some((default_id, _)) = &default_styles.last() {
                         if *default_id != id {
                             default_styles.push((id, styles[id].clone()));
@@ -457,11 +625,574 @@ pub(crate) fn apply_dynamic_styles<S, H>(
                     } else {
                         default_styles.push((id, styles[id].clone()));
                     }
+                    restyled = true;
+                });
+        });
 
-                    for property in &rule.properties {
+        // An important dynamic rule can match even if no normal one did
+        let important_matched = tree[id].style_sheet.as_ref().iter().chain(sheets.iter()).any(|sheet| {
+            sheet
+                .inner
+                .read()
+                .unwrap()
+                .dynamic_rules
+                .iter()
+                .filter(|rule| rule.important)
+                .any(|rule| rule_filter(&rule))
+        });
+        if important_matched && !restyled {
+            default_styles.push((id, styles[id].clone()));
+        }
+
+        if !restyled && !important_matched {
+            continue;
+        }
+
+        // Apply the matching properties from dynamic rules, then inline styles and important rules, since those still
+        // win over dynamic ones
+        let apply_dynamic = |style: &mut Style, filter: fn(&Property) -> bool| {
+            let apply_rule = |style: &mut Style, rule: &Rule| {
+                for property in rule.properties.iter().filter(|property| filter(property)) {
+                    property.apply(style, &parent_style);
+                }
+            };
+
+            tree[id].style_sheet.as_ref().iter().chain(sheets.iter()).for_each(|sheet| {
+                sheet
+                    .inner
+                    .read()
+                    .unwrap()
+                    .dynamic_rules
+                    .iter()
+                    .filter(|rule| !rule.important)
+                    .filter(rule_filter)
+                    .for_each(|rule| apply_rule(style, rule));
+            });
+
+            for property in tree[id].inline_style.iter().filter(|property| filter(property)) {
+                property.apply(style, &parent_style);
+            }
+
+            tree[id].style_sheet.as_ref().iter().chain(sheets.iter()).for_each(|sheet| {
+                let inner = sheet.inner.read().unwrap();
+                inner
+                    .static_rules
+                    .iter()
+                    .chain(inner.dynamic_rules.iter())
+                    .filter(|rule| rule.important)
+                    .filter(rule_filter)
+                    .for_each(|rule| apply_rule(style, rule));
+            });
+        };
+
+        // Find the color first, so currentColor resolves to the final value
+        let is_color = |property: &Property| matches!(property, Property::Color(_));
+        let prev_color = styles[id].color.clone();
+        apply_dynamic(&mut styles[id], is_color);
+
+        // Border colors, gradients, and shadows from static rules may use currentColor, so resolve them again in the same
+        // order as static styles
+        if styles[id].color != prev_color {
+            let uses_current_color = |property: &Property| {
+                matches!(
+                    property,
+                    Property::BorderTopColor(_)
+                        | Property::BorderRightColor(_)
+                        | Property::BorderBottomColor(_)
+                        | Property::BorderLeftColor(_)
+                        | Property::BackgroundImage(_)
+                        | Property::BoxShadow(_)
+                )
+            };
+
+            for important in [false, true] {
+                if important {
+                    for property in tree[id].inline_style.iter().filter(|property| uses_current_color(property)) {
                         property.apply(&mut styles[id], &parent_style);
                     }
+                }
+
+                tree[id].style_sheet.as_ref().iter().chain(sheets.iter()).for_each(|sheet| {
+                    sheet
+                        .inner
+                        .read()
+                        .unwrap()
+                        .static_rules
+                        .iter()
+                        .filter(|rule| rule.important == important)
+                        .filter(rule_filter)
+                        .for_each(|rule| {
+                            for property in rule.properties.iter().filter(|property| uses_current_color(property)) {
+                                property.apply(&mut styles[id], &parent_style);
+                            }
+                        });
+                });
+            }
+        }
+
+        apply_dynamic(&mut styles[id], |property| !matches!(property, Property::Color(_)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+
+    use super::*;
+    use crate::prelude::*;
+
+    struct State {
+        style: Stylesheet,
+        key: Key,
+        // The width and top border color of the keyed node after style callbacks saw it
+        width: Cell<Option<f32>>,
+        border: RefCell<Option<piet::Color>>,
+        // The colors of the keyed node's first shadow and first gradient stop after style callbacks saw it
+        shadow_and_gradient: RefCell<Option<(piet::Color, piet::Color)>>,
+        // More nodes, for tests that compare how rules match them
+        keys: [Key; 3],
+        checked: bool,
+    }
+
+    impl State {
+        fn new(css: &str) -> Self {
+            Self {
+                style: Stylesheet::parse(css),
+                key: Key::new(),
+                width: Cell::new(None),
+                border: RefCell::new(None),
+                shadow_and_gradient: RefCell::new(None),
+                keys: [Key::new(), Key::new(), Key::new()],
+                checked: false,
+            }
+        }
+    }
+
+    // Lay out the view, so styles are applied and the pointer can hit test it
+    fn viewport(state: &State, view: ViewCallback<State, ()>) -> Viewport<State, ()> {
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(state, None).unwrap();
+        viewport
+    }
+
+    // The width and height of a node
+    fn size(viewport: &Viewport<State, ()>, key: Key) -> (Option<f32>, Option<f32>) {
+        let style = viewport.get_style(key).unwrap();
+        (style.width, style.height)
+    }
+
+    fn hover(viewport: &mut Viewport<State, ()>, state: &mut State, x: f64, y: f64) {
+        viewport.pointer_move(state, RawPointerEvent::at(x, y));
+        viewport.draw(state, None).unwrap();
+    }
+
+    #[test]
+    fn important_hover_rule_overrides_inline_style() {
+        fn view(state: &State) -> View<State, ()> {
+            let node = View::default()
+                .key(state.key)
+                .add_classes("a")
+                .inline_style("width: 7px")
+                .on_style(|s: &State, style: &mut Style| s.width.set(style.width));
+            View::default().use_style_sheet(Some(state.style.clone())).add_child(node)
+        }
+
+        let mut state = State::new(".a { height: 50px; } .a:hover { width: 5px !important; }");
+        let mut viewport = viewport(&state, view);
+        assert_eq!(state.width.get(), Some(7.0));
+
+        hover(&mut viewport, &mut state, 1.0, 1.0);
+        assert_eq!(state.width.get(), Some(5.0));
+
+        // The dynamic style is undone after drawing
+        assert_eq!(viewport.get_style(state.key).unwrap().width, Some(7.0));
+    }
+
+    // A keyed node with the class `a`, that records its width after dynamic styles
+    fn keyed_view(state: &State) -> View<State, ()> {
+        let node = View::default()
+            .key(state.key)
+            .add_classes("a")
+            .on_style(|s: &State, style: &mut Style| s.width.set(style.width));
+        View::default().use_style_sheet(Some(state.style.clone())).add_child(node)
+    }
+
+    #[test]
+    fn media_rules_follow_the_color_scheme() {
+        let mut state = State::new(
+            ".a { width: 1px; height: 50px; }
+            @media (prefers-color-scheme: dark) {
+                .a { width: 2px; }
+                .a:hover { width: 3px; }
+            }
+            @media (prefers-color-scheme: light) {
+                .a:hover { width: 4px; }
+            }",
+        );
+        let mut viewport = viewport(&state, keyed_view);
+        assert_eq!(viewport.color_scheme(), ColorScheme::Light);
+        assert_eq!(state.width.get(), Some(1.0));
+        hover(&mut viewport, &mut state, 1.0, 1.0);
+        assert_eq!(state.width.get(), Some(4.0));
+
+        viewport.set_color_scheme(ColorScheme::Dark);
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(viewport.get_style(state.key).unwrap().width, Some(2.0));
+        hover(&mut viewport, &mut state, 2.0, 2.0);
+        assert_eq!(state.width.get(), Some(3.0));
+    }
+
+    #[test]
+    fn unsupported_media_queries_are_dropped() {
+        let state = State::new("@media (min-width: 10px) { .a { width: 1px; } } @media screen { .a { width: 2px; } }");
+        let viewport = viewport(&state, keyed_view);
+        assert_eq!(viewport.get_style(state.key).unwrap().width, None);
+    }
+
+    #[test]
+    fn border_current_color_follows_the_final_color() {
+        fn view(state: &State) -> View<State, ()> {
+            let node = View::default()
+                .key(state.key)
+                .add_classes("a")
+                .on_style(|s: &State, style: &mut Style| {
+                    s.border.replace(Some(style.border_top_color.clone()));
+                });
+            View::default().use_style_sheet(Some(state.style.clone())).add_child(node)
+        }
+
+        // The color is set after the border, and changed again by a later rule and a hover rule
+        let mut state = State::new(
+            ".a { height: 50px; border-color: currentColor; color: blue; }
+            .a { color: red; border-left-color: lime; }
+            .a:hover { color: yellow; }",
+        );
+        let mut viewport = viewport(&state, view);
+        let style = viewport.get_style(state.key).unwrap();
+        assert_eq!(style.border_top_color, piet::Color::RED);
+        assert_eq!(style.border_right_color, piet::Color::RED);
+        assert_eq!(style.border_bottom_color, piet::Color::RED);
+        assert_eq!(style.border_left_color, piet::Color::rgb8(0, 255, 0));
+
+        hover(&mut viewport, &mut state, 1.0, 1.0);
+        assert_eq!(*state.border.borrow(), Some(piet::Color::rgb8(255, 255, 0)));
+    }
+
+    #[test]
+    fn border_current_color_uses_the_inherited_color() {
+        let state = State::new(
+            ".root { color: red; }
+            .a { height: 50px; border-top-color: currentColor; }",
+        );
+        let viewport = viewport(&state, |state| {
+            let node = View::default().key(state.key).add_classes("a");
+            View::default()
+                .use_style_sheet(Some(state.style.clone()))
+                .add_classes("root")
+                .add_child(node)
+        });
+
+        let style = viewport.get_style(state.key).unwrap();
+        assert_eq!(style.border_top_color, piet::Color::RED);
+        assert_eq!(style.border_left_color, Style::default().border_left_color);
+    }
+
+    #[test]
+    fn explicit_border_color_ignores_a_hover_color() {
+        fn view(state: &State) -> View<State, ()> {
+            let node = View::default()
+                .key(state.key)
+                .add_classes("a")
+                .on_style(|s: &State, style: &mut Style| {
+                    s.border.replace(Some(style.border_top_color.clone()));
                 });
+            View::default().use_style_sheet(Some(state.style.clone())).add_child(node)
+        }
+
+        // The border is the same as the color, but wasn't set with currentColor
+        let mut state = State::new(
+            ".a { height: 50px; color: red; border-top-color: red; }
+            .a:hover { color: blue; }",
+        );
+        let mut viewport = viewport(&state, view);
+
+        hover(&mut viewport, &mut state, 1.0, 1.0);
+        assert_eq!(*state.border.borrow(), Some(piet::Color::RED));
+    }
+
+    #[test]
+    fn gradient_current_color_uses_the_inherited_color() {
+        let state = State::new(
+            ".root { color: red; }
+            .a { height: 50px; background-image: linear-gradient(currentColor, currentColor); }",
+        );
+        let viewport = viewport(&state, |state| {
+            let node = View::default().key(state.key).add_classes("a");
+            View::default()
+                .use_style_sheet(Some(state.style.clone()))
+                .add_classes("root")
+                .add_child(node)
         });
+
+        let style = viewport.get_style(state.key).unwrap();
+        let gradient = &style.background_image.as_ref().unwrap()[0];
+        assert!(gradient.gradient_stops.iter().all(|(_, color)| *color == piet::Color::RED));
+    }
+
+    #[test]
+    fn shadow_and_gradient_current_color_follow_the_final_color() {
+        fn view(state: &State) -> View<State, ()> {
+            let node = View::default()
+                .key(state.key)
+                .add_classes("a")
+                .on_style(|s: &State, style: &mut Style| {
+                    let shadow = style.box_shadow.as_ref().unwrap()[0].color.clone();
+                    let gradient = style.background_image.as_ref().unwrap()[0].gradient_stops[0].1.clone();
+                    s.shadow_and_gradient.replace(Some((shadow, gradient)));
+                });
+            View::default().use_style_sheet(Some(state.style.clone())).add_child(node)
+        }
+
+        // The color is set after the shadow and gradient, and changed again by a hover rule
+        let mut state = State::new(
+            ".a { height: 50px; box-shadow: 1px 1px currentColor; background-image: linear-gradient(currentColor, blue); color: red; }
+            .a:hover { color: yellow; }",
+        );
+        let mut viewport = viewport(&state, view);
+        let style = viewport.get_style(state.key).unwrap();
+        assert_eq!(style.box_shadow.as_ref().unwrap()[0].color, piet::Color::RED);
+        let stops = &style.background_image.as_ref().unwrap()[0].gradient_stops;
+        assert_eq!(stops[0].1, piet::Color::RED);
+        assert_eq!(stops[1].1, piet::Color::rgb8(0, 0, 255));
+
+        hover(&mut viewport, &mut state, 1.0, 1.0);
+        let yellow = piet::Color::rgb8(255, 255, 0);
+        assert_eq!(*state.shadow_and_gradient.borrow(), Some((yellow.clone(), yellow)));
+    }
+
+    #[test]
+    fn is_and_not_match_simple_selectors() {
+        fn view(state: &State) -> View<State, ()> {
+            View::default()
+                .use_style_sheet(Some(state.style.clone()))
+                .add_child(View::default().key(state.keys[0]).add_classes("a b"))
+                .add_child(View::default().key(state.keys[1]).add_classes("a c"))
+                .add_child(View::default().key(state.keys[2]).add_classes("a"))
+        }
+
+        let state = State::new(".a:is(.b, .c) { width: 1px; } .a:not(.b) { height: 2px; }");
+        let viewport = viewport(&state, view);
+        assert_eq!(size(&viewport, state.keys[0]), (Some(1.0), None));
+        assert_eq!(size(&viewport, state.keys[1]), (Some(1.0), Some(2.0)));
+        assert_eq!(size(&viewport, state.keys[2]), (None, Some(2.0)));
+    }
+
+    #[test]
+    fn is_takes_its_most_specific_argument() {
+        fn view(state: &State) -> View<State, ()> {
+            let node = View::default().key(state.key).id("main").add_classes("a b");
+            View::default().use_style_sheet(Some(state.style.clone())).add_child(node)
+        }
+
+        // Both rules match, but `:is(#main, .x)` counts as an id, so the earlier rule wins
+        let state = State::new(".a:is(#main, .x) { width: 1px; } .a.b.b { width: 2px; }");
+        let viewport = viewport(&state, view);
+        assert_eq!(size(&viewport, state.key).0, Some(1.0));
+    }
+
+    #[test]
+    fn is_with_hover_is_dynamic() {
+        let mut state = State::new(".a { height: 50px; } .a:is(:hover, .x) { width: 3px; }");
+        let mut viewport = viewport(&state, keyed_view);
+        assert_eq!(state.width.get(), None);
+
+        hover(&mut viewport, &mut state, 1.0, 1.0);
+        assert_eq!(state.width.get(), Some(3.0));
+    }
+
+    #[test]
+    fn id_selector_beats_class_selectors() {
+        fn view(state: &State) -> View<State, ()> {
+            View::default()
+                .use_style_sheet(Some(state.style.clone()))
+                .add_child(View::default().key(state.keys[0]).id("main").add_classes("a b"))
+                .add_child(View::default().key(state.keys[1]).add_classes("a b"))
+        }
+
+        let state = State::new("#main { width: 1px; } .a.b { width: 2px; } .a { width: 3px; }");
+        let viewport = viewport(&state, view);
+        assert_eq!(size(&viewport, state.keys[0]).0, Some(1.0));
+        assert_eq!(size(&viewport, state.keys[1]).0, Some(2.0));
+    }
+
+    #[test]
+    fn checked_and_selected_follow_the_view() {
+        fn view(state: &State) -> View<State, ()> {
+            View::default()
+                .use_style_sheet(Some(state.style.clone()))
+                .add_child(View::default().key(state.keys[0]).add_classes("a").checked(state.checked))
+                .add_child(View::default().key(state.keys[1]).add_classes("a").selected(!state.checked))
+        }
+
+        let mut state = State::new(".a:checked { width: 1px; } .a:selected { width: 2px; } .a:not(:checked) { height: 3px; }");
+        let mut viewport = viewport(&state, view);
+        assert_eq!(size(&viewport, state.keys[0]), (None, Some(3.0)));
+        assert_eq!(size(&viewport, state.keys[1]), (Some(2.0), Some(3.0)));
+
+        // Rebuilding with the new state restyles both nodes
+        state.checked = true;
+        viewport.update_phase(Phase::Build);
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(size(&viewport, state.keys[0]), (Some(1.0), None));
+        assert_eq!(size(&viewport, state.keys[1]), (None, Some(3.0)));
+    }
+
+    #[test]
+    fn empty_matches_nodes_without_children() {
+        fn view(state: &State) -> View<State, ()> {
+            View::default()
+                .use_style_sheet(Some(state.style.clone()))
+                .add_child(View::default().key(state.keys[0]).add_classes("a"))
+                .add_child(View::default().key(state.keys[1]).add_classes("a").add_child(View::default()))
+        }
+
+        let state = State::new(".a:empty { width: 1px; } .a:not(:empty) { width: 2px; }");
+        let viewport = viewport(&state, view);
+        assert_eq!(size(&viewport, state.keys[0]).0, Some(1.0));
+        assert_eq!(size(&viewport, state.keys[1]).0, Some(2.0));
+    }
+
+    #[test]
+    fn rem_ignores_intermediate_font_sizes() {
+        fn view(state: &State) -> View<State, ()> {
+            let inner = View::default().key(state.keys[1]).add_classes("b");
+            let middle = View::default().key(state.keys[0]).add_classes("b").add_child(inner);
+            View::default()
+                .use_style_sheet(Some(state.style.clone()))
+                .add_classes("root")
+                .add_child(middle)
+        }
+
+        let state = State::new(".root { font-size: 10px; } .b { font-size: 2em; width: 1rem; height: 1em; }");
+        let viewport = viewport(&state, view);
+        assert_eq!(size(&viewport, state.keys[0]), (Some(10.0), Some(20.0)));
+        assert_eq!(size(&viewport, state.keys[1]), (Some(10.0), Some(40.0)));
+
+        let style = viewport.get_style(state.keys[1]).unwrap();
+        assert_eq!((style.font_size, style.root_font_size), (40.0, 10.0));
+    }
+
+    #[test]
+    fn byte_order_marks_are_stripped() {
+        let sheet = Stylesheet::from_bytes(b"\xef\xbb\xbf.a { width: 1px; } .b { width: 2px; }").unwrap();
+        assert_eq!(sheet.inner.read().unwrap().static_rules.len(), 2);
+
+        let mut sheet = Stylesheet::parse("");
+        sheet.reparse("\u{feff}.a { width: 1px; }");
+        assert_eq!(sheet.inner.read().unwrap().static_rules.len(), 1);
+    }
+
+    #[test]
+    fn invalid_utf8_is_an_error() {
+        assert!(Stylesheet::from_bytes(b".a { width: 1px; } \xff").is_err());
+        assert!(Stylesheet::from_bytes(b"").is_ok());
+    }
+
+    #[test]
+    fn get_style_reads_the_cascade() {
+        let mut state = State::new(
+            ".root { color: red; font-size: 20px; }
+            .a { background-color: blue; opacity: 0.5; height: 50px; }
+            .a:hover { background-color: lime; }",
+        );
+        let view = |state: &State| {
+            let node = View::default()
+                .key(state.key)
+                .add_classes("a")
+                .on_style(|_, style: &mut Style| style.width = Some(5.0));
+            View::default()
+                .use_style_sheet(Some(state.style.clone()))
+                .add_classes("root")
+                .add_child(node)
+        };
+        let mut viewport = viewport(&state, view);
+
+        let style = viewport.get_style(state.key).unwrap();
+        assert_eq!(style.background_color, piet::Color::rgb8(0, 0, 255));
+        assert_eq!(style.opacity, 0.5);
+        assert_eq!(style.color, piet::Color::RED);
+        assert_eq!(style.font_size, 20.0);
+        assert!(viewport.get_style(Key::new()).is_none());
+
+        // Hover rules and style callbacks only apply while drawing
+        hover(&mut viewport, &mut state, 1.0, 1.0);
+        let style = viewport.get_style(state.key).unwrap();
+        assert_eq!(style.background_color, piet::Color::rgb8(0, 0, 255));
+        assert_eq!(style.width, None);
+    }
+
+    #[test]
+    fn inline_styles_override_rules() {
+        let mut state = State::new(
+            "#main { width: 1px; color: blue; }
+            .a { height: 50px; font-size: 10px; }
+            .a:hover { width: 2px; }",
+        );
+        let view = |state: &State| {
+            let node = View::default()
+                .key(state.key)
+                .id("main")
+                .add_classes("a")
+                .inline_style("width: 7px; color: red; font-size: 16px; bogus: 1px")
+                .on_style(|s: &State, style: &mut Style| s.width.set(style.width));
+            View::default().use_style_sheet(Some(state.style.clone())).add_child(node)
+        };
+        let mut viewport = viewport(&state, view);
+
+        let style = viewport.get_style(state.key).unwrap();
+        assert_eq!((style.width, style.height), (Some(7.0), Some(50.0)));
+        assert_eq!(style.color, piet::Color::RED);
+        assert_eq!(style.font_size, 16.0);
+
+        // Inline styles win over normal hover rules too
+        hover(&mut viewport, &mut state, 1.0, 1.0);
+        assert_eq!(state.width.get(), Some(7.0));
+    }
+
+    #[test]
+    fn style_callbacks_run_after_every_rule() {
+        let mut state = State::new(".a { height: 50px; width: 1px; } .a:hover { width: 2px !important; }");
+        let view = |state: &State| {
+            // Scales the width from the cascade, including the hover rule, by an amount from the state
+            let node = View::default()
+                .key(state.key)
+                .add_classes("a")
+                .on_style(|s: &State, style: &mut Style| {
+                    let scale = if s.checked { 100.0 } else { 10.0 };
+                    style.width = style.width.map(|width| width * scale);
+                    s.width.set(style.width);
+                });
+            View::default().use_style_sheet(Some(state.style.clone())).add_child(node)
+        };
+        let mut viewport = viewport(&state, view);
+        assert_eq!(state.width.get(), Some(10.0));
+
+        hover(&mut viewport, &mut state, 1.0, 1.0);
+        assert_eq!(state.width.get(), Some(20.0));
+
+        // Redrawing reuses its last result, even though the state changed
+        state.checked = true;
+        state.width.set(None);
+        viewport.update_phase(Phase::Draw);
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(state.width.get(), None);
+
+        // Restyling re-runs it with the new state, without rebuilding
+        viewport.update_phase(Phase::Style);
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(state.width.get(), Some(200.0));
+
+        // Its changes are undone after drawing
+        assert_eq!(viewport.get_style(state.key).unwrap().width, Some(1.0));
     }
 }
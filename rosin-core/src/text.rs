@@ -0,0 +1,215 @@
+#![forbid(unsafe_code)]
+
+use std::ops::Range;
+
+use druid_shell::kurbo;
+use druid_shell::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder, TextStorage};
+use unicode_segmentation::{GraphemeCursor, UnicodeSegmentation};
+
+use crate::style::Style;
+
+/// Start laying out text in a style's font, size, and color, so range attributes can be added before it's built.
+/// If the style's font family isn't available, the system UI font is used.
+pub fn text_layout_builder<T: Text>(factory: &mut T, text: impl TextStorage, style: &Style) -> T::TextLayoutBuilder {
+    let font_family = if let Some(family_name) = &style.font_family {
+        factory.font_family(family_name.as_ref())
+    } else {
+        None
+    };
+    let font_family = font_family.unwrap_or(FontFamily::SYSTEM_UI);
+
+    factory
+        .new_text_layout(text)
+        .font(font_family, style.font_size as f64)
+        .text_color(style.color.clone())
+}
+
+/// Lay out text in a style's font, size, and color, the same way labels do. Lines wrap at `max_width`, if it's given.
+///
+/// `factory` can be the `text()` of a `DrawCtx`'s piet context while drawing, or of a `WindowHandle` at other times.
+pub fn text_layout<T: Text>(factory: &mut T, text: &str, style: &Style, max_width: Option<f64>) -> T::TextLayout {
+    let mut builder = text_layout_builder(factory, text.to_string(), style);
+    if let Some(max_width) = max_width {
+        builder = builder.max_width(max_width);
+    }
+    builder.build().unwrap()
+}
+
+/// Measure the size that text will take up when it's drawn, such as to size a node before layout.
+pub fn measure_text<T: Text>(factory: &mut T, text: &str, style: &Style, max_width: Option<f64>) -> kurbo::Size {
+    text_layout(factory, text, style, max_width).size()
+}
+
+/// Find where text breaks into lines when it's wrapped at `max_width`. Returns the byte range of each line.
+pub fn line_breaks<T: Text>(factory: &mut T, text: &str, style: &Style, max_width: f64) -> Vec<Range<usize>> {
+    let layout = text_layout(factory, text, style, Some(max_width));
+    (0..layout.line_count())
+        .filter_map(|line| layout.line_metric(line))
+        .map(|metric| metric.start_offset..metric.end_offset)
+        .collect()
+}
+
+/// The byte offset of the next grapheme boundary after `offset`, so emoji sequences and combining marks are skipped as a whole.
+pub fn next_grapheme(text: &str, offset: usize) -> usize {
+    let mut cursor = GraphemeCursor::new(offset, text.len(), true);
+    cursor.next_boundary(text, 0).ok().flatten().unwrap_or(text.len())
+}
+
+/// The byte offset of the previous grapheme boundary before `offset`.
+pub fn prev_grapheme(text: &str, offset: usize) -> usize {
+    let mut cursor = GraphemeCursor::new(offset, text.len(), true);
+    cursor.prev_boundary(text, 0).ok().flatten().unwrap_or(0)
+}
+
+/// Widen a byte range so it starts and ends on grapheme boundaries, and fits within the text.
+pub fn grapheme_range(text: &str, range: Range<usize>) -> Range<usize> {
+    let mut start = range.start.min(text.len());
+    let mut end = range.end.clamp(start, text.len());
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+
+    if GraphemeCursor::new(start, text.len(), true).is_boundary(text, 0) != Ok(true) {
+        start = prev_grapheme(text, start);
+    }
+    if GraphemeCursor::new(end, text.len(), true).is_boundary(text, 0) != Ok(true) {
+        end = next_grapheme(text, end);
+    }
+    start..end
+}
+
+/// The byte offset of the end of the next word after `offset`, skipping whitespace and punctuation, like Ctrl+Right.
+pub fn next_word(text: &str, offset: usize) -> usize {
+    text.split_word_bound_indices()
+        .map(|(start, word)| (start + word.len(), word))
+        .find(|(end, word)| *end > offset && is_word(word))
+        .map_or(text.len(), |(end, _)| end)
+}
+
+/// The byte offset of the start of the previous word before `offset`, like Ctrl+Left.
+pub fn prev_word(text: &str, offset: usize) -> usize {
+    text.split_word_bound_indices()
+        .rev()
+        .find(|(start, word)| *start < offset && is_word(word))
+        .map_or(0, |(start, _)| start)
+}
+
+fn is_word(segment: &str) -> bool {
+    segment.chars().any(char::is_alphanumeric)
+}
+
+#[cfg(test)]
+mod tests {
+    use druid_shell::piet::{Device, ImageFormat, Piet, RenderContext};
+
+    use super::*;
+    use crate::prelude::*;
+    use crate::widgets::label;
+
+    const TEXT: &str = "The quick brown fox jumps over the lazy dog";
+
+    // Draw into a bitmap, and return its pixels
+    fn render(draw: impl FnOnce(&mut Piet)) -> Vec<u8> {
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(200, 100, 1.0).unwrap();
+        let mut piet = target.render_context();
+        draw(&mut piet);
+        piet.finish().unwrap();
+        drop(piet);
+        target.to_image_buf(ImageFormat::RgbaPremul).unwrap().raw_pixels().to_vec()
+    }
+
+    #[test]
+    fn measurements_match_labels() {
+        fn view(_: &()) -> View<(), ()> {
+            View::default().add_child(label(TEXT).inline_style("font-size: 20px"))
+        }
+
+        let style = Style {
+            font_size: 20.0,
+            ..Style::default()
+        };
+        let mut size = kurbo::Size::ZERO;
+        let expected = render(|piet| {
+            size = measure_text(piet.text(), TEXT, &style, None);
+            let layout = text_layout(piet.text(), TEXT, &style, None);
+            piet.draw_text(&layout, (0.0, 0.0));
+        });
+
+        // A label node draws exactly the text that was measured
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (200.0, 100.0), ());
+        let drawn = render(|piet| viewport.draw(&(), Some(piet)).unwrap());
+        assert_eq!(drawn, expected);
+
+        // And all of it is inside the measured size
+        let ink: Vec<usize> = (0..drawn.len() / 4).filter(|i| drawn[i * 4 + 3] > 0).collect();
+        let right = ink.iter().map(|i| i % 200).max().unwrap();
+        let bottom = ink.iter().map(|i| i / 200).max().unwrap();
+        assert!(right as f64 <= size.width + 1.0);
+        assert!(bottom as f64 <= size.height + 1.0);
+    }
+
+    #[test]
+    fn wrapping_makes_text_narrower_and_taller() {
+        let style = Style::default();
+        render(|piet| {
+            let size = measure_text(piet.text(), TEXT, &style, None);
+            let wrapped = measure_text(piet.text(), TEXT, &style, Some(size.width / 2.0));
+            assert!(wrapped.width <= size.width / 2.0);
+            assert!(wrapped.height > size.height);
+        });
+    }
+
+    #[test]
+    fn line_breaks_cover_the_text() {
+        let style = Style::default();
+        render(|piet| {
+            let width = measure_text(piet.text(), TEXT, &style, None).width;
+            let lines = line_breaks(piet.text(), TEXT, &style, width / 3.0);
+            assert!(lines.len() >= 3);
+            assert_eq!(lines[0].start, 0);
+            assert_eq!(lines[lines.len() - 1].end, TEXT.len());
+            for pair in lines.windows(2) {
+                assert_eq!(pair[0].end, pair[1].start);
+            }
+        });
+    }
+
+    #[test]
+    fn graphemes_keep_emoji_and_accents_together() {
+        // A family emoji joined with ZWJs, and an "e" with a combining acute accent
+        let text = "a\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}e\u{301}b";
+        let family = 1..19;
+        let accent = 19..22;
+
+        assert_eq!(next_grapheme(text, 0), family.start);
+        assert_eq!(next_grapheme(text, family.start), family.end);
+        assert_eq!(next_grapheme(text, family.end), accent.end);
+        assert_eq!(next_grapheme(text, text.len()), text.len());
+
+        assert_eq!(prev_grapheme(text, text.len()), accent.end);
+        assert_eq!(prev_grapheme(text, accent.end), accent.start);
+        assert_eq!(prev_grapheme(text, family.end), family.start);
+        assert_eq!(prev_grapheme(text, 0), 0);
+
+        // Ranges inside a grapheme, or a character, are widened to the whole grapheme
+        assert_eq!(grapheme_range(text, 5..6), family);
+        assert_eq!(grapheme_range(text, 20..21), accent);
+        assert_eq!(grapheme_range(text, 0..100), 0..text.len());
+    }
+
+    #[test]
+    fn words_skip_whitespace_and_punctuation() {
+        let text = "Hello, wörld!  Again";
+        assert_eq!(next_word(text, 0), 5);
+        assert_eq!(next_word(text, 5), 13);
+        assert_eq!(next_word(text, 13), text.len());
+
+        assert_eq!(prev_word(text, text.len()), 16);
+        assert_eq!(prev_word(text, 16), 7);
+        assert_eq!(prev_word(text, 7), 0);
+    }
+}
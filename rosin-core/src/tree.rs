@@ -1,6 +1,8 @@
 use crate::alloc::Alloc;
 use crate::geometry::Size;
+use crate::parser::parse_inline_style;
 use crate::prelude::*;
+use crate::properties::Property;
 use crate::stylesheet::Stylesheet;
 
 use std::collections::HashMap;
@@ -83,9 +85,15 @@ macro_rules! ui {
 
 pub(crate) struct ArrayNode<S: 'static, H: 'static> {
     pub key: Option<Key>,
+    pub id: Option<&'static str>,
+    pub checked: bool,
+    pub indeterminate: bool,
+    pub selected: bool,
+    pub drag_region: bool,
     pub classes: BumpVec<'static, &'static str>,
     pub event_callbacks: BumpVec<'static, (On, &'static mut dyn EventCallback<S, H>)>,
     pub style_sheet: Option<Stylesheet>,
+    pub inline_style: Vec<Property>,
     pub style_callback: Option<&'static mut dyn StyleCallback<S>>,
     pub layout_callback: Option<&'static mut dyn LayoutCallback<S>>,
     pub draw_callback: Option<&'static mut dyn DrawCallback<S>>,
@@ -124,9 +132,15 @@ impl<S, H> std::fmt::Debug for ArrayNode<S, H> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ArrayNode")
             .field("key", &self.key)
+            .field("id", &self.id)
+            .field("checked", &self.checked)
+            .field("indeterminate", &self.indeterminate)
+            .field("selected", &self.selected)
+            .field("drag_region", &self.drag_region)
             .field("classes", &self.classes)
             .field("event_callbacks", &self.event_callbacks.len())
             .field("style_sheet", &self.style_sheet)
+            .field("inline_style", &self.inline_style)
             .field("style_callback", &self.style_callback.is_some())
             .field("layout_callback", &self.layout_callback.is_some())
             .field("draw_callback", &self.draw_callback.is_some())
@@ -169,8 +183,14 @@ impl<S, H> ArrayNode<S, H> {
 #[allow(clippy::type_complexity)]
 pub struct View<S: 'static, H: 'static> {
     key: Option<Key>,
+    id: Option<&'static str>,
+    checked: bool,
+    indeterminate: bool,
+    selected: bool,
+    drag_region: bool,
     classes: Option<BumpVec<'static, &'static str>>,
     style_sheet: Option<Stylesheet>,
+    inline_style: Vec<Property>,
     event_callbacks: Option<BumpVec<'static, (On, &'static mut dyn EventCallback<S, H>)>>,
     style_callback: Option<&'static mut dyn StyleCallback<S>>,
     layout_callback: Option<&'static mut dyn LayoutCallback<S>>,
@@ -189,8 +209,14 @@ impl<S, H> Default for View<S, H> {
 
         Self {
             key: None,
+            id: None,
+            checked: false,
+            indeterminate: false,
+            selected: false,
+            drag_region: false,
             classes: Some(alloc.vec()),
             style_sheet: None,
+            inline_style: Vec::new(),
             event_callbacks: Some(alloc.vec()),
             style_callback: None,
             layout_callback: None,
@@ -211,6 +237,37 @@ impl<S, H> View<S, H> {
         self
     }
 
+    /// Set an id on a node, for matching `#id` selectors in stylesheets.
+    pub fn id(mut self, id: &'static str) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Mark a node as checked, for matching `:checked` selectors in stylesheets.
+    pub fn checked(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
+    }
+
+    /// Mark a node as indeterminate, for matching `:indeterminate` selectors in stylesheets.
+    pub fn indeterminate(mut self, indeterminate: bool) -> Self {
+        self.indeterminate = indeterminate;
+        self
+    }
+
+    /// Mark a node as selected, for matching `:selected` selectors in stylesheets.
+    pub fn selected(mut self, selected: bool) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    /// Mark a node as part of a custom title bar, so dragging it moves the window and double-clicking it maximizes the window.
+    /// Descendants that handle `On::PointerDown`, like buttons, still get clicks.
+    pub fn window_drag_region(mut self) -> Self {
+        self.drag_region = true;
+        self
+    }
+
     /// Register an event callback.
     pub fn event(mut self, event_type: On, callback: impl Fn(&mut S, &mut EventCtx<S, H>) -> Option<Phase> + 'static) -> Self {
         if let Some(callbacks) = &mut self.event_callbacks {
@@ -220,7 +277,20 @@ impl<S, H> View<S, H> {
         self
     }
 
+    /// Set properties from a list of CSS declarations, like the `style` attribute in HTML.
+    /// They override rules from stylesheets, except for `!important` ones.
+    pub fn inline_style(mut self, css: &str) -> Self {
+        self.inline_style.extend(parse_inline_style(css));
+        self
+    }
+
     /// Register a function to modify this node's style before drawing.
+    ///
+    /// The callback receives the computed style after all stylesheet rules, including hover and focus rules,
+    /// and any changes it makes take precedence over them. It runs when the styles are recomputed, or when hover or
+    /// focus changes, and its result is reused for other frames. Its changes are discarded after each draw, so it should
+    /// set values rather than adjust them. Return `Phase::Style` from an event or animation callback to re-run it after
+    /// the state it reads has changed.
     pub fn on_style(mut self, func: impl Fn(&S, &mut Style) + 'static) -> Self {
         let alloc = Alloc::get_thread_local_alloc().unwrap();
         self.style_callback = Some(alloc.alloc(func));
@@ -292,8 +362,14 @@ impl<S, H> View<S, H> {
 
             tree.push(ArrayNode {
                 key: curr_node.key,
+                id: curr_node.id,
+                checked: curr_node.checked,
+                indeterminate: curr_node.indeterminate,
+                selected: curr_node.selected,
+                drag_region: curr_node.drag_region,
                 classes: curr_node.classes.take()?,
                 style_sheet: curr_node.style_sheet.take(),
+                inline_style: std::mem::take(&mut curr_node.inline_style),
                 event_callbacks: curr_node.event_callbacks.take()?,
                 style_callback: curr_node.style_callback.take(),
                 layout_callback: curr_node.layout_callback.take(),
@@ -1,34 +1,58 @@
 use crate::alloc::Alloc;
 use crate::geometry::Point;
+use crate::perf::{FrameRecorder, FrameStats};
 use crate::prelude::*;
-use crate::{alloc::Scope, draw, layout, layout::Layout, stylesheet, tree::*};
+use crate::{alloc::Scope, commands::same_key, draw, layout, layout::Layout, properties::Property, style::Cursor, stylesheet, tree::*};
 
 use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::error::Error;
 use std::rc::Rc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use bumpalo::{collections::Vec as BumpVec, Bump};
+use druid_shell::kurbo;
 use druid_shell::piet::Piet;
-use druid_shell::KeyEvent;
+use druid_shell::{KeyEvent, KeyState};
 
 pub struct Viewport<S: 'static, H: Clone + 'static> {
     resource_loader: ResourceLoader,
     view_callback: ViewCallback<S, H>,
     size: (f32, f32),
     scale: (f32, f32),
+    pixel_snap: bool,
+    color_scheme: ColorScheme,
     handle: H,
     phase: Phase,
+    quit_requested: bool,
+    broadcast_phase: Phase,
+    new_windows: Vec<WindowDesc<S, H>>,
+    file_dialogs: Vec<FileDialog<S>>,
+    frame_recorder: Option<FrameRecorder>,
     focused_node: Option<Key>,
+    captured_node: Option<Key>,
     hot_nodes: Vec<usize>,
     prev_hot_nodes: Vec<usize>,
     prev_hot_keys: Vec<Key>,
+    // Set when the tree is rebuilt, since the ids of the previously hovered nodes no longer match it
+    prev_hot_stale: bool,
+    last_pointer: Option<RawPointerEvent>,
     anim_tasks: Rc<RefCell<Vec<Box<dyn AnimCallback<S>>>>>,
+    shortcuts: Vec<(Modifiers, KbKey, Box<dyn ShortcutCallback<S>>)>,
+    commands: Rc<Commands<S>>,
     key_map: HashMap<Key, usize>,
+    // The border box of each keyed node after the last layout, for `EventCtx::node_rect()`
+    key_rects: Rc<HashMap<Key, kurbo::Rect>>,
     tree_cache: Option<Scope<BumpVec<'static, ArrayNode<S, H>>>>,
     style_cache: Option<Scope<BumpVec<'static, Style>>>,
+    // The cursor of each node as it was drawn, including hover rules and style callbacks
+    cursors: Vec<Cursor>,
+    // The style of each node with a style callback, as the callbacks last left it
+    callback_styles: Vec<(usize, Style)>,
+    // The hovered and focused nodes when the style callbacks last ran, since hover and focus rules change what they're given
+    callback_hot_nodes: Vec<usize>,
+    callback_focus: Option<Key>,
     layout_cache: Option<Scope<BumpVec<'static, Layout>>>,
     alloc: Rc<Alloc>,
     temp: Bump,
@@ -41,16 +65,33 @@ impl<S, H: Clone> Viewport<S, H> {
             view_callback,
             size,
             scale: (1.0, 1.0),
+            pixel_snap: false,
+            color_scheme: ColorScheme::default(),
             handle,
             phase: Phase::Build,
+            quit_requested: false,
+            broadcast_phase: Phase::Idle,
+            new_windows: Vec::new(),
+            file_dialogs: Vec::new(),
+            frame_recorder: None,
             focused_node: None,
+            captured_node: None,
             hot_nodes: Vec::new(),
             prev_hot_nodes: Vec::new(),
             prev_hot_keys: Vec::new(),
+            prev_hot_stale: false,
+            last_pointer: None,
             anim_tasks: Rc::new(RefCell::new(Vec::new())),
+            shortcuts: Vec::new(),
+            commands: Rc::new(Commands::new()),
             key_map: HashMap::new(),
+            key_rects: Rc::new(HashMap::new()),
             tree_cache: None,
             style_cache: None,
+            cursors: Vec::new(),
+            callback_styles: Vec::new(),
+            callback_hot_nodes: Vec::new(),
+            callback_focus: None,
             layout_cache: None,
             alloc: Rc::new(Alloc::default()),
             temp: Bump::new(),
@@ -68,6 +109,7 @@ impl<S, H: Clone> Viewport<S, H> {
         self.alloc.reset().expect("[Rosin] Failed to reset cache");
 
         self.prev_hot_nodes.clear();
+        self.prev_hot_stale = true;
         self.key_map.clear();
     }
 
@@ -82,11 +124,41 @@ impl<S, H: Clone> Viewport<S, H> {
     pub fn size(&mut self, new_size: (f32, f32)) {
         self.size.0 = new_size.0;
         self.size.1 = new_size.1;
-        self.update_phase(Phase::Layout);
+
+        // Viewport units are resolved when styling, so restyle if any stylesheet or inline style uses them
+        let viewport_relative = self.tree_cache.as_ref().map_or(false, |tree| {
+            tree.borrow().iter().any(|node| {
+                node.style_sheet.as_ref().map_or(false, Stylesheet::is_viewport_relative)
+                    || node.inline_style.iter().any(Property::is_viewport_relative)
+            })
+        });
+        self.update_phase(if viewport_relative { Phase::Style } else { Phase::Layout });
     }
 
     pub fn scale(&mut self, new_scale: (f32, f32)) {
         self.scale = new_scale;
+        if self.pixel_snap {
+            self.update_phase(Phase::Layout);
+        }
+    }
+
+    /// Round layout to device pixels, so thin borders don't blur at fractional scales.
+    pub fn set_pixel_snap(&mut self, pixel_snap: bool) {
+        self.pixel_snap = pixel_snap;
+        self.update_phase(Phase::Layout);
+    }
+
+    pub fn color_scheme(&self) -> ColorScheme {
+        self.color_scheme
+    }
+
+    /// Set the appearance used to match `@media (prefers-color-scheme)` rules.
+    /// The platform's setting isn't read, so an app that follows it needs to set the scheme itself.
+    pub fn set_color_scheme(&mut self, color_scheme: ColorScheme) {
+        if self.color_scheme != color_scheme {
+            self.color_scheme = color_scheme;
+            self.update_phase(Phase::Style);
+        }
     }
 
     pub fn set_view(&mut self, new_view: ViewCallback<S, H>) {
@@ -94,6 +166,87 @@ impl<S, H: Clone> Viewport<S, H> {
         self.phase = Phase::Build;
     }
 
+    /// Get the computed style of the node with `key`, as of the last time stylesheets were applied.
+    /// Hover and focus rules and style callbacks are only applied while drawing, so they aren't included.
+    pub fn get_style(&self, key: Key) -> Option<&Style> {
+        let id = *self.key_map.get(&key)?;
+        self.style_cache.as_ref()?.borrow().get(id)
+    }
+
+    /// Get the border box of the node with `key` in window coordinates, as of the last layout.
+    pub fn node_rect(&self, key: Key) -> Option<kurbo::Rect> {
+        self.key_rects.get(&key).copied()
+    }
+
+    /// Returns true once if a callback has called `EventCtx::quit()`.
+    pub fn take_quit_request(&mut self) -> bool {
+        std::mem::take(&mut self.quit_requested)
+    }
+
+    /// Returns the phase that callbacks have asked other windows to update with `EventCtx::broadcast()`, and resets it.
+    pub fn take_broadcast(&mut self) -> Phase {
+        std::mem::replace(&mut self.broadcast_phase, Phase::Idle)
+    }
+
+    /// Returns the window descriptions that callbacks have passed to `EventCtx::open_window()`, and resets the list.
+    pub fn take_new_windows(&mut self) -> Vec<WindowDesc<S, H>> {
+        std::mem::take(&mut self.new_windows)
+    }
+
+    /// Returns the file dialogs that callbacks have asked for with `EventCtx::open_file_dialog()` and
+    /// `EventCtx::save_file_dialog()`, and resets the list.
+    pub fn take_file_dialogs(&mut self) -> Vec<FileDialog<S>> {
+        std::mem::take(&mut self.file_dialogs)
+    }
+
+    /// Returns the cursor of the innermost hovered node that sets one, or `Cursor::Default`.
+    /// Unlike `get_style()`, this includes hover and focus rules and style callbacks, as of the last draw.
+    pub fn cursor(&self) -> Cursor {
+        if let (Some(tree), Some(&id)) = (&self.tree_cache, self.hot_nodes.last()) {
+            let tree = tree.borrow();
+            let mut curr = id;
+            while let (Some(node), Some(&cursor)) = (tree.get(curr), self.cursors.get(curr)) {
+                if cursor != Cursor::Default {
+                    return cursor;
+                }
+                curr = node.parent;
+            }
+        }
+        Cursor::Default
+    }
+
+    /// Returns true if the pointer is over a node marked with `window_drag_region()`, and not over a descendant that handles clicks.
+    pub fn is_over_drag_region(&self) -> bool {
+        if let (Some(tree), Some(&id)) = (&self.tree_cache, self.hot_nodes.last()) {
+            let tree = tree.borrow();
+            let mut curr = id;
+            while let Some(node) = tree.get(curr) {
+                if node.drag_region {
+                    return true;
+                }
+                if node.has_callback(On::PointerDown) {
+                    return false;
+                }
+                curr = node.parent;
+            }
+        }
+        false
+    }
+
+    /// Start recording the timings of the last `capacity` frames, discarding any previous recording.
+    pub fn record_frames(&mut self, capacity: usize) {
+        self.frame_recorder = Some(FrameRecorder::new(capacity));
+    }
+
+    pub fn frame_recorder(&self) -> Option<&FrameRecorder> {
+        self.frame_recorder.as_ref()
+    }
+
+    /// Stop recording frame timings, and return what was recorded.
+    pub fn stop_recording(&mut self) -> Option<FrameRecorder> {
+        self.frame_recorder.take()
+    }
+
     pub fn is_idle(&self) -> bool {
         self.phase == Phase::Idle
     }
@@ -106,6 +259,18 @@ impl<S, H: Clone> Viewport<S, H> {
         self.anim_tasks.borrow_mut().push(Box::new(callback));
     }
 
+    /// Register a keyboard shortcut that doesn't depend on which node has focus.
+    /// Shortcuts without Ctrl, Alt, or Meta only fire when no node has focus, so text input still gets plain keys.
+    /// Character keys match regardless of case, so a shortcut for Ctrl+Shift and `k` fires when the key reports `K`.
+    pub fn add_shortcut(&mut self, modifiers: Modifiers, key: KbKey, callback: impl Fn(&mut S) -> Phase + 'static) {
+        self.shortcuts.push((modifiers, key, Box::new(callback)));
+    }
+
+    /// Set the commands that `EventCtx::dispatch()` runs, and whose shortcuts this viewport handles.
+    pub fn set_commands(&mut self, commands: Rc<Commands<S>>) {
+        self.commands = commands;
+    }
+
     pub fn got_focus(&mut self, state: &mut S) {
         self.root_event(state, On::WindowFocus);
     }
@@ -122,19 +287,15 @@ impl<S, H: Clone> Viewport<S, H> {
         if let Some(tree) = &mut self.tree_cache {
             let tree = tree.borrow_mut();
 
-            let default_style = Style::default();
-            let default_layout = Layout::default();
-
-            let mut ctx = EventCtx {
-                info: EventInfo::None,
-                platform_handle: self.handle.clone(),
-                resource_loader: self.resource_loader.clone(),
-                focus: self.focused_node,
-                style: default_style,
-                layout: default_layout,
-                change: false,
-                anim_tasks: self.anim_tasks.clone(),
-            };
+            let mut ctx = EventCtx::new(
+                EventInfo::None,
+                self.handle.clone(),
+                self.resource_loader.clone(),
+                self.focused_node,
+                self.captured_node,
+                self.anim_tasks.clone(),
+                self.key_rects.clone(),
+            );
 
             let mut phase = Self::dispatch_event(event_type, state, &mut ctx, tree, 0);
             phase.update(self.handle_ctx(state, ctx));
@@ -143,29 +304,29 @@ impl<S, H: Clone> Viewport<S, H> {
     }
 
     pub fn pointer_leave(&mut self, state: &mut S) {
-        if let Some(tree) = &mut self.tree_cache {
+        if let (Some(tree), Some(layout)) = (&mut self.tree_cache, &self.layout_cache) {
             let tree = tree.borrow_mut();
+            let layout = layout.borrow();
 
-            let default_style = Style::default();
-            let default_layout = Layout::default();
-
-            let mut ctx = EventCtx {
-                info: EventInfo::None,
-                platform_handle: self.handle.clone(),
-                resource_loader: self.resource_loader.clone(),
-                focus: self.focused_node,
-                style: default_style,
-                layout: default_layout,
-                change: false,
-                anim_tasks: self.anim_tasks.clone(),
-            };
-
-            if self.prev_hot_nodes.is_empty() {
-                for key in &self.prev_hot_keys {
-                    if let Some(&id) = self.key_map.get(key) {
-                        self.prev_hot_nodes.push(id);
-                    }
-                }
+            let mut ctx = EventCtx::new(
+                EventInfo::None,
+                self.handle.clone(),
+                self.resource_loader.clone(),
+                self.focused_node,
+                self.captured_node,
+                self.anim_tasks.clone(),
+                self.key_rects.clone(),
+            );
+
+            if self.prev_hot_stale {
+                Self::restore_hot_nodes(
+                    &mut self.prev_hot_nodes,
+                    &self.prev_hot_keys,
+                    &self.key_map,
+                    tree,
+                    layout,
+                    self.last_pointer,
+                );
             }
 
             // Dispatch MouseLeave event to all previously hovered nodes
@@ -181,27 +342,42 @@ impl<S, H: Clone> Viewport<S, H> {
             self.prev_hot_nodes.clear();
             self.prev_hot_keys.clear();
         }
+        self.prev_hot_stale = false;
+        self.last_pointer = None;
     }
 
     pub fn pointer_wheel(&mut self, state: &mut S, event: RawPointerEvent) {
-        self.pointer_event(state, event, On::PointerWheel)
+        self.pointer_event(state, event, Some(On::PointerWheel))
     }
 
     pub fn pointer_move(&mut self, state: &mut S, event: RawPointerEvent) {
-        self.pointer_event(state, event, On::PointerMove)
+        self.pointer_event(state, event, Some(On::PointerMove))
     }
 
     pub fn pointer_down(&mut self, state: &mut S, event: RawPointerEvent) {
-        self.pointer_event(state, event, On::PointerDown)
+        self.pointer_event(state, event, Some(On::PointerDown))
     }
 
     pub fn pointer_up(&mut self, state: &mut S, event: RawPointerEvent) {
-        self.pointer_event(state, event, On::PointerUp)
+        self.pointer_event(state, event, Some(On::PointerUp))
+    }
+
+    /// Update which nodes are under a stationary pointer, after the tree or layout has changed.
+    /// Only `PointerEnter` and `PointerLeave` events are dispatched.
+    pub fn refresh_pointer(&mut self, state: &mut S) {
+        if let Some(event) = self.last_pointer {
+            self.pointer_event(state, event, None);
+        }
     }
 
-    fn pointer_event(&mut self, state: &mut S, event: RawPointerEvent, event_type: On) {
+    // If event_type is None, only enter and leave events are dispatched
+    fn pointer_event(&mut self, state: &mut S, event: RawPointerEvent, event_type: Option<On>) {
+        let last_pointer = self.last_pointer.replace(event);
+
         // TODO - this shouldn't be necessary, set phase only when dynamic styles require it
-        self.update_phase(Phase::Draw);
+        if event_type.is_some() {
+            self.update_phase(Phase::Draw);
+        }
 
         if let (Some(tree), Some(styles), Some(layout)) = (&mut self.tree_cache, &self.style_cache, &self.layout_cache) {
             let tree = tree.borrow_mut();
@@ -209,19 +385,15 @@ impl<S, H: Clone> Viewport<S, H> {
             let layout = layout.borrow();
             self.temp.reset();
 
-            let default_style = Style::default();
-            let default_layout = Layout::default();
-
-            let mut ctx = EventCtx {
-                info: EventInfo::None,
-                platform_handle: self.handle.clone(),
-                resource_loader: self.resource_loader.clone(),
-                focus: self.focused_node,
-                style: default_style,
-                layout: default_layout,
-                change: false,
-                anim_tasks: self.anim_tasks.clone(),
-            };
+            let mut ctx = EventCtx::new(
+                EventInfo::None,
+                self.handle.clone(),
+                self.resource_loader.clone(),
+                self.focused_node,
+                self.captured_node,
+                self.anim_tasks.clone(),
+                self.key_rects.clone(),
+            );
 
             let position = Point {
                 x: event.window_pos_x as f32,
@@ -232,15 +404,17 @@ impl<S, H: Clone> Viewport<S, H> {
             self.hot_nodes.clear();
             layout::hit_test(layout, position, &mut self.hot_nodes);
 
-            // If there are no hovered ids from the previous frame, the tree might have been rebuilt
-            // So, use keys to get the ids of previously hovered nodes
-            if self.prev_hot_nodes.is_empty() {
-                for key in &self.prev_hot_keys {
-                    if let Some(&id) = self.key_map.get(key) {
-                        self.prev_hot_nodes.push(id);
-                    }
-                }
-                self.prev_hot_nodes.sort_unstable();
+            // If the tree has been rebuilt, find which of its nodes were hovered before
+            if self.prev_hot_stale {
+                Self::restore_hot_nodes(
+                    &mut self.prev_hot_nodes,
+                    &self.prev_hot_keys,
+                    &self.key_map,
+                    tree,
+                    layout,
+                    last_pointer,
+                );
+                self.prev_hot_stale = false;
             }
 
             let mut mouse_enter_nodes: BumpVec<usize> = BumpVec::new_in(&self.temp);
@@ -280,6 +454,11 @@ impl<S, H: Clone> Viewport<S, H> {
             let mut phase = Phase::Idle;
             let mut pointer_event: PointerEvent = event.into();
 
+            // Redraw if hover state changed, so :hover styles are updated
+            if !mouse_enter_nodes.is_empty() || !mouse_leave_nodes.is_empty() {
+                phase.update(Phase::Draw);
+            }
+
             for id in mouse_leave_nodes {
                 pointer_event.pos_x = pointer_event.window_pos_x - layout[id].position.x as f64;
                 pointer_event.pos_y = pointer_event.window_pos_y - layout[id].position.y as f64;
@@ -298,13 +477,40 @@ impl<S, H: Clone> Viewport<S, H> {
                 phase.update(Self::dispatch_event(On::PointerEnter, state, &mut ctx, tree, id));
             }
 
-            for &id in &self.hot_nodes {
-                pointer_event.pos_x = pointer_event.window_pos_x - layout[id].position.x as f64;
-                pointer_event.pos_y = pointer_event.window_pos_y - layout[id].position.y as f64;
-                ctx.info = EventInfo::Pointer(pointer_event);
-                ctx.style = styles[id].clone();
-                ctx.layout = layout[id];
-                phase.update(Self::dispatch_event(event_type, state, &mut ctx, tree, id));
+            if let Some(event_type) = event_type {
+                // Pointer events bubble from the innermost node outward, so a nested handler can keep an outer one
+                // from scrolling or starting a drag
+                ctx.stopped = false;
+
+                // A node that has captured the pointer gets move and up events instead of the nodes under the pointer
+                let captured = match event_type {
+                    On::PointerMove | On::PointerUp => self.captured_node.and_then(|key| self.key_map.get(&key)),
+                    _ => None,
+                };
+                let hot_nodes = match captured {
+                    Some(id) => std::slice::from_ref(id),
+                    None => &self.hot_nodes[..],
+                };
+
+                // If more than one node captures the pointer, the innermost one keeps it
+                let mut capture = None;
+                for &id in hot_nodes.iter().rev() {
+                    pointer_event.pos_x = pointer_event.window_pos_x - layout[id].position.x as f64;
+                    pointer_event.pos_y = pointer_event.window_pos_y - layout[id].position.y as f64;
+                    ctx.info = EventInfo::Pointer(pointer_event);
+                    ctx.style = styles[id].clone();
+                    ctx.layout = layout[id];
+                    phase.update(Self::dispatch_event(event_type, state, &mut ctx, tree, id));
+                    if capture.is_none() && ctx.capture != self.captured_node {
+                        capture = Some(ctx.capture);
+                    }
+                    if ctx.stopped {
+                        break;
+                    }
+                }
+                if let Some(capture) = capture {
+                    ctx.capture = capture;
+                }
             }
 
             // Store the keys from hovered nodes in case the tree gets rebuilt
@@ -319,11 +525,63 @@ impl<S, H: Clone> Viewport<S, H> {
 
             phase.update(self.handle_ctx(state, ctx));
             self.update_phase(phase);
+
+            // Releasing the button ends any capture
+            if event_type == Some(On::PointerUp) {
+                self.captured_node = None;
+            }
+        }
+    }
+
+    // Find the nodes of a rebuilt tree that were hovered before it was rebuilt. Keyed nodes are found by their keys.
+    // Unkeyed nodes can't be matched up, so the ones under the pointer's last position are assumed to be the same nodes.
+    // Otherwise they would get another `PointerEnter` without a `PointerLeave` every time the tree is rebuilt.
+    fn restore_hot_nodes(
+        prev_hot_nodes: &mut Vec<usize>,
+        prev_hot_keys: &[Key],
+        key_map: &HashMap<Key, usize>,
+        tree: &[ArrayNode<S, H>],
+        layout: &[Layout],
+        last_pointer: Option<RawPointerEvent>,
+    ) {
+        prev_hot_nodes.clear();
+        prev_hot_nodes.extend(prev_hot_keys.iter().filter_map(|key| key_map.get(key)));
+
+        if let Some(event) = last_pointer {
+            let position = Point {
+                x: event.window_pos_x as f32,
+                y: event.window_pos_y as f32,
+            };
+            let mut under_pointer = Vec::new();
+            layout::hit_test(layout, position, &mut under_pointer);
+            prev_hot_nodes.extend(under_pointer.into_iter().filter(|&id| tree[id].key.is_none()));
         }
+
+        prev_hot_nodes.sort_unstable();
+        prev_hot_nodes.dedup();
     }
 
     // TODO - always route events to root
     pub fn key_event(&mut self, state: &mut S, event: KeyEvent) -> bool {
+        // Check shortcuts before the focused node
+        if event.state == KeyState::Down {
+            let chord = Modifiers::CONTROL | Modifiers::ALT | Modifiers::META;
+            let mods = Modifiers::from_bits_truncate(event.mods.raw().bits()) & (chord | Modifiers::SHIFT);
+            if mods.intersects(chord) || self.focused_node.is_none() {
+                if let Some((_, _, callback)) = self.shortcuts.iter().find(|(m, k, _)| *m == mods && same_key(k, &event.key)) {
+                    let phase = callback(state);
+                    self.update_phase(phase);
+                    return true;
+                }
+                if let Some(command) = self.commands.find_shortcut(mods, &event.key) {
+                    if let Some(phase) = command.run(state) {
+                        self.update_phase(phase);
+                        return true;
+                    }
+                }
+            }
+        }
+
         if let Some(tree) = &mut self.tree_cache {
             let tree = tree.borrow_mut();
 
@@ -338,26 +596,41 @@ impl<S, H: Clone> Viewport<S, H> {
                 0
             };
 
-            let default_style = Style::default();
-            let default_layout = Layout::default();
+            let event_type = if event.state == KeyState::Down { On::KeyDown } else { On::KeyUp };
+
+            let mut ctx = EventCtx::new(
+                EventInfo::Keyboard(event),
+                self.handle.clone(),
+                self.resource_loader.clone(),
+                self.focused_node,
+                self.captured_node,
+                self.anim_tasks.clone(),
+                self.key_rects.clone(),
+            );
+
+            let mut handled = false;
+            let mut phase = Phase::Idle;
 
             if tree[id].has_callback(On::Keyboard) {
-                let mut ctx = EventCtx {
-                    info: EventInfo::Keyboard(event),
-                    platform_handle: self.handle.clone(),
-                    resource_loader: self.resource_loader.clone(),
-                    focus: self.focused_node,
-                    style: default_style,
-                    layout: default_layout,
-                    change: false,
-                    anim_tasks: self.anim_tasks.clone(),
-                };
+                phase.update(Self::dispatch_event(On::Keyboard, state, &mut ctx, tree, id));
+                handled = true;
+            }
+
+            // Key down and up events bubble from the focused node through its ancestors
+            let mut curr = id;
+            while curr != usize::MAX && !ctx.stopped {
+                if tree[curr].has_callback(event_type) {
+                    phase.update(Self::dispatch_event(event_type, state, &mut ctx, tree, curr));
+                    handled = true;
+                }
+                curr = tree[curr].parent;
+            }
 
-                let mut phase = Self::dispatch_event(On::Keyboard, state, &mut ctx, tree, id);
+            if handled {
                 phase.update(self.handle_ctx(state, ctx));
                 self.update_phase(phase);
-                return true;
             }
+            return handled;
         }
         false
     }
@@ -368,19 +641,15 @@ impl<S, H: Clone> Viewport<S, H> {
 
         // If requested, dispatch a change event
         if ctx.change {
-            let default_style = Style::default();
-            let default_layout = Layout::default();
-
-            let mut change_ctx: EventCtx<S, H> = EventCtx {
-                info: EventInfo::None,
-                platform_handle: ctx.platform_handle.clone(),
-                resource_loader: ctx.resource_loader.clone(),
-                focus: ctx.focus,
-                style: default_style,
-                layout: default_layout,
-                change: false,
-                anim_tasks: ctx.anim_tasks.clone(),
-            };
+            let mut change_ctx: EventCtx<S, H> = EventCtx::new(
+                EventInfo::None,
+                ctx.platform_handle.clone(),
+                ctx.resource_loader.clone(),
+                ctx.focus,
+                ctx.capture,
+                ctx.anim_tasks.clone(),
+                ctx.key_rects.clone(),
+            );
 
             if event_type != On::Change && tree[id].has_callback(On::Change) {
                 phase.update(Self::dispatch_event(On::Change, state, &mut change_ctx, tree, id));
@@ -391,6 +660,12 @@ impl<S, H: Clone> Viewport<S, H> {
                     if tree[curr].has_callback(On::Change) {
                         phase.update(Self::dispatch_event(On::Change, state, &mut change_ctx, tree, curr));
                         ctx.focus = change_ctx.focus;
+                        ctx.capture = change_ctx.capture;
+                        ctx.quit |= change_ctx.quit;
+                        ctx.broadcast.update(change_ctx.broadcast);
+                        ctx.new_windows.append(&mut change_ctx.new_windows);
+                        ctx.commands.append(&mut change_ctx.commands);
+                        ctx.file_dialogs.append(&mut change_ctx.file_dialogs);
                         return phase;
                     }
                     curr = tree[curr].parent;
@@ -403,30 +678,37 @@ impl<S, H: Clone> Viewport<S, H> {
             }
 
             ctx.focus = change_ctx.focus;
+            ctx.capture = change_ctx.capture;
+            ctx.quit |= change_ctx.quit;
+            ctx.broadcast.update(change_ctx.broadcast);
+            ctx.new_windows.append(&mut change_ctx.new_windows);
+            ctx.commands.append(&mut change_ctx.commands);
+            ctx.file_dialogs.append(&mut change_ctx.file_dialogs);
         }
 
         phase
     }
 
-    fn handle_ctx(&mut self, state: &mut S, ctx: EventCtx<S, H>) -> Phase {
+    fn handle_ctx(&mut self, state: &mut S, mut ctx: EventCtx<S, H>) -> Phase {
         let mut phase = Phase::Idle;
+        self.quit_requested |= ctx.quit;
+        self.broadcast_phase.update(ctx.broadcast);
+        self.new_windows.append(&mut ctx.new_windows);
+        self.file_dialogs.append(&mut ctx.file_dialogs);
+        let mut commands = std::mem::take(&mut ctx.commands);
 
         if let Some(tree) = &mut self.tree_cache {
             let tree = tree.borrow_mut();
 
-            let default_style = Style::default();
-            let default_layout = Layout::default();
-
-            let mut focus_ctx: EventCtx<S, H> = EventCtx {
-                info: EventInfo::None,
-                platform_handle: ctx.platform_handle.clone(),
-                resource_loader: ctx.resource_loader.clone(),
-                focus: ctx.focus,
-                style: default_style,
-                layout: default_layout,
-                change: false,
-                anim_tasks: self.anim_tasks.clone(),
-            };
+            let mut focus_ctx: EventCtx<S, H> = EventCtx::new(
+                EventInfo::None,
+                ctx.platform_handle.clone(),
+                ctx.resource_loader.clone(),
+                ctx.focus,
+                ctx.capture,
+                self.anim_tasks.clone(),
+                self.key_rects.clone(),
+            );
 
             // Dispatch focus and blur events
             match (self.focused_node, ctx.focus) {
@@ -452,9 +734,23 @@ impl<S, H: Clone> Viewport<S, H> {
                 }
                 (None, None) => {}
             }
+
+            self.quit_requested |= focus_ctx.quit;
+            self.broadcast_phase.update(focus_ctx.broadcast);
+            self.new_windows.append(&mut focus_ctx.new_windows);
+            self.file_dialogs.append(&mut focus_ctx.file_dialogs);
+            commands.append(&mut focus_ctx.commands);
         }
 
         self.focused_node = ctx.focus;
+        self.captured_node = ctx.capture;
+
+        // Run dispatched commands now that no callbacks are borrowing the state
+        for id in commands {
+            if let Some(command_phase) = self.commands.run(id, state) {
+                phase.update(command_phase);
+            }
+        }
 
         phase
     }
@@ -474,6 +770,8 @@ impl<S, H: Clone> Viewport<S, H> {
         Alloc::set_thread_local_alloc(Some(self.alloc.clone()));
         let alloc = self.alloc.clone();
         self.temp.reset();
+        let frame_start = Instant::now();
+        let restyle = self.phase >= Phase::Style || self.tree_cache.is_none();
 
         // ---------- Build Phase ----------
         if self.phase == Phase::Build || self.tree_cache.is_none() {
@@ -499,29 +797,57 @@ impl<S, H: Clone> Viewport<S, H> {
                 alloc.scope(|| alloc.vec_capacity(len))
             };
 
-            stylesheet::apply_static_styles(&self.temp, tree.borrow(), styles.borrow_mut());
+            stylesheet::apply_static_styles(&self.temp, tree.borrow(), self.color_scheme, self.size, styles.borrow_mut());
             self.tree_cache = Some(tree);
             self.style_cache = Some(styles);
+        } else if let (Phase::Style, Some(tree), Some(styles)) = (self.phase, &self.tree_cache, &mut self.style_cache) {
+            // ---------- Style Phase ----------
+            let tree: &BumpVec<ArrayNode<S, H>> = tree.borrow();
+            let styles: &mut BumpVec<Style> = styles.borrow_mut();
+            styles.clear();
+            stylesheet::apply_static_styles(&self.temp, tree, self.color_scheme, self.size, styles);
         }
 
+        let build_end = Instant::now();
         let tree: &mut BumpVec<ArrayNode<S, H>> = self.tree_cache.as_mut().unwrap().borrow_mut();
         let styles: &mut BumpVec<Style> = self.style_cache.as_mut().unwrap().borrow_mut();
 
         // Stash default styles, apply hover/focus styles, and run style callbacks
         let mut default_styles: BumpVec<(usize, Style)> = BumpVec::new_in(&self.temp);
-        if self.phase != Phase::Idle {
+
+        // TODO - set phase to layout only if needed
+        // NOTE: If the tree was just rebuilt, hot_nodes is stale until refresh_pointer() is called after drawing
+        stylesheet::apply_dynamic_styles(
+            &self.temp,
+            tree,
+            self.color_scheme,
+            self.focused_node,
+            &self.hot_nodes,
+            styles,
+            &mut default_styles,
+        );
+
+        // Style callbacks run last, so they can override any stylesheet rule.
+        // They only run again when the styles are recomputed, or when hover or focus changes the style they're given.
+        // Otherwise their results from the last time are reused, since their changes are undone after each draw.
+        if restyle || self.callback_hot_nodes != self.hot_nodes || self.callback_focus != self.focused_node {
+            self.callback_styles.clear();
             for (id, node) in tree.iter_mut().enumerate() {
                 if let Some(style_callback) = &mut node.style_callback {
                     default_styles.push((id, styles[id].clone()));
                     style_callback(state, &mut styles[id]);
+                    self.callback_styles.push((id, styles[id].clone()));
                 }
             }
+            self.callback_hot_nodes.clone_from(&self.hot_nodes);
+            self.callback_focus = self.focused_node;
+        } else {
+            for (id, style) in &self.callback_styles {
+                default_styles.push((*id, std::mem::replace(&mut styles[*id], style.clone())));
+            }
         }
-
-        // TODO - set phase to layout only if needed
-        // TODO - what happens if the tree was just rebuilt? Need to populate hot_nodes from prev_hot_keys
-        stylesheet::apply_dynamic_styles(&self.temp, tree, self.focused_node, &self.hot_nodes, styles, &mut default_styles);
         self.phase = Phase::Layout;
+        let style_end = Instant::now();
 
         // ---------- Layout Phase ----------
         if self.phase >= Phase::Layout || self.layout_cache.is_none() {
@@ -541,32 +867,378 @@ impl<S, H: Clone> Viewport<S, H> {
                 layout.push(Layout::default());
             }
 
-            layout::layout(&self.temp, tree, styles, self.size.into(), layout);
+            let snap_scale = if self.pixel_snap { Some(self.scale) } else { None };
+            layout::layout(&self.temp, tree, styles, self.size.into(), snap_scale, layout);
 
             for (id, node) in tree.iter_mut().enumerate() {
                 if let Some(layout_callback) = &mut node.layout_callback {
                     layout_callback(state, layout[id].size);
                 }
             }
+
+            let key_rects = self.key_map.iter().map(|(&key, &id)| {
+                let Layout { position, size } = layout[id];
+                let rect = kurbo::Rect::from_origin_size((position.x as f64, position.y as f64), (size.width as f64, size.height as f64));
+                (key, rect)
+            });
+            self.key_rects = Rc::new(key_rects.collect());
         }
 
         let layout: &BumpVec<Layout> = self.layout_cache.as_ref().unwrap().borrow();
+        let layout_end = Instant::now();
 
         // ---------- Draw Phase ----------
         // TODO - If phase == Idle, re-issue commands from last frame
         if let Some(piet) = piet {
             draw::draw(&self.temp, state, tree, styles, layout, piet);
         }
+        let draw_end = Instant::now();
+
+        if let Some(recorder) = &mut self.frame_recorder {
+            recorder.push(FrameStats {
+                start: frame_start.saturating_duration_since(recorder.epoch()),
+                build: build_end - frame_start,
+                style: style_end - build_end,
+                layout: layout_end - style_end,
+                draw: draw_end - layout_end,
+                node_count: tree.len(),
+            });
+        }
 
         // ---------- Cleanup ----------
         Alloc::set_thread_local_alloc(None);
         self.phase = Phase::Idle;
 
-        // Restore default styles
-        for (id, style) in default_styles {
+        // Keep the cursors before dynamic styles are undone, since a hover rule or style callback may have set them
+        self.cursors.clear();
+        self.cursors.extend(styles.iter().map(|style| style.cursor));
+
+        // Restore default styles. A node may be stashed twice, so go in reverse to restore the earliest copy.
+        for (id, style) in default_styles.into_iter().rev() {
             styles[id] = style;
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct State {
+        enters: Cell<u32>,
+        leaves: Cell<u32>,
+        key: Key,
+        shortcuts: u32,
+        events: Vec<&'static str>,
+    }
+
+    // An unkeyed node in the top left corner that asks for a rebuild whenever the pointer enters it
+    fn hover_view(_: &State) -> View<State, ()> {
+        let node = View::default()
+            .inline_style("width: 10px; height: 10px")
+            .event(On::PointerEnter, |s: &mut State, _| {
+                s.enters.set(s.enters.get() + 1);
+                Some(Phase::Build)
+            })
+            .event(On::PointerLeave, |s: &mut State, _| {
+                s.leaves.set(s.leaves.get() + 1);
+                Some(Phase::Draw)
+            });
+        View::default().add_child(node)
+    }
+
+    #[test]
+    fn rebuild_keeps_unkeyed_hover() {
+        let mut state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), hover_view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        viewport.pointer_move(&mut state, RawPointerEvent::at(5.0, 5.0));
+        assert_eq!(state.enters.get(), 1);
+
+        // Each rebuild is followed by a refresh, like after painting a frame
+        for _ in 0..3 {
+            viewport.draw(&state, None).unwrap();
+            viewport.refresh_pointer(&mut state);
+        }
+        assert_eq!(state.enters.get(), 1);
+        assert_eq!(state.leaves.get(), 0);
+        // Nothing changed, so nothing asked for another rebuild
+        assert!(viewport.is_idle());
+
+        viewport.pointer_move(&mut state, RawPointerEvent::at(50.0, 50.0));
+        assert_eq!(state.leaves.get(), 1);
+
+        viewport.pointer_move(&mut state, RawPointerEvent::at(5.0, 5.0));
+        viewport.draw(&state, None).unwrap();
+        viewport.pointer_leave(&mut state);
+        assert_eq!((state.enters.get(), state.leaves.get()), (2, 2));
+    }
+
+    #[test]
+    fn viewport_units_follow_resizes() {
+        fn view(state: &State) -> View<State, ()> {
+            View::default().add_child(View::default().key(state.key).inline_style("width: 50vw; height: 10vmin"))
+        }
+
+        let state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 80.0), ());
+        viewport.draw(&state, None).unwrap();
+        let size = |viewport: &Viewport<State, ()>| {
+            let style = viewport.get_style(state.key).unwrap();
+            (style.width, style.height)
+        };
+        assert_eq!(size(&viewport), (Some(50.0), Some(8.0)));
+
+        viewport.size((300.0, 200.0));
+        assert_eq!(viewport.phase, Phase::Style);
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(size(&viewport), (Some(150.0), Some(20.0)));
+    }
+
+    #[test]
+    fn resizing_only_restyles_for_viewport_units() {
+        fn view(_: &State) -> View<State, ()> {
+            let sheet = Stylesheet::parse(".a { width: 50%; } .a:hover { height: 5vh; }");
+            View::default().use_style_sheet(Some(sheet)).add_classes("a")
+        }
+
+        let state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), hover_view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+        viewport.size((200.0, 200.0));
+        assert_eq!(viewport.phase, Phase::Layout);
+
+        // A dynamic rule counts too, since it's applied on top of the static styles
+        viewport.set_view(view);
+        viewport.draw(&state, None).unwrap();
+        viewport.size((300.0, 300.0));
+        assert_eq!(viewport.phase, Phase::Style);
+    }
+
+    fn key_down(key: &str, mods: Modifiers) -> KeyEvent {
+        KeyEvent {
+            state: KeyState::Down,
+            key: KbKey::Character(key.into()),
+            mods: mods.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn shortcuts_ignore_case_and_respect_focus() {
+        let mut state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), hover_view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+        viewport.add_shortcut(
+            Modifiers::CONTROL | Modifiers::SHIFT,
+            KbKey::Character("k".into()),
+            |s: &mut State| {
+                s.shortcuts += 1;
+                Phase::Idle
+            },
+        );
+        viewport.add_shortcut(Modifiers::empty(), KbKey::Character("j".into()), |s: &mut State| {
+            s.shortcuts += 10;
+            Phase::Idle
+        });
+
+        // Shift reports an upper case character
+        assert!(viewport.key_event(&mut state, key_down("K", Modifiers::CONTROL | Modifiers::SHIFT)));
+        assert!(!viewport.key_event(&mut state, key_down("K", Modifiers::SHIFT)));
+        assert!(viewport.key_event(&mut state, key_down("J", Modifiers::empty())));
+        assert_eq!(state.shortcuts, 11);
+
+        // Plain keys go to the focused node instead
+        viewport.focused_node = Some(state.key);
+        assert!(!viewport.key_event(&mut state, key_down("j", Modifiers::empty())));
+        assert!(viewport.key_event(&mut state, key_down("k", Modifiers::CONTROL | Modifiers::SHIFT)));
+        assert_eq!(state.shortcuts, 12);
+    }
+
+    #[test]
+    fn key_events_bubble_until_stopped() {
+        fn view(state: &State) -> View<State, ()> {
+            let focused = View::default().key(state.key).event(On::KeyDown, |s: &mut State, ctx| {
+                s.events.push("focused");
+                if ctx.keyboard()?.key == KbKey::Character("x".into()) {
+                    ctx.stop_propagation();
+                }
+                Some(Phase::Idle)
+            });
+            View::default()
+                .event(On::KeyDown, |s: &mut State, _| {
+                    s.events.push("root down");
+                    Some(Phase::Idle)
+                })
+                .event(On::KeyUp, |s: &mut State, _| {
+                    s.events.push("root up");
+                    Some(Phase::Idle)
+                })
+                .add_child(View::default().add_child(focused))
+        }
+
+        let mut state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+        viewport.focused_node = Some(state.key);
+
+        assert!(viewport.key_event(&mut state, key_down("a", Modifiers::empty())));
+        assert!(viewport.key_event(&mut state, key_down("x", Modifiers::empty())));
+        let up = KeyEvent {
+            state: KeyState::Up,
+            ..key_down("x", Modifiers::empty())
+        };
+        assert!(viewport.key_event(&mut state, up));
+        assert_eq!(state.events, ["focused", "root down", "focused", "root up"]);
+    }
+
+    #[test]
+    fn wheel_handlers_can_stop_outer_nodes() {
+        fn view(_: &State) -> View<State, ()> {
+            let inner = View::default()
+                .inline_style("width: 10px; height: 10px")
+                .event(On::PointerWheel, |s: &mut State, ctx| {
+                    let info = ctx.pointer()?;
+                    s.events.push(if info.wheel_y > 0.0 { "inner down" } else { "inner up" });
+                    if info.mods.contains(Modifiers::CONTROL) {
+                        ctx.stop_propagation();
+                    }
+                    Some(Phase::Idle)
+                });
+            let outer = View::default().add_child(inner).event(On::PointerWheel, |s: &mut State, _| {
+                s.events.push("outer");
+                Some(Phase::Idle)
+            });
+            View::default().add_child(outer)
+        }
+
+        let mut state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        let scroll = RawPointerEvent {
+            wheel_y: 3.0,
+            ..RawPointerEvent::at(5.0, 5.0)
+        };
+        viewport.pointer_wheel(&mut state, scroll);
+        let zoom = RawPointerEvent {
+            wheel_y: -3.0,
+            mods: Modifiers::CONTROL,
+            ..scroll
+        };
+        viewport.pointer_wheel(&mut state, zoom);
+        assert_eq!(state.events, ["inner down", "outer", "inner up"]);
+    }
+
+    #[test]
+    fn quit_requests_are_taken_once() {
+        fn view(_: &State) -> View<State, ()> {
+            let button = View::default()
+                .inline_style("width: 10px; height: 10px")
+                .event(On::PointerDown, |_, ctx| {
+                    ctx.quit();
+                    Some(Phase::Idle)
+                });
+            View::default().add_child(button).event(On::WindowClose, |s: &mut State, _| {
+                s.events.push("close");
+                Some(Phase::Idle)
+            })
+        }
+
+        let mut state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+        assert!(!viewport.take_quit_request());
+
+        viewport.pointer_down(&mut state, RawPointerEvent::at(5.0, 5.0).press(PointerButton::Left));
+        assert!(viewport.take_quit_request());
+        assert!(!viewport.take_quit_request());
+
+        // The platform layer runs the close callbacks before closing the window
+        viewport.close(&mut state);
+        assert_eq!(state.events, ["close"]);
+        assert!(!viewport.take_quit_request());
+    }
+
+    #[test]
+    fn broadcasts_are_merged_and_taken_once() {
+        fn view(_: &State) -> View<State, ()> {
+            View::default().event(On::PointerDown, |_, ctx| {
+                ctx.broadcast(Phase::Draw);
+                ctx.broadcast(Phase::Layout);
+                ctx.broadcast(Phase::Idle);
+                Some(Phase::Idle)
+            })
+        }
+
+        let mut state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(viewport.take_broadcast(), Phase::Idle);
+
+        viewport.pointer_down(&mut state, RawPointerEvent::at(5.0, 5.0).press(PointerButton::Left));
+        assert_eq!(viewport.take_broadcast(), Phase::Layout);
+        assert_eq!(viewport.take_broadcast(), Phase::Idle);
+    }
+
+    #[test]
+    fn drag_regions_leave_clickable_children_alone() {
+        fn view(_: &State) -> View<State, ()> {
+            let button = View::default()
+                .inline_style("width: 10px; height: 10px; flex-grow: 0")
+                .event(On::PointerDown, |_, _| Some(Phase::Idle));
+            let title_bar = View::default()
+                .inline_style("height: 20px; flex-grow: 0")
+                .window_drag_region()
+                .add_child(button);
+            View::default().inline_style("flex-direction: column").add_child(title_bar)
+        }
+
+        let mut state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        let mut over = |x, y| {
+            viewport.pointer_move(&mut state, RawPointerEvent::at(x, y));
+            viewport.is_over_drag_region()
+        };
+        assert!(over(50.0, 15.0));
+        assert!(!over(5.0, 5.0));
+        assert!(over(5.0, 15.0));
+        assert!(!over(50.0, 50.0));
+    }
+
+    #[test]
+    fn new_windows_are_queued_for_the_platform() {
+        fn view(_: &State) -> View<State, ()> {
+            let button = View::default()
+                .inline_style("width: 10px; height: 10px")
+                .event(On::PointerDown, |_, ctx| {
+                    ctx.open_window(
+                        WindowDesc::new(ViewFn::new(b"view", view))
+                            .with_title("Second")
+                            .with_size(300.0, 200.0),
+                    );
+                    Some(Phase::Idle)
+                });
+            View::default().add_child(button)
+        }
+
+        let mut state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+        viewport.pointer_down(&mut state, RawPointerEvent::at(5.0, 5.0).press(PointerButton::Left));
+
+        let windows = viewport.take_new_windows();
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].title.as_deref(), Some("Second"));
+        assert_eq!(windows[0].size, (300.0, 200.0));
+        assert!(viewport.take_new_windows().is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+#![forbid(unsafe_code)]
+
+use druid_shell::piet::{RenderContext, TextLayout, TextLayoutBuilder};
+
+use crate::prelude::*;
+use crate::style::{AlignItems, FlexDirection};
+use crate::text::text_layout_builder;
+
+// ---------- Badge ----------
+/// Put a small count on the top-right corner of `target`, like the number of unread messages.
+/// The badge is hidden when `count` is zero, and shows `cap+` when `count` is larger than `cap`.
+///
+/// The badge has the class `badge`, and is a pill sized by its `font-size`. The count is drawn centered in its `color`.
+pub fn badge<S, H>(target: View<S, H>, count: u32, cap: u32) -> View<S, H> {
+    let result = ui!([{
+        .on_style(|_, style: &mut Style| {
+            style.flex_direction = FlexDirection::Row;
+            style.align_items = AlignItems::FlexStart;
+        })
+    }
+    (target)
+    ]);
+
+    let text = match badge_text(count, cap) {
+        Some(text) => text,
+        None => return result,
+    };
+
+    // Estimate the width from the number of digits, since text isn't measured during layout
+    let digits = text.chars().count() as f32;
+
+    result.add_child(ui!("badge" [{
+        .on_style(move |_, style: &mut Style| {
+            let height = style.font_size * 1.4;
+            let radius = height / 2.0;
+            style.height = Some(height);
+            style.min_width = height.max(style.font_size * (0.6 * digits + 0.8));
+            style.border_top_left_radius = radius;
+            style.border_top_right_radius = radius;
+            style.border_bottom_left_radius = radius;
+            style.border_bottom_right_radius = radius;
+            style.flex_shrink = 0.0;
+
+            // Pull the badge back over the corner of the target
+            style.margin_left = Some(-height);
+            style.margin_top = Some(-radius);
+        })
+        .on_draw(true, move |_, ctx: &mut DrawCtx| {
+            let layout = text_layout_builder(ctx.piet.text(), text.clone(), ctx.style).build().unwrap();
+
+            let size = layout.size();
+            ctx.piet.draw_text(&layout, ((ctx.width - size.width) / 2.0, (ctx.height - size.height) / 2.0));
+        })
+    }]))
+}
+
+fn badge_text(count: u32, cap: u32) -> Option<String> {
+    if count == 0 {
+        None
+    } else if count > cap {
+        Some(format!("{}+", cap))
+    } else {
+        Some(count.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_are_capped() {
+        assert_eq!(badge_text(0, 99), None);
+        assert_eq!(badge_text(7, 99).as_deref(), Some("7"));
+        assert_eq!(badge_text(99, 99).as_deref(), Some("99"));
+        assert_eq!(badge_text(100, 99).as_deref(), Some("99+"));
+    }
+
+    #[test]
+    fn zero_leaves_the_badge_out() {
+        fn view(count: &u32) -> View<u32, ()> {
+            View::default().add_child(badge(View::default(), *count, 9))
+        }
+
+        let mut count = 0;
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.record_frames(2);
+        viewport.draw(&count, None).unwrap();
+        count = 12;
+        viewport.update_phase(Phase::Build);
+        viewport.draw(&count, None).unwrap();
+
+        let nodes: Vec<usize> = viewport.frame_recorder().unwrap().frames().map(|frame| frame.node_count).collect();
+        assert_eq!(nodes, [3, 4]);
+    }
+}
@@ -0,0 +1,92 @@
+#![forbid(unsafe_code)]
+
+use druid_shell::{
+    kurbo::{Affine, Rect},
+    piet::RenderContext,
+};
+
+use crate::prelude::*;
+
+// ---------- Canvas ----------
+/// A node for custom drawing, like charts and diagrams. `func` is called when the node is drawn,
+/// with the origin at the top-left of the content box, `width` and `height` set to the content box's size,
+/// and drawing clipped to the content box.
+pub fn canvas<S, H>(func: impl Fn(&S, &mut DrawCtx) + 'static) -> View<S, H> {
+    ui!([
+        .on_draw(false, move |state: &S, ctx: &mut DrawCtx| {
+            let style = ctx.style;
+            let left = (style.border_left_width + style.padding_left) as f64;
+            let top = (style.border_top_width + style.padding_top) as f64;
+            let width = ctx.width - (style.border_left_width + style.border_right_width + style.padding_left + style.padding_right) as f64;
+            let height = ctx.height - (style.border_top_width + style.border_bottom_width + style.padding_top + style.padding_bottom) as f64;
+            if width <= 0.0 || height <= 0.0 { return }
+            let must_draw = ctx.must_draw;
+
+            let _ = ctx.piet.with_save(|piet| {
+                piet.clip(Rect::new(left, top, left + width, top + height));
+                piet.transform(Affine::translate((left, top)));
+
+                let mut content = DrawCtx {
+                    piet,
+                    style,
+                    width,
+                    height,
+                    must_draw,
+                };
+                func(state, &mut content);
+                Ok(())
+            });
+        })
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use druid_shell::piet::Device;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct State {
+        size: Cell<Option<(f64, f64)>>,
+    }
+
+    fn draw(viewport: &mut Viewport<State, ()>, state: &State) {
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(100, 100, 1.0).unwrap();
+        let mut piet = target.render_context();
+        viewport.draw(state, Some(&mut piet)).unwrap();
+        piet.finish().unwrap();
+    }
+
+    #[test]
+    fn draws_in_the_content_box() {
+        fn view(_: &State) -> View<State, ()> {
+            let canvas = canvas(|s: &State, ctx| s.size.set(Some((ctx.width, ctx.height))))
+                .inline_style("width: 60px; height: 40px; flex-grow: 0; padding: 5px; border-width: 2px; border-left-width: 8px");
+            View::default().add_child(canvas)
+        }
+
+        let state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        draw(&mut viewport, &state);
+        // Width and height set the content box, and the node's padding and borders go around it
+        assert_eq!(state.size.get(), Some((60.0, 40.0)));
+    }
+
+    #[test]
+    fn skips_empty_content_boxes() {
+        fn view(_: &State) -> View<State, ()> {
+            let canvas = canvas(|s: &State, ctx| s.size.set(Some((ctx.width, ctx.height))))
+                .inline_style("width: 0px; height: 10px; flex-grow: 0; padding: 5px");
+            View::default().add_child(canvas)
+        }
+
+        let state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        draw(&mut viewport, &state);
+        assert_eq!(state.size.get(), None);
+    }
+}
@@ -0,0 +1,194 @@
+#![forbid(unsafe_code)]
+
+use std::{cell::Cell, fmt::Debug, rc::Rc};
+
+use druid_shell::{
+    kurbo::{BezPath, Line, Point, Rect},
+    piet::RenderContext,
+};
+
+use crate::prelude::*;
+
+// ---------- Checkbox ----------
+/// A checkbox that's checked (`Some(true)`), unchecked (`Some(false)`), or indeterminate (`None`).
+/// The node matches `:checked` and `:indeterminate` selectors, and the glyph is drawn with the `color` property.
+#[derive(Debug)]
+pub struct Checkbox {
+    pub key: Key,
+    data: Rc<Data>,
+}
+
+#[derive(Debug)]
+struct Data {
+    value: Cell<Option<bool>>,
+    changed: Cell<bool>,
+}
+
+impl Checkbox {
+    pub fn new(value: Option<bool>) -> Self {
+        Self {
+            key: Key::new(),
+            data: Rc::new(Data {
+                value: Cell::new(value),
+                changed: Cell::new(false),
+            }),
+        }
+    }
+
+    /// Set the state. Returns `Phase::Build`, since `:checked` and `:indeterminate` are matched when the tree is built.
+    pub fn set(&self, new_value: Option<bool>) -> Phase {
+        self.data.value.replace(new_value);
+        self.data.changed.replace(true);
+        Phase::Build
+    }
+
+    pub fn get(&self) -> Option<bool> {
+        self.data.value.get()
+    }
+
+    pub fn view<S, H>(&self) -> View<S, H> {
+        let weak1 = Rc::downgrade(&self.data);
+        let weak2 = Rc::downgrade(&self.data);
+        let value = self.data.value.get();
+
+        ui!([
+            .key(self.key)
+            .checked(value == Some(true))
+            .indeterminate(value.is_none())
+            .event(On::PointerDown, move |_, ctx: &mut EventCtx<S, H>| {
+                let this = if let Some(this) = weak1.upgrade() { this } else { return Some(Phase::Idle) };
+                let info = ctx.pointer()?;
+
+                if info.button.is_left() {
+                    // Unchecked and indeterminate boxes both become checked
+                    this.value.set(Some(this.value.get() != Some(true)));
+                    this.changed.set(true);
+
+                    ctx.emit_change();
+                    Some(Phase::Build)
+                } else {
+                    Some(Phase::Idle)
+                }
+            })
+            .on_draw(true, move |_, ctx: &mut DrawCtx| {
+                // If the underlying data is gone, then just return since there's nothing to draw.
+                let this = if let Some(this) = weak2.upgrade() { this } else { return };
+                if !this.changed.get() && !ctx.must_draw { return }
+                this.changed.set(false);
+
+                let color = ctx.style.color.clone();
+                let size = ctx.width.min(ctx.height);
+
+                ctx.piet.stroke(Rect::new(1.0, 1.0, size - 1.0, size - 1.0), &color, 2.0);
+
+                match this.value.get() {
+                    Some(true) => {
+                        let mut check = BezPath::new();
+                        check.move_to(Point::new(size * 0.25, size * 0.5));
+                        check.line_to(Point::new(size * 0.45, size * 0.7));
+                        check.line_to(Point::new(size * 0.75, size * 0.3));
+                        ctx.piet.stroke(check, &color, 2.0);
+                    }
+                    None => {
+                        let dash = Line::new(Point::new(size * 0.25, size * 0.5), Point::new(size * 0.75, size * 0.5));
+                        ctx.piet.stroke(dash, &color, 2.0);
+                    }
+                    Some(false) => {}
+                }
+            })
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use druid_shell::piet::{Device, ImageFormat};
+
+    use super::*;
+    use crate::callbacks::{PointerButton, RawPointerEvent};
+
+    struct State {
+        style: Stylesheet,
+        checkbox: Checkbox,
+        changes: u32,
+    }
+
+    fn view(state: &State) -> View<State, ()> {
+        View::default()
+            .use_style_sheet(Some(state.style.clone()))
+            .event(On::Change, |s: &mut State, _| {
+                s.changes += 1;
+                Some(Phase::Idle)
+            })
+            .add_child(state.checkbox.view().add_classes("box"))
+    }
+
+    fn width(viewport: &Viewport<State, ()>, state: &State) -> Option<f32> {
+        viewport.get_style(state.checkbox.key).unwrap().width
+    }
+
+    #[test]
+    fn clicks_cycle_through_the_states() {
+        let mut state = State {
+            style: Stylesheet::parse(
+                ".box { height: 10px; width: 10px; } .box:checked { width: 20px; } .box:indeterminate { width: 30px; }",
+            ),
+            checkbox: Checkbox::new(None),
+            changes: 0,
+        };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(width(&viewport, &state), Some(30.0));
+
+        let click = RawPointerEvent::at(5.0, 5.0).press(PointerButton::Left);
+        viewport.pointer_down(&mut state, click);
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(state.checkbox.get(), Some(true));
+        assert_eq!(width(&viewport, &state), Some(20.0));
+
+        viewport.pointer_down(&mut state, click);
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(state.checkbox.get(), Some(false));
+        assert_eq!(width(&viewport, &state), Some(10.0));
+        assert_eq!(state.changes, 2);
+
+        // Setting it from the state doesn't report a change
+        viewport.update_phase(state.checkbox.set(None));
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(width(&viewport, &state), Some(30.0));
+        assert_eq!(state.changes, 2);
+    }
+
+    // Draw the checkbox, and return the alpha of the pixel at each point
+    fn alphas(viewport: &mut Viewport<State, ()>, state: &State, points: &[(usize, usize)]) -> Vec<u8> {
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(100, 100, 1.0).unwrap();
+        let mut piet = target.render_context();
+        viewport.draw(state, Some(&mut piet)).unwrap();
+        piet.finish().unwrap();
+        drop(piet);
+
+        let image = target.to_image_buf(ImageFormat::RgbaPremul).unwrap();
+        points.iter().map(|(x, y)| image.raw_pixels()[(y * 100 + x) * 4 + 3]).collect()
+    }
+
+    #[test]
+    fn each_state_draws_its_glyph() {
+        let state = State {
+            style: Stylesheet::parse(".box { height: 40px; width: 40px; }"),
+            checkbox: Checkbox::new(Some(true)),
+            changes: 0,
+        };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+
+        // Points on the border, on the check's first stroke, and on the dash
+        let points = [(1, 20), (14, 24), (12, 19)];
+        assert_eq!(alphas(&mut viewport, &state, &points), [255, 255, 0]);
+
+        viewport.update_phase(state.checkbox.set(None));
+        assert_eq!(alphas(&mut viewport, &state, &points), [255, 0, 255]);
+
+        viewport.update_phase(state.checkbox.set(Some(false)));
+        assert_eq!(alphas(&mut viewport, &state, &points), [255, 0, 0]);
+    }
+}
@@ -0,0 +1,261 @@
+#![forbid(unsafe_code)]
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Debug,
+    rc::{Rc, Weak},
+    time::Duration,
+};
+
+use crate::prelude::*;
+use crate::style::FlexDirection;
+use crate::widgets::reveal;
+
+// ---------- Collapsible ----------
+/// A section with a header that shows or hides its content when clicked. The height animates when it opens and closes.
+/// The header has the class `header`, plus `expanded` while open, and the content is wrapped in a node with the class `content`.
+#[derive(Debug)]
+pub struct Collapsible {
+    pub key: Key,
+    data: Rc<Data>,
+}
+
+/// Collapsible sections that only allow one of them to be open at a time, like an accordion.
+#[derive(Debug, Default, Clone)]
+pub struct CollapsibleGroup {
+    members: Rc<RefCell<Vec<Weak<Data>>>>,
+}
+
+#[derive(Debug)]
+struct Data {
+    open: Cell<bool>,
+    // How far open the section is, from 0.0 to 1.0
+    progress: Cell<f64>,
+    animating: Cell<bool>,
+    duration: Cell<Duration>,
+    // The node that clips the content, and the content's natural height after the last layout
+    content_key: Key,
+    content_height: Rc<Cell<f32>>,
+    group: RefCell<Option<CollapsibleGroup>>,
+}
+
+impl Collapsible {
+    pub fn new(open: bool) -> Self {
+        Self {
+            key: Key::new(),
+            data: Rc::new(Data {
+                open: Cell::new(open),
+                progress: Cell::new(if open { 1.0 } else { 0.0 }),
+                animating: Cell::new(false),
+                duration: Cell::new(Duration::from_millis(150)),
+                content_key: Key::new(),
+                content_height: Rc::new(Cell::new(0.0)),
+                group: RefCell::new(None),
+            }),
+        }
+    }
+
+    /// Set how long opening and closing takes. Use `Duration::ZERO` to disable the animation.
+    pub fn with_duration(self, duration: Duration) -> Self {
+        self.data.duration.set(duration);
+        self
+    }
+
+    /// Add this section to a group, so opening it closes the others.
+    pub fn with_group(self, group: &CollapsibleGroup) -> Self {
+        group.members.borrow_mut().push(Rc::downgrade(&self.data));
+        self.data.group.replace(Some(group.clone()));
+        self
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.data.open.get()
+    }
+
+    /// Open or close the section immediately, without animating.
+    pub fn set_open(&self, open: bool) -> Phase {
+        self.data.open.set(open);
+        self.data.progress.set(if open { 1.0 } else { 0.0 });
+        Phase::Build
+    }
+
+    pub fn view<S, H>(&self, header: View<S, H>, content: View<S, H>) -> View<S, H> {
+        let weak1 = Rc::downgrade(&self.data);
+        let weak2 = Rc::downgrade(&self.data);
+
+        let mut header = ui!("header" [{
+                .event(On::PointerDown, move |_, ctx: &mut EventCtx<S, H>| {
+                    let this = weak1.upgrade()?;
+                    this.toggle(ctx);
+                    ctx.emit_change();
+                    Some(Phase::Build)
+                })
+            }
+            (header)
+        ]);
+        if self.data.open.get() {
+            header = header.add_classes("expanded");
+        }
+
+        let result = ui!([{
+            .key(self.key)
+            .on_style(|_, style: &mut Style| {
+                style.flex_direction = FlexDirection::Column;
+            })
+        }])
+        .add_child(header);
+
+        // Closed content stays in the tree, since every node that a view creates has to be part of it,
+        // but it's clipped to no height
+        let progress = move || weak2.upgrade().map_or(1.0, |this| this.progress.get());
+        let content = reveal(content, progress, self.data.content_height.clone());
+        result.add_child(content.key(self.data.content_key).add_classes("content"))
+    }
+}
+
+impl Data {
+    fn toggle<S, H>(self: &Rc<Self>, ctx: &mut EventCtx<S, H>) {
+        let open = !self.open.get();
+        if open {
+            if let Some(group) = &*self.group.borrow() {
+                for other in group.members.borrow().iter().filter_map(Weak::upgrade) {
+                    if !Rc::ptr_eq(&other, self) && other.open.get() {
+                        other.open.set(false);
+                        other.animate(ctx);
+                    }
+                }
+            }
+        }
+        self.open.set(open);
+        self.animate(ctx);
+    }
+
+    // Move the progress towards the open state, and rebuild when it's done
+    fn animate<S, H>(self: &Rc<Self>, ctx: &mut EventCtx<S, H>) {
+        if self.animating.replace(true) {
+            return;
+        }
+
+        let weak = Rc::downgrade(self);
+        ctx.start_animation(move |_, dt| {
+            let this = if let Some(this) = weak.upgrade() {
+                this
+            } else {
+                return (Phase::Idle, ShouldStop::Yes);
+            };
+            let target = if this.open.get() { 1.0 } else { 0.0 };
+            let step = if this.duration.get().is_zero() {
+                1.0
+            } else {
+                dt.as_secs_f64() / this.duration.get().as_secs_f64()
+            };
+
+            let progress = this.progress.get();
+            let progress = if target > progress {
+                (progress + step).min(target)
+            } else {
+                (progress - step).max(target)
+            };
+            this.progress.set(progress);
+
+            if progress == target {
+                this.animating.set(false);
+                (Phase::Build, ShouldStop::Yes)
+            } else {
+                (Phase::Style, ShouldStop::No)
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callbacks::{PointerButton, RawPointerEvent};
+
+    struct State {
+        sections: [Collapsible; 2],
+        changes: u32,
+    }
+
+    fn view(state: &State) -> View<State, ()> {
+        let mut root = View::default()
+            .inline_style("flex-direction: column")
+            .event(On::Change, |s: &mut State, _| {
+                s.changes += 1;
+                Some(Phase::Idle)
+            });
+        for section in &state.sections {
+            let header = View::default().inline_style("height: 10px");
+            let content = View::default().inline_style("height: 20px");
+            root = root.add_child(section.view(header, content).inline_style("flex-grow: 0"));
+        }
+        root
+    }
+
+    fn setup() -> (State, Viewport<State, ()>) {
+        let group = CollapsibleGroup::default();
+        let state = State {
+            sections: [
+                Collapsible::new(true).with_group(&group),
+                Collapsible::new(false).with_group(&group),
+            ],
+            changes: 0,
+        };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+        (state, viewport)
+    }
+
+    // The laid out heights of both sections' content
+    fn heights(state: &State, viewport: &Viewport<State, ()>) -> [f64; 2] {
+        [0, 1].map(|i| viewport.node_rect(state.sections[i].data.content_key).unwrap().height())
+    }
+
+    #[test]
+    fn opening_a_section_closes_the_rest_of_its_group() {
+        let (mut state, mut viewport) = setup();
+        assert_eq!(heights(&state, &viewport), [20.0, 0.0]);
+
+        // The second section's header is below the first section's content
+        viewport.pointer_down(&mut state, RawPointerEvent::at(5.0, 35.0).press(PointerButton::Left));
+        assert!(!state.sections[0].is_open());
+        assert!(state.sections[1].is_open());
+        assert_eq!(state.changes, 1);
+
+        // Both animate halfway, then finish
+        viewport.animation_frame(&mut state, Duration::from_millis(75));
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(heights(&state, &viewport), [10.0, 10.0]);
+        viewport.animation_frame(&mut state, Duration::from_millis(100));
+        assert!(!viewport.has_anim_tasks());
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(heights(&state, &viewport), [0.0, 20.0]);
+    }
+
+    #[test]
+    fn closing_a_section_leaves_the_rest_alone() {
+        let (mut state, mut viewport) = setup();
+        viewport.pointer_down(&mut state, RawPointerEvent::at(5.0, 5.0).press(PointerButton::Left));
+        assert!(!state.sections[0].is_open());
+        assert!(!state.sections[1].is_open());
+
+        // Rebuilding after the animation leaves both sections closed
+        viewport.animation_frame(&mut state, Duration::from_millis(200));
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(heights(&state, &viewport), [0.0, 0.0]);
+
+        // Opening it again without the animation restores the content's natural height
+        viewport.update_phase(state.sections[0].set_open(true));
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(heights(&state, &viewport), [20.0, 0.0]);
+    }
+
+    #[test]
+    fn set_open_skips_the_animation() {
+        let section = Collapsible::new(false).with_duration(Duration::ZERO);
+        assert_eq!(section.set_open(true), Phase::Build);
+        assert!(section.is_open());
+        assert_eq!(section.data.progress.get(), 1.0);
+    }
+}
@@ -0,0 +1,403 @@
+#![forbid(unsafe_code)]
+
+use std::{cell::Cell, fmt::Debug, rc::Rc};
+
+use druid_shell::{
+    kurbo::{Circle, Line, Point, Rect},
+    piet::{Color, LinearGradient, RenderContext, UnitPoint},
+    KeyState,
+};
+
+use crate::prelude::*;
+
+const STRIP_HEIGHT: f64 = 16.0;
+const GAP: f64 = 8.0;
+const STEP: f64 = 0.01;
+
+// ---------- Color Picker ----------
+/// A saturation/value square with hue and alpha strips below it. Changes are reported with `On::Change`.
+/// Click a part to select it, then the arrow keys adjust it.
+#[derive(Debug)]
+pub struct ColorPicker {
+    pub key: Key,
+    data: Rc<Data>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    SatVal,
+    Hue,
+    Alpha,
+}
+
+#[derive(Debug)]
+struct Data {
+    hue: Cell<f64>,
+    saturation: Cell<f64>,
+    value: Cell<f64>,
+    alpha: Cell<f64>,
+    // The part being dragged, if any
+    dragging: Cell<Option<Part>>,
+    // The part that arrow keys adjust
+    selected: Cell<Part>,
+    changed: Cell<bool>,
+}
+
+impl ColorPicker {
+    pub fn new(color: Color) -> Self {
+        let [hue, saturation, value, alpha] = to_hsva(&color);
+        Self {
+            key: Key::new(),
+            data: Rc::new(Data {
+                hue: Cell::new(hue),
+                saturation: Cell::new(saturation),
+                value: Cell::new(value),
+                alpha: Cell::new(alpha),
+                dragging: Cell::new(None),
+                selected: Cell::new(Part::SatVal),
+                changed: Cell::new(false),
+            }),
+        }
+    }
+
+    pub fn set(&self, new_color: Color) -> Phase {
+        let [hue, saturation, value, alpha] = to_hsva(&new_color);
+
+        // Keep the current hue for grays, where it's undefined
+        if saturation > 0.0 && value > 0.0 {
+            self.data.hue.set(hue);
+        }
+        self.data.saturation.set(saturation);
+        self.data.value.set(value);
+        self.data.alpha.set(alpha);
+        self.data.changed.replace(true);
+        Phase::Draw
+    }
+
+    pub fn get(&self) -> Color {
+        self.data.color()
+    }
+
+    /// Set the color from a hex string, like the text of a `TextBox`. Returns `None` if the string isn't a valid color.
+    pub fn set_hex(&self, hex: &str) -> Option<Phase> {
+        Some(self.set(Color::from_hex(hex.trim())?))
+    }
+
+    pub fn hex(&self) -> String {
+        self.get().to_hex()
+    }
+
+    pub fn view<S, H>(&self) -> View<S, H> {
+        let key = self.key;
+        let weak1 = Rc::downgrade(&self.data);
+        let weak2 = Rc::downgrade(&self.data);
+        let weak3 = Rc::downgrade(&self.data);
+        let weak4 = Rc::downgrade(&self.data);
+        let weak5 = Rc::downgrade(&self.data);
+
+        ui!([
+            .key(key)
+            .event(On::PointerDown, move |_, ctx: &mut EventCtx<S, H>| {
+                let this = if let Some(this) = weak1.upgrade() { this } else { return Some(Phase::Idle) };
+                let info = ctx.pointer()?;
+
+                if info.button.is_left() {
+                    let (sat_val, _, _) = parts(ctx.width(), ctx.height());
+                    let part = if info.pos_y < sat_val.y1 + GAP / 2.0 {
+                        Part::SatVal
+                    } else if info.pos_y < sat_val.y1 + GAP * 1.5 + STRIP_HEIGHT {
+                        Part::Hue
+                    } else {
+                        Part::Alpha
+                    };
+
+                    this.dragging.set(Some(part));
+                    this.selected.set(part);
+                    this.drag_to(part, info.pos_x, info.pos_y, ctx.width(), ctx.height());
+
+                    // Keep dragging when the pointer moves past the edge of the picker
+                    ctx.capture_pointer(key);
+                    ctx.focus_on(key);
+                    ctx.emit_change();
+                    Some(Phase::Draw)
+                } else {
+                    Some(Phase::Idle)
+                }
+            })
+            .event(On::PointerMove, move |_, ctx: &mut EventCtx<S, H>| {
+                let this = if let Some(this) = weak2.upgrade() { this } else { return Some(Phase::Idle) };
+                let info = ctx.pointer()?;
+
+                match this.dragging.get() {
+                    Some(part) if info.buttons.has_left() => {
+                        this.drag_to(part, info.pos_x, info.pos_y, ctx.width(), ctx.height());
+                        ctx.emit_change();
+                        Some(Phase::Draw)
+                    }
+                    _ => {
+                        this.dragging.set(None);
+                        Some(Phase::Idle)
+                    }
+                }
+            })
+            .event(On::PointerUp, move |_, _| {
+                let this = if let Some(this) = weak3.upgrade() { this } else { return Some(Phase::Idle) };
+                this.dragging.set(None);
+                Some(Phase::Idle)
+            })
+            .event(On::Keyboard, move |_, ctx: &mut EventCtx<S, H>| {
+                let this = if let Some(this) = weak4.upgrade() { this } else { return Some(Phase::Idle) };
+                let event = ctx.keyboard()?;
+                if event.state != KeyState::Down {
+                    return Some(Phase::Idle);
+                }
+
+                let (dx, dy) = match event.key {
+                    KbKey::ArrowLeft => (-STEP, 0.0),
+                    KbKey::ArrowRight => (STEP, 0.0),
+                    KbKey::ArrowUp => (0.0, STEP),
+                    KbKey::ArrowDown => (0.0, -STEP),
+                    _ => return Some(Phase::Idle),
+                };
+
+                let adjust = |cell: &Cell<f64>, delta: f64| cell.set((cell.get() + delta).clamp(0.0, 1.0));
+                match this.selected.get() {
+                    Part::SatVal => {
+                        adjust(&this.saturation, dx);
+                        adjust(&this.value, dy);
+                    }
+                    Part::Hue => adjust(&this.hue, dx + dy),
+                    Part::Alpha => adjust(&this.alpha, dx + dy),
+                }
+                this.changed.set(true);
+
+                ctx.emit_change();
+                Some(Phase::Draw)
+            })
+            .on_draw(true, move |_, ctx: &mut DrawCtx| {
+                // If the underlying data is gone, then just return since there's nothing to draw.
+                let this = if let Some(this) = weak5.upgrade() { this } else { return };
+                if !this.changed.get() && !ctx.must_draw { return }
+                this.changed.set(false);
+
+                let (sat_val, hue, alpha) = parts(ctx.width, ctx.height);
+                let pure_hue = from_hsva([this.hue.get(), 1.0, 1.0, 1.0]);
+                let opaque = this.color().with_alpha(1.0);
+
+                // Saturation increases to the right, and value decreases downwards
+                ctx.piet.fill(sat_val, &LinearGradient::new(UnitPoint::LEFT, UnitPoint::RIGHT, (Color::WHITE, pure_hue)));
+                ctx.piet.fill(sat_val, &LinearGradient::new(UnitPoint::TOP, UnitPoint::BOTTOM, (Color::TRANSPARENT, Color::BLACK)));
+
+                let hue_stops: Vec<Color> = (0..=6).map(|i| from_hsva([i as f64 / 6.0, 1.0, 1.0, 1.0])).collect();
+                ctx.piet.fill(hue, &LinearGradient::new(UnitPoint::LEFT, UnitPoint::RIGHT, hue_stops.as_slice()));
+
+                ctx.piet.fill(alpha, &Color::grey(0.8));
+                ctx.piet.fill(alpha, &LinearGradient::new(UnitPoint::LEFT, UnitPoint::RIGHT, (opaque.with_alpha(0.0), opaque)));
+
+                // Markers
+                let marker = Point::new(
+                    sat_val.x0 + this.saturation.get() * sat_val.width(),
+                    sat_val.y0 + (1.0 - this.value.get()) * sat_val.height(),
+                );
+                ctx.piet.stroke(Circle::new(marker, 5.0), &Color::WHITE, 2.0);
+                ctx.piet.stroke(Circle::new(marker, 7.0), &Color::BLACK, 1.0);
+
+                for (rect, t) in [(hue, this.hue.get()), (alpha, this.alpha.get())] {
+                    let x = rect.x0 + t * rect.width();
+                    ctx.piet.stroke(Line::new((x, rect.y0 - 2.0), (x, rect.y1 + 2.0)), &Color::BLACK, 3.0);
+                    ctx.piet.stroke(Line::new((x, rect.y0 - 2.0), (x, rect.y1 + 2.0)), &Color::WHITE, 1.0);
+                }
+            })
+        ])
+    }
+}
+
+impl Data {
+    fn color(&self) -> Color {
+        from_hsva([self.hue.get(), self.saturation.get(), self.value.get(), self.alpha.get()])
+    }
+
+    // Update a part from a pointer position, relative to the widget
+    fn drag_to(&self, part: Part, x: f64, y: f64, width: f64, height: f64) {
+        let (sat_val, _, _) = parts(width, height);
+        let x = ((x - sat_val.x0) / sat_val.width().max(1.0)).clamp(0.0, 1.0);
+
+        match part {
+            Part::SatVal => {
+                self.saturation.set(x);
+                self.value.set(1.0 - ((y - sat_val.y0) / sat_val.height().max(1.0)).clamp(0.0, 1.0));
+            }
+            Part::Hue => self.hue.set(x),
+            Part::Alpha => self.alpha.set(x),
+        }
+        self.changed.set(true);
+    }
+}
+
+// Split the widget into the saturation/value square, and the hue and alpha strips
+fn parts(width: f64, height: f64) -> (Rect, Rect, Rect) {
+    let sat_val_height = (height - 2.0 * (STRIP_HEIGHT + GAP)).max(0.0);
+    let hue_top = sat_val_height + GAP;
+    let alpha_top = hue_top + STRIP_HEIGHT + GAP;
+
+    (
+        Rect::new(0.0, 0.0, width, sat_val_height),
+        Rect::new(0.0, hue_top, width, hue_top + STRIP_HEIGHT),
+        Rect::new(0.0, alpha_top, width, alpha_top + STRIP_HEIGHT),
+    )
+}
+
+fn from_hsva([hue, saturation, value, alpha]: [f64; 4]) -> Color {
+    let h = hue.rem_euclid(1.0) * 6.0;
+    let c = value * saturation;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = value - c;
+    Color::rgba(r + m, g + m, b + m, alpha)
+}
+
+fn to_hsva(color: &Color) -> [f64; 4] {
+    let (r, g, b, a) = color.as_rgba();
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / delta).rem_euclid(6.0) / 6.0
+    } else if max == g {
+        ((b - r) / delta + 2.0) / 6.0
+    } else {
+        ((r - g) / delta + 4.0) / 6.0
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+    [hue, saturation, max, a]
+}
+
+#[cfg(test)]
+mod tests {
+    use druid_shell::KeyEvent;
+
+    use super::*;
+    use crate::callbacks::{PointerButton, RawPointerEvent};
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn hsva_round_trips() {
+        for hex in [
+            "#ff0000", "#00ff00", "#0000ff", "#ffff00", "#00ffff", "#ff00ff", "#336699", "#000000", "#ffffff",
+        ] {
+            let color = Color::from_hex(hex).unwrap();
+            assert_eq!(from_hsva(to_hsva(&color)).to_hex(), color.to_hex());
+        }
+
+        let [hue, saturation, value, alpha] = to_hsva(&Color::rgba(0.0, 1.0, 1.0, 0.2));
+        assert!(close(hue, 0.5) && close(saturation, 1.0) && close(value, 1.0) && close(alpha, 0.2));
+    }
+
+    #[test]
+    fn grays_keep_the_hue() {
+        let picker = ColorPicker::new(Color::rgb(0.0, 1.0, 0.0));
+        assert_eq!(picker.set(Color::grey(0.5)), Phase::Draw);
+        assert!(close(picker.data.hue.get(), 1.0 / 3.0));
+
+        assert_eq!(picker.set_hex(" #ff0000 "), Some(Phase::Draw));
+        assert!(close(picker.data.hue.get(), 0.0));
+        assert_eq!(picker.set_hex("not a color"), None);
+        assert_eq!(picker.hex(), Color::rgb(1.0, 0.0, 0.0).to_hex());
+    }
+
+    struct State {
+        picker: ColorPicker,
+        changes: u32,
+    }
+
+    fn view(state: &State) -> View<State, ()> {
+        View::default()
+            .event(On::Change, |s: &mut State, _| {
+                s.changes += 1;
+                Some(Phase::Idle)
+            })
+            .add_child(state.picker.view())
+    }
+
+    #[test]
+    fn clicks_pick_a_part_and_keys_adjust_it() {
+        let mut state = State {
+            picker: ColorPicker::new(Color::rgb(1.0, 0.0, 0.0)),
+            changes: 0,
+        };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+        let data = state.picker.data.clone();
+
+        // The square takes the height that the strips leave
+        let (sat_val, hue, _) = parts(100.0, 100.0);
+        let click = |x, y| RawPointerEvent::at(x, y).press(PointerButton::Left);
+        let key = |key| KeyEvent {
+            state: KeyState::Down,
+            key,
+            ..Default::default()
+        };
+
+        viewport.pointer_down(&mut state, click(75.0, sat_val.height() / 2.0));
+        assert!(close(data.saturation.get(), 0.75) && close(data.value.get(), 0.5));
+        viewport.key_event(&mut state, key(KbKey::ArrowRight));
+        viewport.key_event(&mut state, key(KbKey::ArrowUp));
+        assert!(close(data.saturation.get(), 0.76) && close(data.value.get(), 0.51));
+
+        viewport.pointer_down(&mut state, click(25.0, hue.center().y));
+        assert!(close(data.hue.get(), 0.25));
+        viewport.key_event(&mut state, key(KbKey::ArrowLeft));
+        assert!(close(data.hue.get(), 0.24));
+        assert!(close(data.saturation.get(), 0.76));
+        assert_eq!(state.changes, 5);
+    }
+
+    #[test]
+    fn drags_clamp_outside_of_the_picker() {
+        let mut state = State {
+            picker: ColorPicker::new(Color::rgb(1.0, 0.0, 0.0)),
+            changes: 0,
+        };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+        let data = state.picker.data.clone();
+
+        let (sat_val, hue, _) = parts(100.0, 100.0);
+        let down = RawPointerEvent::at(50.0, sat_val.height() / 2.0).press(PointerButton::Left);
+        let drag = |x, y| RawPointerEvent {
+            window_pos_x: x,
+            window_pos_y: y,
+            ..down
+        };
+
+        // The pointer is outside the window, so it isn't over the picker
+        viewport.pointer_down(&mut state, down);
+        viewport.pointer_move(&mut state, drag(150.0, -20.0));
+        assert!(close(data.saturation.get(), 1.0) && close(data.value.get(), 1.0));
+        viewport.pointer_move(&mut state, drag(-30.0, 500.0));
+        assert!(close(data.saturation.get(), 0.0) && close(data.value.get(), 0.0));
+        viewport.pointer_up(&mut state, drag(-30.0, 500.0).release(PointerButton::Left));
+
+        // The hue strip keeps its part while the pointer is over the square
+        viewport.pointer_down(&mut state, drag(50.0, hue.center().y));
+        viewport.pointer_move(&mut state, drag(-10.0, 0.0));
+        assert!(close(data.hue.get(), 0.0));
+        viewport.pointer_move(&mut state, drag(250.0, 0.0));
+        assert!(close(data.hue.get(), 1.0));
+        assert!(close(data.saturation.get(), 0.0));
+    }
+}
@@ -0,0 +1,407 @@
+#![forbid(unsafe_code)]
+
+use std::{cell::Cell, fmt::Debug, rc::Rc};
+
+use druid_shell::KeyState;
+
+use crate::prelude::*;
+use crate::widgets::*;
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAYS: [&str; 7] = ["Su", "Mo", "Tu", "We", "Th", "Fr", "Sa"];
+
+/// A calendar date. Months and days start at 1. Create one with `Date::new()`, which keeps it valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    /// Months outside of 1 to 12 are clamped to that range, and days are clamped to the days in the month.
+    pub fn new(year: i32, month: u32, day: u32) -> Self {
+        let month = month.clamp(1, 12);
+        let day = day.clamp(1, Self::days_in_month(year, month));
+        Self { year, month, day }
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    pub fn days_in_month(year: i32, month: u32) -> u32 {
+        match month {
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    /// The day of the week, where 0 is Sunday.
+    pub fn weekday(&self) -> u32 {
+        // Sakamoto's method
+        const OFFSETS: [i32; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+        let year = if self.month < 3 { self.year - 1 } else { self.year };
+        (year + year.div_euclid(4) - year.div_euclid(100) + year.div_euclid(400) + OFFSETS[self.month as usize - 1] + self.day as i32)
+            .rem_euclid(7) as u32
+    }
+
+    /// Move by a number of months, keeping the day within the new month.
+    pub fn add_months(&self, months: i32) -> Self {
+        let index = self.year * 12 + self.month as i32 - 1 + months;
+        let year = index.div_euclid(12);
+        let month = index.rem_euclid(12) as u32 + 1;
+        Self::new(year, month, self.day.min(Self::days_in_month(year, month)))
+    }
+
+    pub fn add_days(&self, mut days: i32) -> Self {
+        let mut date = *self;
+        while days != 0 {
+            if days > 0 {
+                let remaining = (Self::days_in_month(date.year, date.month) - date.day) as i32;
+                if days <= remaining {
+                    date.day += days as u32;
+                    days = 0;
+                } else {
+                    days -= remaining + 1;
+                    date = date.add_months(1);
+                    date.day = 1;
+                }
+            } else if -days < date.day as i32 {
+                date.day -= (-days) as u32;
+                days = 0;
+            } else {
+                days += date.day as i32;
+                date = date.add_months(-1);
+                date.day = Self::days_in_month(date.year, date.month);
+            }
+        }
+        date
+    }
+}
+
+// ---------- Date Picker ----------
+/// A calendar for picking a date. Selecting a day is reported with `On::Change`.
+/// Once it has focus, the arrow keys move the selection by days and weeks, and PageUp and PageDown move by months.
+///
+/// The calendar is built from nodes with the classes `header`, `prev`, `next`, `title`, `weekdays`, `weekday`, `week`, `day`, and `blank`,
+/// and the selected day matches `:selected`, so it can be styled with a stylesheet.
+#[derive(Debug)]
+pub struct DatePicker {
+    pub key: Key,
+    data: Rc<Data>,
+}
+
+#[derive(Debug)]
+struct Data {
+    // The first day of the month that's displayed
+    shown: Cell<Date>,
+    selected: Cell<Option<Date>>,
+    first_weekday: Cell<u32>,
+}
+
+impl DatePicker {
+    /// Create a date picker that shows the month of `shown`, with nothing selected.
+    pub fn new(shown: Date) -> Self {
+        Self {
+            key: Key::new(),
+            data: Rc::new(Data {
+                shown: Cell::new(Date::new(shown.year, shown.month, 1)),
+                selected: Cell::new(None),
+                first_weekday: Cell::new(0),
+            }),
+        }
+    }
+
+    /// Set the day that weeks start on, where 0 is Sunday and 1 is Monday.
+    pub fn with_first_weekday(self, first_weekday: u32) -> Self {
+        self.data.first_weekday.set(first_weekday % 7);
+        self
+    }
+
+    pub fn get(&self) -> Option<Date> {
+        self.data.selected.get()
+    }
+
+    /// Select a date, and show its month.
+    pub fn set(&self, date: Option<Date>) -> Phase {
+        self.data.select(date);
+        Phase::Build
+    }
+
+    /// Show another month, without changing the selection.
+    pub fn show_month(&self, months: i32) -> Phase {
+        self.data.shown.set(self.data.shown.get().add_months(months));
+        Phase::Build
+    }
+
+    pub fn view<S, H>(&self) -> View<S, H> {
+        let key = self.key;
+        let shown = self.data.shown.get();
+        let selected = self.data.selected.get();
+        let first_weekday = self.data.first_weekday.get();
+
+        let weak1 = Rc::downgrade(&self.data);
+        let weak2 = Rc::downgrade(&self.data);
+        let weak3 = Rc::downgrade(&self.data);
+
+        let header = ui!("header" [
+            "prev" (button("<", move |_, ctx| {
+                let this = weak1.upgrade()?;
+                this.shown.set(this.shown.get().add_months(-1));
+                ctx.focus_on(key);
+                Some(Phase::Build)
+            }))
+            "title" (label(format!("{} {}", MONTHS[shown.month as usize - 1], shown.year)))
+            "next" (button(">", move |_, ctx| {
+                let this = weak2.upgrade()?;
+                this.shown.set(this.shown.get().add_months(1));
+                ctx.focus_on(key);
+                Some(Phase::Build)
+            }))
+        ]);
+
+        let mut weekdays = ui!("weekdays" []);
+        for i in 0..7 {
+            weekdays = weekdays.add_child(label(WEEKDAYS[((first_weekday + i) % 7) as usize]).add_classes("weekday"));
+        }
+
+        // Lay out six weeks, so the calendar doesn't change size between months
+        let leading = (shown.weekday() + 7 - first_weekday) % 7;
+        let days_in_month = Date::days_in_month(shown.year, shown.month);
+        let mut calendar = ui!([]);
+        for week in 0..6 {
+            let mut row = ui!("week" []);
+            for weekday in 0..7 {
+                let index = week * 7 + weekday;
+                if index < leading || index - leading >= days_in_month {
+                    row = row.add_child(ui!("blank" []));
+                    continue;
+                }
+
+                let date = Date::new(shown.year, shown.month, index - leading + 1);
+                let weak = Rc::downgrade(&self.data);
+                row = row.add_child(
+                    label(date.day.to_string())
+                        .add_classes("day")
+                        .selected(selected == Some(date))
+                        .event(On::PointerDown, move |_, ctx: &mut EventCtx<S, H>| {
+                            weak.upgrade()?.selected.set(Some(date));
+                            ctx.focus_on(key);
+                            ctx.emit_change();
+                            Some(Phase::Build)
+                        }),
+                );
+            }
+            calendar = calendar.add_child(row);
+        }
+
+        ui!([{
+            .key(key)
+            .event(On::Keyboard, move |_, ctx: &mut EventCtx<S, H>| {
+                let this = weak3.upgrade()?;
+                let event = ctx.keyboard()?;
+                if event.state != KeyState::Down {
+                    return Some(Phase::Idle);
+                }
+
+                let current = this.selected.get().unwrap_or(this.shown.get());
+                let date = match event.key {
+                    KbKey::ArrowLeft => current.add_days(-1),
+                    KbKey::ArrowRight => current.add_days(1),
+                    KbKey::ArrowUp => current.add_days(-7),
+                    KbKey::ArrowDown => current.add_days(7),
+                    KbKey::PageUp => current.add_months(-1),
+                    KbKey::PageDown => current.add_months(1),
+                    _ => return Some(Phase::Idle),
+                };
+                this.select(Some(date));
+
+                ctx.emit_change();
+                Some(Phase::Build)
+            })
+        }
+        (header)
+        (weekdays)
+        (calendar)
+        ])
+    }
+}
+
+impl Data {
+    fn select(&self, date: Option<Date>) {
+        if let Some(date) = date {
+            self.shown.set(Date::new(date.year, date.month, 1));
+        }
+        self.selected.set(date);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callbacks::{PointerButton, RawPointerEvent};
+    use druid_shell::KeyEvent;
+
+    #[test]
+    fn date_math_handles_leap_years() {
+        assert_eq!(Date::days_in_month(2024, 2), 29);
+        assert_eq!(Date::days_in_month(1900, 2), 28);
+        assert_eq!(Date::days_in_month(2000, 2), 29);
+        assert_eq!(Date::new(2000, 1, 1).weekday(), 6);
+        assert_eq!(Date::new(2026, 10, 1).weekday(), 4);
+
+        assert_eq!(Date::new(2024, 1, 31).add_months(1), Date::new(2024, 2, 29));
+        assert_eq!(Date::new(2024, 3, 15).add_months(-13), Date::new(2023, 2, 15));
+        assert_eq!(Date::new(2023, 12, 31).add_days(1), Date::new(2024, 1, 1));
+        assert_eq!(Date::new(2024, 3, 1).add_days(-1), Date::new(2024, 2, 29));
+        assert_eq!(Date::new(2024, 1, 1).add_days(366), Date::new(2025, 1, 1));
+    }
+
+    #[test]
+    fn invalid_dates_are_clamped() {
+        assert_eq!(Date::new(2026, 0, 0), Date::new(2026, 1, 1));
+        assert_eq!(Date::new(2026, 13, 40), Date::new(2026, 12, 31));
+        assert_eq!(Date::new(2023, 2, 29), Date::new(2023, 2, 28));
+        assert_eq!(Date::new(2026, 13, 1).weekday(), 2);
+
+        let date = Date::new(2026, 13, 40);
+        assert_eq!((date.year(), date.month(), date.day()), (2026, 12, 31));
+    }
+
+    struct State {
+        picker: DatePicker,
+        changes: u32,
+    }
+
+    // Clicking anywhere focuses the picker, so it gets the key events
+    fn view(state: &State) -> View<State, ()> {
+        View::default()
+            .event(On::PointerDown, |s: &mut State, ctx| {
+                ctx.focus_on(s.picker.key);
+                Some(Phase::Idle)
+            })
+            .event(On::Change, |s: &mut State, _| {
+                s.changes += 1;
+                Some(Phase::Idle)
+            })
+            .add_child(state.picker.view())
+    }
+
+    // Give every part of the calendar a fixed size, so clicks can find them.
+    // The header, weekdays, and each week are rows of 10px cells, stacked from the top.
+    const CSS: &str = "
+        * { flex-grow: 0; flex-shrink: 0; }
+        .root > * { flex-direction: column; }
+        .root > * > * { flex-direction: column; }
+        .header { flex-direction: row; }
+        .weekdays { flex-direction: row; }
+        .week { flex-direction: row; }
+        .prev { width: 10px; height: 10px; }
+        .title { width: 10px; height: 10px; }
+        .next { width: 10px; height: 10px; }
+        .weekday { width: 10px; height: 10px; }
+        .day { width: 10px; height: 10px; }
+        .blank { width: 10px; height: 10px; }
+    ";
+
+    fn sized_view(state: &State) -> View<State, ()> {
+        view(state).use_style_sheet(Some(Stylesheet::parse(CSS))).add_classes("root")
+    }
+
+    fn key_down(key: KbKey) -> KeyEvent {
+        KeyEvent {
+            state: KeyState::Down,
+            key,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn keys_move_the_selection_across_months() {
+        let mut state = State {
+            picker: DatePicker::new(Date::new(2026, 10, 17)),
+            changes: 0,
+        };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+        viewport.pointer_down(&mut state, RawPointerEvent::at(99.0, 99.0).press(PointerButton::Left));
+
+        // Without a selection, keys move from the first day of the month that's shown
+        let mut press = |state: &mut State, key| {
+            viewport.key_event(state, key_down(key));
+            viewport.draw(state, None).unwrap();
+            state.picker.get().unwrap()
+        };
+        assert_eq!(press(&mut state, KbKey::ArrowLeft), Date::new(2026, 9, 30));
+        assert_eq!(press(&mut state, KbKey::ArrowUp), Date::new(2026, 9, 23));
+        assert_eq!(press(&mut state, KbKey::PageDown), Date::new(2026, 10, 23));
+        assert_eq!(press(&mut state, KbKey::ArrowDown), Date::new(2026, 10, 30));
+        assert_eq!(press(&mut state, KbKey::ArrowRight), Date::new(2026, 10, 31));
+        assert_eq!(press(&mut state, KbKey::PageDown), Date::new(2026, 11, 30));
+        assert_eq!(state.changes, 6);
+
+        // Showing another month keeps the selection
+        let phase = state.picker.show_month(-2);
+        assert_eq!(phase, Phase::Build);
+        assert_eq!(press(&mut state, KbKey::ArrowRight), Date::new(2026, 12, 1));
+    }
+
+    #[test]
+    fn clicks_change_the_month_and_select_a_day() {
+        let mut state = State {
+            picker: DatePicker::new(Date::new(2026, 12, 5)),
+            changes: 0,
+        };
+        let mut viewport = Viewport::new(ResourceLoader::default(), sized_view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        let mut click = |state: &mut State, x, y| {
+            viewport.pointer_down(state, RawPointerEvent::at(x, y).press(PointerButton::Left));
+            viewport.pointer_up(state, RawPointerEvent::at(x, y).release(PointerButton::Left));
+            viewport.draw(state, None).unwrap();
+            state.picker.data.shown.get()
+        };
+
+        // The next button rolls over into the next year, and the previous button goes back
+        assert_eq!(click(&mut state, 25.0, 5.0), Date::new(2027, 1, 1));
+        assert_eq!(click(&mut state, 5.0, 5.0), Date::new(2026, 12, 1));
+        assert_eq!(click(&mut state, 5.0, 5.0), Date::new(2026, 11, 1));
+        assert_eq!(click(&mut state, 25.0, 5.0), Date::new(2026, 12, 1));
+        assert_eq!(state.picker.get(), None);
+        assert_eq!(state.changes, 0);
+
+        // December 2026 starts on a Tuesday, so the 17th is the Thursday of the third week
+        assert_eq!(click(&mut state, 45.0, 45.0), Date::new(2026, 12, 1));
+        assert_eq!(state.picker.get(), Some(Date::new(2026, 12, 17)));
+        assert_eq!(state.changes, 1);
+
+        // Blank cells before the first day don't select anything
+        click(&mut state, 5.0, 25.0);
+        assert_eq!(state.picker.get(), Some(Date::new(2026, 12, 17)));
+        assert_eq!(state.changes, 1);
+    }
+}
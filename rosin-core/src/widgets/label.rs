@@ -1,31 +1,187 @@
 #![forbid(unsafe_code)]
 
-use druid_shell::piet::{FontFamily, RenderContext, Text, TextLayoutBuilder};
+use std::{ops::Range, sync::Arc};
+
+use druid_shell::{
+    kurbo::Vec2,
+    piet::{Color, FontWeight, RenderContext, Text, TextAttribute, TextLayout, TextLayoutBuilder},
+};
 
 use crate::prelude::*;
+use crate::text::{grapheme_range, text_layout_builder};
 
 // ---------- Static Label ----------
-pub fn label<S, H>(text: &'static str) -> View<S, H> {
+/// A label with text that's fixed until the tree is rebuilt.
+pub fn label<S, H>(text: impl Into<Arc<str>>) -> View<S, H> {
+    let text: Arc<str> = text.into();
+
     ui!([
         .on_draw(true, move |_: &S, ctx: &mut DrawCtx| {
-            let font_color = ctx.style.color.clone();
-
-            let font_family = if let Some(family_name) = &ctx.style.font_family {
-                ctx.piet.text().font_family(family_name.as_ref())
-            } else {
-                None
-            };
-            let font_family = font_family.unwrap_or(FontFamily::SYSTEM_UI);
-
-            let layout = ctx.piet
-                .text()
-                .new_text_layout(text)
-                .font(font_family, ctx.style.font_size as f64)
-                .text_color(font_color)
-                .build()
-                .unwrap();
-
+            let layout = text_layout_builder(ctx.piet.text(), text.clone(), ctx.style).build().unwrap();
             ctx.piet.draw_text(&layout, (ctx.style.padding_left as f64, ctx.style.padding_top as f64));
         })
     ])
 }
+
+/// How `highlighted_label` draws the highlighted parts of its text.
+#[derive(Debug, Clone)]
+pub struct Highlight {
+    /// The text color, instead of the node's `color`.
+    pub color: Option<Color>,
+    pub bold: bool,
+    /// A color to fill behind the text.
+    pub background: Option<Color>,
+}
+
+impl Default for Highlight {
+    fn default() -> Self {
+        Self {
+            color: None,
+            bold: true,
+            background: None,
+        }
+    }
+}
+
+// ---------- Highlighted Label ----------
+/// A static label with some parts highlighted, like the matches in search results.
+/// `spans` are byte ranges of the text, which are widened to grapheme boundaries so they never split a character.
+/// For the positions from `fuzzy_match()`, use a range for each matched character.
+pub fn highlighted_label<S, H>(
+    text: impl Into<Arc<str>>,
+    spans: impl IntoIterator<Item = Range<usize>>,
+    highlight: Highlight,
+) -> View<S, H> {
+    let text: Arc<str> = text.into();
+    let spans = highlight_spans(&text, spans);
+
+    ui!([
+        .on_draw(true, move |_: &S, ctx: &mut DrawCtx| {
+            let layout = highlight_layout(ctx.piet.text(), &text, &spans, &highlight, ctx.style);
+
+            let origin = Vec2::new(ctx.style.padding_left as f64, ctx.style.padding_top as f64);
+            if let Some(background) = &highlight.background {
+                for span in &spans {
+                    for rect in layout.rects_for_range(span.clone()) {
+                        ctx.piet.fill(rect + origin, background);
+                    }
+                }
+            }
+
+            ctx.piet.draw_text(&layout, origin.to_point());
+        })
+    ])
+}
+
+// Lay out the text in the node's style, with the highlight's color and weight on each span
+fn highlight_layout<T: Text>(
+    factory: &mut T,
+    text: &Arc<str>,
+    spans: &[Range<usize>],
+    highlight: &Highlight,
+    style: &Style,
+) -> T::TextLayout {
+    let mut builder = text_layout_builder(factory, text.clone(), style);
+    for span in spans {
+        if let Some(color) = &highlight.color {
+            builder = builder.range_attribute(span.clone(), TextAttribute::TextColor(color.clone()));
+        }
+        if highlight.bold {
+            builder = builder.range_attribute(span.clone(), TextAttribute::Weight(FontWeight::BOLD));
+        }
+    }
+    builder.build().unwrap()
+}
+
+// Snap spans to graphemes, then sort and merge them. Empty spans are dropped first, since snapping would widen them
+// to a whole character when they're inside one
+fn highlight_spans(text: &str, spans: impl IntoIterator<Item = Range<usize>>) -> Vec<Range<usize>> {
+    let mut spans: Vec<Range<usize>> = spans
+        .into_iter()
+        .filter(|span| !span.is_empty())
+        .map(|span| grapheme_range(text, span))
+        .filter(|span| !span.is_empty())
+        .collect();
+    spans.sort_by_key(|span| span.start);
+    spans.dedup_by(|next, prev| {
+        if next.start <= prev.end {
+            prev.end = prev.end.max(next.end);
+            true
+        } else {
+            false
+        }
+    });
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use druid_shell::{
+        kurbo::Point,
+        piet::{Device, ImageFormat},
+    };
+
+    use super::*;
+
+    #[test]
+    fn spans_are_snapped_sorted_and_merged() {
+        // "e" with a combining accent takes up bytes 1..4
+        let text = "ne\u{301}w window";
+        assert_eq!(highlight_spans(text, [5..9, 2..3]), [1..4, 5..9]);
+        assert_eq!(highlight_spans(text, [6..8, 0..1, 7..10, 1..2]), [0..4, 6..10]);
+        assert_eq!(highlight_spans(text, [3..3, 20..30]), []);
+    }
+
+    #[test]
+    fn fuzzy_positions_become_runs() {
+        let (_, positions) = fuzzy_match("ow", "Open Window").unwrap();
+        assert_eq!(highlight_spans("Open Window", positions.iter().map(|&p| p..p + 1)), [0..1, 5..6]);
+    }
+
+    #[test]
+    fn backgrounds_fill_only_the_highlighted_spans() {
+        fn highlight() -> Highlight {
+            Highlight {
+                color: Some(Color::rgb8(255, 0, 0)),
+                bold: true,
+                background: Some(Color::rgb8(255, 255, 0)),
+            }
+        }
+        fn view(_: &()) -> View<(), ()> {
+            View::default().add_child(highlighted_label("Open File", [0..1, 5..6], highlight()))
+        }
+
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(100, 100, 1.0).unwrap();
+        let mut piet = target.render_context();
+        viewport.draw(&(), Some(&mut piet)).unwrap();
+
+        // Where the spans are, laid out the same way as the node
+        let text: Arc<str> = "Open File".into();
+        let spans = [0..1, 5..6];
+        let layout = highlight_layout(piet.text(), &text, &spans, &highlight(), &Style::default());
+        let rects: Vec<_> = spans.iter().flat_map(|span| layout.rects_for_range(span.clone())).collect();
+        assert_eq!(rects.len(), 2);
+        piet.finish().unwrap();
+        drop(piet);
+        let image = target.to_image_buf(ImageFormat::RgbaPremul).unwrap();
+        let pixels = image.raw_pixels();
+
+        // Every yellow pixel is inside a span, allowing for antialiasing at the edges, and each span has some
+        let mut found = [false; 2];
+        for y in 0..100 {
+            for x in 0..100 {
+                let i = (y * 100 + x) * 4;
+                if pixels[i..i + 4] != [255, 255, 0, 255] {
+                    continue;
+                }
+                let point = Point::new(x as f64 + 0.5, y as f64 + 0.5);
+                let inside = rects.iter().position(|rect| rect.inflate(1.0, 1.0).contains(point));
+                assert!(inside.is_some(), "highlight at ({}, {}) is outside the spans", x, y);
+                found[inside.unwrap()] = true;
+            }
+        }
+        assert_eq!(found, [true, true]);
+    }
+}
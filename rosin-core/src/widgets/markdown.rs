@@ -0,0 +1,442 @@
+#![forbid(unsafe_code)]
+
+use std::rc::Rc;
+
+use crate::prelude::*;
+use crate::style::FlexDirection;
+use crate::widgets::*;
+
+const HEADINGS: [&str; 6] = ["h1", "h2", "h3", "h4", "h5", "h6"];
+
+// ---------- Markdown ----------
+/// Render a subset of Markdown, such as for help and about screens. Clicking a link calls `on_link` with its URL,
+/// which can open it with `EventCtx::open_url()`.
+///
+/// Supported are `#` headings, paragraphs, `-`, `*`, `+`, and `1.` list items, fenced code blocks,
+/// and inline `**bold**`, `*italic*` or `_italic_`, `` `code` ``, and `[links](url)`. A backslash escapes the next character.
+/// Emphasis markers only count when they touch the text they wrap, so `2 * 3` is left as is.
+/// Anything else is shown as plain text.
+///
+/// The root node has the class `markdown`. Headings have the classes `h1` to `h6`, paragraphs have `p`,
+/// lists have `ul` or `ol` with an `li` for each item, and code blocks have `pre`.
+/// Layout doesn't measure text, so each block is given the height of its lines without wrapping;
+/// give paragraphs that wrap a larger `min-height` in the stylesheet.
+pub fn markdown<S, H>(source: &str, on_link: impl Fn(&mut S, &mut EventCtx<S, H>, &str) -> Option<Phase> + 'static) -> View<S, H> {
+    // Shared by every block that has a link
+    let on_link = Rc::new(on_link);
+
+    let mut result = ui!("markdown" [{
+        .on_style(|_, style: &mut Style| {
+            style.flex_direction = FlexDirection::Column;
+        })
+    }]);
+
+    for item in blocks(source) {
+        let child = match item {
+            Block::Heading(level, text) => block(inline(text), 1, &on_link).add_classes(HEADINGS[level - 1]),
+            Block::Paragraph(text) => block(inline(&text), 1, &on_link).add_classes("p"),
+            Block::Code(lines) => {
+                let runs = vec![TextRun::new(lines.join("\n")).monospace()];
+                block(runs, lines.len().max(1), &on_link).add_classes("pre")
+            }
+            Block::List(ordered, items) => {
+                let mut view = ui!([{
+                    .on_style(|_, style: &mut Style| {
+                        style.flex_direction = FlexDirection::Column;
+                    })
+                }])
+                .add_classes(if ordered { "ol" } else { "ul" });
+
+                for (number, text) in items {
+                    let marker = match number {
+                        Some(number) => format!("{}. ", number),
+                        None => "• ".to_string(),
+                    };
+                    let mut runs = vec![TextRun::new(marker)];
+                    runs.extend(inline(text));
+                    view = view.add_child(block(runs, 1, &on_link).add_classes("li"));
+                }
+                view
+            }
+        };
+        result = result.add_child(child);
+    }
+
+    result
+}
+
+// A block-level element of a document
+#[derive(Debug, PartialEq)]
+enum Block<'a> {
+    Heading(usize, &'a str),
+    // The lines of a paragraph, joined with spaces
+    Paragraph(String),
+    // Whether it's ordered, and its items
+    List(bool, Vec<ListItem<'a>>),
+    Code(Vec<&'a str>),
+}
+
+// The number of a list item, if it's ordered, and its text
+type ListItem<'a> = (Option<u32>, &'a str);
+
+// Split a document into its blocks
+fn blocks(source: &str) -> Vec<Block<'_>> {
+    let mut result = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut list: Option<(bool, Vec<ListItem>)> = None;
+    let mut code: Option<Vec<&str>> = None;
+
+    for line in source.lines() {
+        // Inside a code block, everything up to the closing fence is kept as is
+        if let Some(code_lines) = &mut code {
+            if line.trim_start().starts_with("```") {
+                result.push(Block::Code(std::mem::take(code_lines)));
+                code = None;
+            } else {
+                code_lines.push(line);
+            }
+            continue;
+        }
+
+        let trimmed = line.trim();
+        let item = list_item(trimmed);
+        let heading = heading(trimmed);
+        let fence = trimmed.starts_with("```");
+
+        // Any other kind of line ends the paragraph or list before it
+        let ends_paragraph = trimmed.is_empty() || item.is_some() || heading.is_some() || fence;
+        if ends_paragraph && !paragraph.is_empty() {
+            result.push(Block::Paragraph(paragraph.join(" ")));
+            paragraph.clear();
+        }
+        if item.is_none() {
+            if let Some((ordered, items)) = list.take() {
+                result.push(Block::List(ordered, items));
+            }
+        }
+
+        if fence {
+            code = Some(Vec::new());
+        } else if let Some((level, text)) = heading {
+            result.push(Block::Heading(level, text));
+        } else if let Some((number, text)) = item {
+            let ordered = number.is_some();
+            if matches!(&list, Some((list_ordered, _)) if *list_ordered != ordered) {
+                let (list_ordered, items) = list.take().unwrap();
+                result.push(Block::List(list_ordered, items));
+            }
+            list.get_or_insert_with(|| (ordered, Vec::new())).1.push((number, text));
+        } else if !trimmed.is_empty() {
+            paragraph.push(trimmed);
+        }
+    }
+
+    // Close whatever is still open at the end of the document
+    if let Some(code_lines) = code {
+        result.push(Block::Code(code_lines));
+    }
+    if !paragraph.is_empty() {
+        result.push(Block::Paragraph(paragraph.join(" ")));
+    }
+    if let Some((ordered, items)) = list {
+        result.push(Block::List(ordered, items));
+    }
+
+    result
+}
+
+// A block of text that's at least `lines` lines tall
+fn block<S, H, F>(runs: Vec<TextRun>, lines: usize, on_link: &Rc<F>) -> View<S, H>
+where
+    F: Fn(&mut S, &mut EventCtx<S, H>, &str) -> Option<Phase> + 'static,
+{
+    let view = if runs.iter().any(|run| run.link.is_some()) {
+        let on_link = on_link.clone();
+        linked_label(runs, move |s, ctx, url| on_link(s, ctx, url))
+    } else {
+        rich_label(runs)
+    };
+
+    view.on_style(move |_, style: &mut Style| {
+        let height = lines as f32 * style.font_size * 1.3 + style.padding_top + style.padding_bottom;
+        style.min_height = style.min_height.max(height);
+    })
+}
+
+// Parse a heading like `## Title` into its level and text
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+    if (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' ')) {
+        Some((level, rest.trim()))
+    } else {
+        None
+    }
+}
+
+// Parse a list item like `- Item` or `3. Item` into its number, if it's ordered, and its text
+fn list_item(line: &str) -> Option<ListItem<'_>> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(text) = line.strip_prefix(bullet) {
+            return Some((None, text.trim()));
+        }
+    }
+
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let text = line[digits..].strip_prefix(". ")?;
+    let number = line[..digits].parse().ok()?;
+    Some((Some(number), text.trim()))
+}
+
+// Split inline markup into styled runs
+fn inline(text: &str) -> Vec<TextRun> {
+    let mut runs = Vec::new();
+    let mut current = String::new();
+    let mut bold = false;
+    let mut italic = false;
+
+    // Push the text collected so far as a run with the current style
+    let flush = |runs: &mut Vec<TextRun>, current: &mut String, bold: bool, italic: bool| {
+        if !current.is_empty() {
+            let mut run = TextRun::new(std::mem::take(current));
+            run.bold = bold;
+            run.italic = italic;
+            runs.push(run);
+        }
+    };
+
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let after = &rest[c.len_utf8()..];
+
+        if c == '\\' {
+            if let Some(escaped) = after.chars().next() {
+                current.push(escaped);
+                rest = &after[escaped.len_utf8()..];
+                continue;
+            }
+        } else if c == '`' {
+            if let Some(end) = after.find('`') {
+                flush(&mut runs, &mut current, bold, italic);
+                runs.push(TextRun::new(&after[..end]).monospace());
+                rest = &after[end + 1..];
+                continue;
+            }
+        } else if rest.starts_with("**") {
+            if flanking(text, rest, 2, bold) {
+                flush(&mut runs, &mut current, bold, italic);
+                bold = !bold;
+            } else {
+                current.push_str("**");
+            }
+            rest = &rest[2..];
+            continue;
+        } else if (c == '*' || (c == '_' && !intraword(text, rest))) && flanking(text, rest, 1, italic) {
+            flush(&mut runs, &mut current, bold, italic);
+            italic = !italic;
+            rest = after;
+            continue;
+        } else if c == '[' {
+            if let Some((label, url, remaining)) = link(after) {
+                flush(&mut runs, &mut current, bold, italic);
+                let mut run = TextRun::new(label).link(url);
+                run.bold = bold;
+                run.italic = italic;
+                runs.push(run);
+                rest = remaining;
+                continue;
+            }
+        }
+
+        current.push(c);
+        rest = after;
+    }
+    flush(&mut runs, &mut current, bold, italic);
+
+    runs
+}
+
+// Whether the character at the start of `rest` is between two letters or digits, like in `snake_case`
+fn intraword(text: &str, rest: &str) -> bool {
+    let before = text[..text.len() - rest.len()].chars().next_back();
+    let after = rest.chars().nth(1);
+    before.map_or(false, char::is_alphanumeric) && after.map_or(false, char::is_alphanumeric)
+}
+
+// Whether the delimiter of `len` bytes at the start of `rest` can open emphasis, by coming right before some text,
+// or close it, by coming right after some, so a `*` between spaces is left as is
+fn flanking(text: &str, rest: &str, len: usize, closing: bool) -> bool {
+    let neighbor = if closing {
+        text[..text.len() - rest.len()].chars().next_back()
+    } else {
+        rest[len..].chars().next()
+    };
+    neighbor.map_or(false, |c| !c.is_whitespace())
+}
+
+// Parse the rest of a link after its `[`, returning the label, the URL, and the text after the link
+fn link(text: &str) -> Option<(&str, &str, &str)> {
+    let label_end = text.find("](")?;
+    let after_label = &text[label_end + 2..];
+    let url_end = after_label.find(')')?;
+    Some((&text[..label_end], after_label[..url_end].trim(), &after_label[url_end + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use druid_shell::piet::{Device, ImageFormat};
+
+    use super::*;
+    use crate::callbacks::{PointerButton, RawPointerEvent};
+
+    // The text of each run, and whether it's bold and italic
+    fn styled(text: &str) -> Vec<(String, bool, bool)> {
+        inline(text).into_iter().map(|run| (run.text, run.bold, run.italic)).collect()
+    }
+
+    fn plain(text: &str) -> Vec<(String, bool, bool)> {
+        vec![(text.to_string(), false, false)]
+    }
+
+    #[test]
+    fn headings() {
+        assert_eq!(heading("# Title"), Some((1, "Title")));
+        assert_eq!(heading("###   Spaced  "), Some((3, "Spaced")));
+        assert_eq!(heading("#"), Some((1, "")));
+        assert_eq!(heading("#Title"), None);
+        assert_eq!(heading("####### Seven"), None);
+        assert_eq!(heading("Text"), None);
+    }
+
+    #[test]
+    fn list_items() {
+        assert_eq!(list_item("- One"), Some((None, "One")));
+        assert_eq!(list_item("* Two"), Some((None, "Two")));
+        assert_eq!(list_item("+ Three"), Some((None, "Three")));
+        assert_eq!(list_item("12. Twelve"), Some((Some(12), "Twelve")));
+        assert_eq!(list_item("-One"), None);
+        assert_eq!(list_item("1.One"), None);
+        assert_eq!(list_item(". Nothing"), None);
+    }
+
+    #[test]
+    fn inline_emphasis() {
+        assert_eq!(
+            styled("a **b** *c* _d_"),
+            vec![
+                ("a ".to_string(), false, false),
+                ("b".to_string(), true, false),
+                (" ".to_string(), false, false),
+                ("c".to_string(), false, true),
+                (" ".to_string(), false, false),
+                ("d".to_string(), false, true),
+            ]
+        );
+        assert_eq!(
+            styled("**bold *both***"),
+            vec![("bold ".to_string(), true, false), ("both".to_string(), true, true)]
+        );
+    }
+
+    #[test]
+    fn inline_delimiters_need_flanking_text() {
+        assert_eq!(styled("2 * 3 * 4"), plain("2 * 3 * 4"));
+        assert_eq!(styled("a ** b"), plain("a ** b"));
+        assert_eq!(styled("a _ b"), plain("a _ b"));
+        assert_eq!(styled("snake_case_name"), plain("snake_case_name"));
+        assert_eq!(styled("\\*not italic\\*"), plain("*not italic*"));
+    }
+
+    #[test]
+    fn inline_code_and_links() {
+        let runs = inline("see `x*y` at [the site](https://example.com)");
+        assert_eq!(runs.len(), 4);
+        assert_eq!(runs[1].text, "x*y");
+        assert!(runs[1].monospace && !runs[1].italic);
+        assert_eq!(runs[3].text, "the site");
+        assert_eq!(runs[3].link.as_deref(), Some("https://example.com"));
+    }
+
+    #[test]
+    fn block_structure() {
+        let source = "# Title\n\nFirst line\nsecond line\n- one\n- two\n1. three\n```\nlet x = 1;\n\n```\nLast\n## End";
+        assert_eq!(
+            blocks(source),
+            vec![
+                Block::Heading(1, "Title"),
+                Block::Paragraph("First line second line".to_string()),
+                Block::List(false, vec![(None, "one"), (None, "two")]),
+                Block::List(true, vec![(Some(1), "three")]),
+                Block::Code(vec!["let x = 1;", ""]),
+                Block::Paragraph("Last".to_string()),
+                Block::Heading(2, "End"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_blocks_end_with_the_document() {
+        assert_eq!(
+            blocks("text\n```\ncode"),
+            vec![Block::Paragraph("text".to_string()), Block::Code(vec!["code"])]
+        );
+        assert_eq!(blocks("- item"), vec![Block::List(false, vec![(None, "item")])]);
+    }
+
+    #[derive(Default)]
+    struct State {
+        links: Vec<String>,
+    }
+
+    // Each kind of block gets its own background, and is as tall as its lines, 13px at this font size
+    fn document(_: &State) -> View<State, ()> {
+        let sheet = Stylesheet::parse(
+            ".markdown { font-size: 10px; }
+            .markdown > .h1 { flex-grow: 0; background-color: #ff0000; }
+            .markdown > .p { flex-grow: 0; background-color: #00ff00; }
+            .markdown > .ul { flex-grow: 0; }
+            .ul > .li { flex-grow: 0; background-color: #0000ff; }
+            .markdown > .pre { flex-grow: 0; background-color: #ffff00; }",
+        );
+        let source = "# Title\n\n[link](https://example.com)\n\n- one\n- two\n\n```\ncode\n```";
+        let markdown = markdown(source, |s: &mut State, _, url| {
+            s.links.push(url.to_string());
+            Some(Phase::Idle)
+        });
+        View::default().use_style_sheet(Some(sheet)).add_child(markdown)
+    }
+
+    #[test]
+    fn renders_blocks_and_follows_links() {
+        let mut state = State::default();
+        let mut viewport = Viewport::new(ResourceLoader::default(), document, (100.0, 100.0), ());
+
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(100, 100, 1.0).unwrap();
+        let mut piet = target.render_context();
+        viewport.draw(&state, Some(&mut piet)).unwrap();
+        piet.finish().unwrap();
+        drop(piet);
+        let image = target.to_image_buf(ImageFormat::RgbaPremul).unwrap();
+        let pixels = image.raw_pixels();
+
+        // Sampled at the right edge, past the text, halfway down each line
+        let rgb = |y: usize| {
+            let i = (y * 100 + 95) * 4;
+            [pixels[i], pixels[i + 1], pixels[i + 2]]
+        };
+        assert_eq!(rgb(6), [255, 0, 0]);
+        assert_eq!(rgb(19), [0, 255, 0]);
+        assert_eq!(rgb(32), [0, 0, 255]);
+        assert_eq!(rgb(45), [0, 0, 255]);
+        assert_eq!(rgb(58), [255, 255, 0]);
+        assert_eq!(rgb(80), [0, 0, 0]);
+
+        // Past the end of the link, the paragraph doesn't react
+        viewport.pointer_down(&mut state, RawPointerEvent::at(95.0, 19.0).press(PointerButton::Left));
+        assert!(state.links.is_empty());
+
+        viewport.pointer_down(&mut state, RawPointerEvent::at(3.0, 19.0).press(PointerButton::Left));
+        assert_eq!(state.links, ["https://example.com"]);
+    }
+}
@@ -1,14 +1,61 @@
+mod badge;
+pub use badge::badge;
+
 mod button;
 pub use button::button;
 
 mod label;
-pub use label::label;
+pub use label::{highlighted_label, label, Highlight};
 
 mod dynlabel;
 pub use dynlabel::DynLabel;
 
+mod canvas;
+pub use canvas::canvas;
+
+mod checkbox;
+pub use checkbox::Checkbox;
+
+mod collapsible;
+pub use collapsible::{Collapsible, CollapsibleGroup};
+
+mod colorpicker;
+pub use colorpicker::ColorPicker;
+
+mod datepicker;
+pub use datepicker::{Date, DatePicker};
+
+#[cfg(feature = "markdown")]
+mod markdown;
+#[cfg(feature = "markdown")]
+pub use markdown::markdown;
+
+mod palette;
+pub use palette::CommandPalette;
+
+mod progressbar;
+pub use progressbar::ProgressBar;
+
 mod textbox;
 pub use textbox::TextBox;
 
+mod reveal;
+pub(crate) use reveal::reveal;
+
+mod richlabel;
+pub use richlabel::{linked_label, rich_label, TextRun};
+
+mod rubberband;
+pub use rubberband::RubberBand;
+
 mod slider;
-pub use slider::Slider;
+pub use slider::{RangeSlider, Slider};
+
+mod spinner;
+pub use spinner::Spinner;
+
+mod splitpane;
+pub use splitpane::SplitPane;
+
+mod toasts;
+pub use toasts::{ToastLevel, Toasts};
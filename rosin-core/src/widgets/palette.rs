@@ -0,0 +1,288 @@
+#![forbid(unsafe_code)]
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Debug,
+    rc::Rc,
+};
+
+use druid_shell::KeyState;
+
+use crate::prelude::*;
+use crate::widgets::*;
+
+// ---------- Command Palette ----------
+/// A searchable list of the app's commands. Typing filters the list, the arrow keys move the highlight,
+/// Enter runs the highlighted command with `EventCtx::dispatch()`, and Escape closes the palette.
+/// Commands are ordered by how well they match, using `fuzzy_match()`, and then by how recently they were used.
+///
+/// The palette is only part of the tree while it's open. Open it from a callback with `open()`, such as a `KeyDown`
+/// handler on the root node for Ctrl+Shift+P, so it gets focus. Disabled commands aren't listed.
+///
+/// The palette has a `query` node with the search text, and a node with the class `item` for each command, where the highlighted one matches `:selected`.
+/// The matched characters in each item are bold.
+#[derive(Debug)]
+pub struct CommandPalette {
+    pub key: Key,
+    data: Rc<Data>,
+}
+
+#[derive(Debug)]
+struct Data {
+    open: Cell<bool>,
+    query: RefCell<String>,
+    selected: Cell<usize>,
+    // The commands that were listed when the tree was last built
+    listed: RefCell<Vec<CommandId>>,
+    // Most recently used first
+    recent: RefCell<Vec<CommandId>>,
+}
+
+impl Default for CommandPalette {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandPalette {
+    pub fn new() -> Self {
+        Self {
+            key: Key::new(),
+            data: Rc::new(Data {
+                open: Cell::new(false),
+                query: RefCell::new(String::new()),
+                selected: Cell::new(0),
+                listed: RefCell::new(Vec::new()),
+                recent: RefCell::new(Vec::new()),
+            }),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.data.open.get()
+    }
+
+    /// Open the palette with an empty search, and focus it.
+    pub fn open<S, H>(&self, ctx: &mut EventCtx<S, H>) -> Phase {
+        self.data.open.set(true);
+        self.data.query.borrow_mut().clear();
+        self.data.selected.set(0);
+        ctx.focus_on(self.key);
+        Phase::Build
+    }
+
+    pub fn close(&self) -> Phase {
+        self.data.open.set(false);
+        Phase::Build
+    }
+
+    pub fn view<S, H>(&self, state: &S, commands: &Commands<S>) -> View<S, H> {
+        if !self.data.open.get() {
+            return ui!([]);
+        }
+
+        let key = self.key;
+        let query = self.data.query.borrow().clone();
+        let recent = self.data.recent.borrow();
+
+        // Sort by match quality, then by how recently each command was used
+        let mut matches: Vec<(i64, usize, &Command<S>, Vec<usize>)> = commands
+            .iter()
+            .filter(|command| command.is_enabled(state))
+            .filter_map(|command| {
+                let (score, positions) = fuzzy_match(&query, &command.title)?;
+                let recency = recent.iter().position(|id| *id == command.id).unwrap_or(usize::MAX);
+                Some((score, recency, command, positions))
+            })
+            .collect();
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        let selected = self.data.selected.get().min(matches.len().saturating_sub(1));
+        self.data.selected.set(selected);
+        let listed = matches.iter().map(|(_, _, command, _)| command.id).collect();
+        self.data.listed.replace(listed);
+
+        let weak = Rc::downgrade(&self.data);
+        let mut result = ui!([{
+            .key(key)
+            .event(On::PointerDown, move |_, ctx: &mut EventCtx<S, H>| {
+                ctx.focus_on(key);
+                Some(Phase::Idle)
+            })
+            .event(On::Keyboard, move |_, ctx: &mut EventCtx<S, H>| {
+                let this = weak.upgrade()?;
+                let event = ctx.keyboard()?;
+                if event.state != KeyState::Down {
+                    return Some(Phase::Idle);
+                }
+
+                match event.key.clone() {
+                    KbKey::Character(c) => {
+                        this.query.borrow_mut().push_str(&c);
+                        this.selected.set(0);
+                    }
+                    KbKey::Backspace => {
+                        this.query.borrow_mut().pop();
+                        this.selected.set(0);
+                    }
+                    KbKey::ArrowDown => this.selected.set(this.selected.get() + 1),
+                    KbKey::ArrowUp => this.selected.set(this.selected.get().saturating_sub(1)),
+                    KbKey::Enter => {
+                        let id = *this.listed.borrow().get(this.selected.get())?;
+                        this.run(id, ctx);
+                    }
+                    KbKey::Escape => {
+                        this.open.set(false);
+                        ctx.blur();
+                    }
+                    _ => return Some(Phase::Idle),
+                }
+                ctx.stop_propagation();
+                Some(Phase::Build)
+            })
+        }
+        "query" (label(query))
+        ]);
+
+        for (i, (_, _, command, positions)) in matches.iter().enumerate() {
+            let id = command.id;
+            let weak = Rc::downgrade(&self.data);
+            // The label widens each span to cover the whole character
+            let spans = positions.iter().map(|&p| p..p + 1);
+            let item = highlighted_label(command.title.clone(), spans, Highlight::default())
+                .add_classes("item")
+                .selected(i == selected);
+            result = result.add_child(item.event(On::PointerDown, move |_, ctx: &mut EventCtx<S, H>| {
+                weak.upgrade()?.run(id, ctx);
+                // The palette would take focus again
+                ctx.stop_propagation();
+                Some(Phase::Build)
+            }));
+        }
+
+        result
+    }
+}
+
+impl Data {
+    fn run<S, H>(&self, id: CommandId, ctx: &mut EventCtx<S, H>) {
+        let mut recent = self.recent.borrow_mut();
+        recent.retain(|recent_id| *recent_id != id);
+        recent.insert(0, id);
+
+        self.open.set(false);
+        ctx.blur();
+        ctx.dispatch(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use druid_shell::KeyEvent;
+
+    use super::*;
+    use crate::callbacks::{PointerButton, RawPointerEvent};
+
+    const OPEN: CommandId = CommandId("file.open");
+    const SAVE: CommandId = CommandId("file.save");
+    const CLOSE: CommandId = CommandId("window.close");
+
+    struct State {
+        palette: CommandPalette,
+        commands: Rc<Commands<State>>,
+        ran: Vec<&'static str>,
+    }
+
+    impl State {
+        fn new() -> Self {
+            let command = |id: CommandId, title| {
+                Command::new(id, title, move |s: &mut State| {
+                    s.ran.push(id.0);
+                    Phase::Idle
+                })
+            };
+            let commands = Commands::new()
+                .add_command(command(OPEN, "Open File"))
+                .add_command(command(SAVE, "Save File"))
+                .add_command(command(CLOSE, "Close Window"));
+            Self {
+                palette: CommandPalette::new(),
+                commands: Rc::new(commands),
+                ran: Vec::new(),
+            }
+        }
+    }
+
+    fn view(state: &State) -> View<State, ()> {
+        View::default()
+            .event(On::PointerDown, |s: &mut State, ctx| {
+                if s.palette.is_open() {
+                    return None;
+                }
+                Some(s.palette.open(ctx))
+            })
+            .add_child(state.palette.view(state, &state.commands))
+    }
+
+    fn press(viewport: &mut Viewport<State, ()>, state: &mut State, key: KbKey) {
+        let event = KeyEvent {
+            state: KeyState::Down,
+            key,
+            ..Default::default()
+        };
+        viewport.key_event(state, event);
+        viewport.draw(state, None).unwrap();
+    }
+
+    fn type_text(viewport: &mut Viewport<State, ()>, state: &mut State, text: &str) {
+        for c in text.chars() {
+            press(viewport, state, KbKey::Character(c.to_string()));
+        }
+    }
+
+    fn open(viewport: &mut Viewport<State, ()>, state: &mut State) {
+        viewport.pointer_down(state, RawPointerEvent::at(5.0, 5.0).press(PointerButton::Left));
+        viewport.draw(state, None).unwrap();
+        assert!(state.palette.is_open());
+    }
+
+    #[test]
+    fn typing_filters_and_enter_runs_the_highlighted_command() {
+        let mut state = State::new();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (200.0, 200.0), ());
+        viewport.set_commands(state.commands.clone());
+        viewport.draw(&state, None).unwrap();
+
+        open(&mut viewport, &mut state);
+        assert_eq!(state.palette.data.listed.borrow().len(), 3);
+
+        type_text(&mut viewport, &mut state, "fi");
+        assert_eq!(*state.palette.data.listed.borrow(), [OPEN, SAVE]);
+
+        press(&mut viewport, &mut state, KbKey::ArrowDown);
+        press(&mut viewport, &mut state, KbKey::Enter);
+        assert_eq!(state.ran, ["file.save"]);
+        assert!(!state.palette.is_open());
+
+        // Recently used commands come first among equal matches
+        open(&mut viewport, &mut state);
+        type_text(&mut viewport, &mut state, "fi");
+        assert_eq!(*state.palette.data.listed.borrow(), [SAVE, OPEN]);
+    }
+
+    #[test]
+    fn escape_closes_without_running_anything() {
+        let mut state = State::new();
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (200.0, 200.0), ());
+        viewport.set_commands(state.commands.clone());
+        viewport.draw(&state, None).unwrap();
+
+        open(&mut viewport, &mut state);
+        type_text(&mut viewport, &mut state, "xyz");
+        assert!(state.palette.data.listed.borrow().is_empty());
+        press(&mut viewport, &mut state, KbKey::Enter);
+        press(&mut viewport, &mut state, KbKey::Escape);
+        assert!(!state.palette.is_open());
+        assert!(state.ran.is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+#![forbid(unsafe_code)]
+
+use std::{cell::Cell, fmt::Debug, rc::Rc};
+
+use druid_shell::{
+    kurbo::Rect,
+    piet::{Color, RenderContext, TextLayout, TextLayoutBuilder},
+};
+
+use crate::prelude::*;
+use crate::text::text_layout_builder;
+
+// ---------- Progress Bar ----------
+/// A bar that fills from left to right as `value` goes from 0.0 to 1.0, drawn with the `color` property.
+/// An optional buffered value, like how much of a download has been received, is drawn as a lighter fill behind it.
+#[derive(Debug)]
+pub struct ProgressBar {
+    pub key: Key,
+    data: Rc<Data>,
+}
+
+#[derive(Debug)]
+struct Data {
+    value: Cell<f64>,
+    buffered: Cell<f64>,
+    format: Cell<Option<LabelFormat>>,
+    changed: Cell<bool>,
+}
+
+// Turns the value into the label's text
+type LabelFormat = fn(f64) -> String;
+
+impl ProgressBar {
+    pub fn new(value: f64) -> Self {
+        Self {
+            key: Key::new(),
+            data: Rc::new(Data {
+                value: Cell::new(value.clamp(0.0, 1.0)),
+                buffered: Cell::new(0.0),
+                format: Cell::new(None),
+                changed: Cell::new(false),
+            }),
+        }
+    }
+
+    /// Show a label centered over the bar, with text from `format`, such as `ProgressBar::percent`.
+    pub fn with_label(self, format: fn(f64) -> String) -> Self {
+        self.data.format.set(Some(format));
+        self
+    }
+
+    /// Format a value as a whole percentage, like `42%`.
+    pub fn percent(value: f64) -> String {
+        format!("{:.0}%", value * 100.0)
+    }
+
+    pub fn set(&self, new_value: f64) -> Phase {
+        self.data.value.replace(new_value.clamp(0.0, 1.0));
+        self.data.changed.replace(true);
+        Phase::Draw
+    }
+
+    pub fn get(&self) -> f64 {
+        self.data.value.get()
+    }
+
+    pub fn set_buffered(&self, new_buffered: f64) -> Phase {
+        self.data.buffered.replace(new_buffered.clamp(0.0, 1.0));
+        self.data.changed.replace(true);
+        Phase::Draw
+    }
+
+    pub fn buffered(&self) -> f64 {
+        self.data.buffered.get()
+    }
+
+    pub fn view<S, H>(&self) -> View<S, H> {
+        let weak = Rc::downgrade(&self.data);
+
+        ui!([
+            .key(self.key)
+            .on_draw(true, move |_, ctx: &mut DrawCtx| {
+                // If the underlying data is gone, then just return since there's nothing to draw.
+                let this = if let Some(this) = weak.upgrade() { this } else { return };
+                if !this.changed.get() && !ctx.must_draw { return }
+                this.changed.set(false);
+
+                let color = ctx.style.color.clone();
+                let value = this.value.get();
+                let buffered = this.buffered.get().max(value);
+
+                let filled = Rect::new(0.0, 0.0, value * ctx.width, ctx.height);
+                ctx.piet.fill(Rect::new(0.0, 0.0, buffered * ctx.width, ctx.height), &color.clone().with_alpha(0.35));
+                ctx.piet.fill(filled, &color);
+
+                let format = if let Some(format) = this.format.get() { format } else { return };
+
+                // Draw the label twice, so it contrasts with both the filled and unfilled parts of the bar
+                let text = format(value);
+                let (r, g, b, _) = color.as_rgba();
+                let inverse = if 0.299 * r + 0.587 * g + 0.114 * b > 0.5 { Color::BLACK } else { Color::WHITE };
+                for (text_color, clip) in [(color.clone(), None), (inverse, Some(filled))] {
+                    let layout = text_layout_builder(ctx.piet.text(), text.clone(), ctx.style)
+                        .text_color(text_color)
+                        .build()
+                        .unwrap();
+                    let size = layout.size();
+                    let pos = ((ctx.width - size.width) / 2.0, (ctx.height - size.height) / 2.0);
+
+                    let _ = ctx.piet.with_save(|piet| {
+                        if let Some(clip) = clip {
+                            piet.clip(clip);
+                        }
+                        piet.draw_text(&layout, pos);
+                        Ok(())
+                    });
+                }
+            })
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use druid_shell::piet::{Device, ImageFormat};
+
+    use super::*;
+
+    #[test]
+    fn values_are_clamped() {
+        let bar = ProgressBar::new(1.5);
+        assert_eq!(bar.get(), 1.0);
+        assert_eq!(bar.set(-0.25), Phase::Draw);
+        assert_eq!(bar.get(), 0.0);
+        assert_eq!(bar.set_buffered(2.0), Phase::Draw);
+        assert_eq!(bar.buffered(), 1.0);
+        assert_eq!(bar.set(0.4), Phase::Draw);
+        assert_eq!(bar.get(), 0.4);
+    }
+
+    #[test]
+    fn percent_rounds_to_whole_numbers() {
+        assert_eq!(ProgressBar::percent(0.0), "0%");
+        assert_eq!(ProgressBar::percent(0.424), "42%");
+        assert_eq!(ProgressBar::percent(0.996), "100%");
+    }
+
+    #[test]
+    fn draws_fill_buffer_and_a_label_that_contrasts_with_both() {
+        fn view(bar: &ProgressBar) -> View<ProgressBar, ()> {
+            View::default().add_child(bar.view().inline_style("width: 200px; height: 20px; color: #0000ff"))
+        }
+
+        let bar = ProgressBar::new(0.5).with_label(ProgressBar::percent);
+        bar.set_buffered(0.75);
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (200.0, 20.0), ());
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(200, 20, 1.0).unwrap();
+        let mut piet = target.render_context();
+        viewport.draw(&bar, Some(&mut piet)).unwrap();
+        piet.finish().unwrap();
+        drop(piet);
+        let image = target.to_image_buf(ImageFormat::RgbaPremul).unwrap();
+        let pixels = image.raw_pixels();
+        let pixel = |x: usize, y: usize| {
+            let i = (y * 200 + x) * 4;
+            [pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]]
+        };
+
+        // Filled, buffered at 35% opacity, and empty, away from the label in the middle
+        assert_eq!(pixel(10, 10), [0, 0, 255, 255]);
+        let buffered = pixel(140, 10);
+        assert_eq!(&buffered[..2], [0, 0]);
+        assert!((88..=90).contains(&buffered[3]) && buffered[2] == buffered[3]);
+        assert_eq!(pixel(190, 10), [0, 0, 0, 0]);
+
+        // The label is white over the fill, and blue past its edge
+        let area = |xs: std::ops::Range<usize>| xs.flat_map(|x| (0..20).map(move |y| pixel(x, y))).collect::<Vec<_>>();
+        assert!(area(80..100).iter().any(|p| p[0] > 0));
+        let past_edge = area(100..120);
+        assert!(past_edge.iter().all(|p| p[0] == 0));
+        assert!(past_edge.iter().any(|p| p[3] > buffered[3]));
+    }
+}
@@ -0,0 +1,44 @@
+#![forbid(unsafe_code)]
+
+use std::{cell::Cell, rc::Rc};
+
+use crate::prelude::*;
+use crate::style::{ClipPath, FlexDirection, Length, LengthPercentage};
+
+// ---------- Reveal ----------
+// Wrap content that slides open and closed, for widgets that animate their height.
+// `progress` is how far open the content is, from 0.0 to 1.0. While it's partly open, the wrapper is clipped to that much
+// of the content's natural height, which is written to `height` after each layout.
+// The clipping is set by a style callback, so animations that change `progress` should return `Phase::Style`.
+pub(crate) fn reveal<S, H>(content: View<S, H>, progress: impl Fn() -> f64 + 'static, height: Rc<Cell<f32>>) -> View<S, H> {
+    let height2 = height.clone();
+
+    ui!([{
+            .on_style(move |_, style: &mut Style| {
+                let progress = progress() as f32;
+                style.flex_direction = FlexDirection::Column;
+                if progress < 1.0 {
+                    let zero = LengthPercentage::Length(Length::Px(0.0));
+                    style.max_height = progress * height.get();
+                    style.min_height = 0.0;
+                    style.clip_path = Some(ClipPath::Inset {
+                        top: zero,
+                        right: zero,
+                        bottom: zero,
+                        left: zero,
+                        radii: [zero; 4],
+                    });
+                }
+            })
+        }
+        [{
+            .on_style(|_, style: &mut Style| {
+                style.flex_shrink = 0.0;
+            })
+            .on_layout(move |_, size| {
+                height2.set(size.height);
+            })
+        }
+        (content)]
+    ])
+}
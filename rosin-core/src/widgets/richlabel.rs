@@ -0,0 +1,223 @@
+#![forbid(unsafe_code)]
+
+use std::{cell::RefCell, ops::Range, rc::Rc, sync::Arc};
+
+use druid_shell::{
+    kurbo::{Point, Rect, Vec2},
+    piet::{Color, FontFamily, FontStyle, FontWeight, RenderContext, Text, TextAttribute, TextLayout, TextLayoutBuilder},
+};
+
+use crate::prelude::*;
+use crate::text::text_layout_builder;
+
+/// A piece of text with its own style, for building a `rich_label`. Runs without a color use the node's `color`.
+#[derive(Debug, Clone, Default)]
+pub struct TextRun {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub monospace: bool,
+    pub color: Option<Color>,
+    /// A URL or other target that's passed to the callback of a `linked_label` when the run is clicked.
+    pub link: Option<Arc<str>>,
+}
+
+impl TextRun {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    pub fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+
+    pub fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    pub fn monospace(mut self) -> Self {
+        self.monospace = true;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Make the run a link. Links are underlined.
+    pub fn link(mut self, target: impl Into<Arc<str>>) -> Self {
+        self.link = Some(target.into());
+        self.underline = true;
+        self
+    }
+}
+
+// ---------- Rich Label ----------
+/// A static label made of runs of differently styled text, laid out as one paragraph that wraps to the node's width.
+pub fn rich_label<S, H>(runs: impl IntoIterator<Item = TextRun>) -> View<S, H> {
+    rich_view(runs, Rc::default())
+}
+
+/// A rich label where clicking a run with a link calls `on_link` with its target.
+pub fn linked_label<S, H>(
+    runs: impl IntoIterator<Item = TextRun>,
+    on_link: impl Fn(&mut S, &mut EventCtx<S, H>, &str) -> Option<Phase> + 'static,
+) -> View<S, H> {
+    let links: LinkRects = Rc::default();
+    rich_view(runs, links.clone()).event(On::PointerDown, move |s, ctx| {
+        let info = ctx.pointer()?;
+        let pos = Point::new(info.pos_x, info.pos_y);
+        let target = links.borrow().iter().find(|(rect, _)| rect.contains(pos))?.1.clone();
+        on_link(s, ctx, &target)
+    })
+}
+
+// Where each link was drawn, relative to the node, so clicks can be matched to them
+type LinkRects = Rc<RefCell<Vec<(Rect, Arc<str>)>>>;
+
+fn rich_view<S, H>(runs: impl IntoIterator<Item = TextRun>, links: LinkRects) -> View<S, H> {
+    let (text, styled) = join_runs(runs);
+
+    ui!([
+        .on_draw(true, move |_: &S, ctx: &mut DrawCtx| {
+            let style = ctx.style;
+            let max_width = ctx.width
+                - (style.border_left_width + style.padding_left + style.padding_right + style.border_right_width) as f64;
+            let offset = Vec2::new(
+                (style.border_left_width + style.padding_left) as f64,
+                (style.border_top_width + style.padding_top) as f64,
+            );
+
+            let layout = rich_layout(ctx.piet.text(), &text, &styled, style, max_width.max(0.0));
+
+            let mut link_rects = links.borrow_mut();
+            link_rects.clear();
+            for (range, run) in &styled {
+                if let Some(target) = &run.link {
+                    for rect in layout.rects_for_range(range.clone()) {
+                        link_rects.push((rect + offset, target.clone()));
+                    }
+                }
+            }
+
+            ctx.piet.draw_text(&layout, offset.to_point());
+        })
+    ])
+}
+
+// Lay out the joined runs in the node's style, each with its own attributes, wrapping at `max_width`
+fn rich_layout<T: Text>(
+    factory: &mut T,
+    text: &Arc<str>,
+    styled: &[(Range<usize>, TextRun)],
+    style: &Style,
+    max_width: f64,
+) -> T::TextLayout {
+    let mut builder = text_layout_builder(factory, text.clone(), style).max_width(max_width);
+    for (range, run) in styled {
+        if run.bold {
+            builder = builder.range_attribute(range.clone(), TextAttribute::Weight(FontWeight::BOLD));
+        }
+        if run.italic {
+            builder = builder.range_attribute(range.clone(), TextAttribute::Style(FontStyle::Italic));
+        }
+        if run.underline {
+            builder = builder.range_attribute(range.clone(), TextAttribute::Underline(true));
+        }
+        if run.monospace {
+            builder = builder.range_attribute(range.clone(), TextAttribute::FontFamily(FontFamily::MONOSPACE));
+        }
+        if let Some(color) = &run.color {
+            builder = builder.range_attribute(range.clone(), TextAttribute::TextColor(color.clone()));
+        }
+    }
+    builder.build().unwrap()
+}
+
+// Join the runs into one string, and find which part of it each run covers
+fn join_runs(runs: impl IntoIterator<Item = TextRun>) -> (Arc<str>, Vec<(Range<usize>, TextRun)>) {
+    let mut text = String::new();
+    let mut styled = Vec::new();
+    for run in runs {
+        let start = text.len();
+        text.push_str(&run.text);
+        styled.push((start..text.len(), run));
+    }
+    (text.into(), styled)
+}
+
+#[cfg(test)]
+mod tests {
+    use druid_shell::piet::Device;
+
+    use super::*;
+
+    #[test]
+    fn runs_are_joined_into_one_paragraph() {
+        let (text, styled) = join_runs([
+            TextRun::new("Plain, "),
+            TextRun::new("bold").bold(),
+            TextRun::new(""),
+            TextRun::new(" and ünderlined").underline(),
+        ]);
+        assert_eq!(&*text, "Plain, bold and ünderlined");
+
+        let ranges: Vec<_> = styled.iter().map(|(range, _)| range.clone()).collect();
+        assert_eq!(ranges, [0..7, 7..11, 11..11, 11..27]);
+        assert!(styled[1].1.bold && !styled[1].1.underline);
+    }
+
+    #[test]
+    fn links_are_underlined() {
+        let run = TextRun::new("docs").link("https://example.com");
+        assert!(run.underline);
+        assert_eq!(run.link.as_deref(), Some("https://example.com"));
+    }
+
+    #[test]
+    fn mixed_runs_wrap_to_the_node_width() {
+        fn runs() -> [TextRun; 5] {
+            [
+                TextRun::new("Bold ").bold(),
+                TextRun::new("italic ").italic(),
+                TextRun::new("code ").monospace(),
+                TextRun::new("red ").color(Color::rgb8(255, 0, 0)),
+                TextRun::new("link").link("target"),
+            ]
+        }
+        fn view(_: &()) -> View<(), ()> {
+            let label = linked_label(runs(), |_, _, _| Some(Phase::Idle)).inline_style("width: 30px; padding: 4px");
+            View::default().add_child(label)
+        }
+
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(100, 100, 1.0).unwrap();
+        let mut piet = target.render_context();
+
+        // The runs stay on one line when there's room, and break into several in a 30px node
+        let (text, styled) = join_runs(runs());
+        let style = Style::default();
+        let wide = rich_layout(piet.text(), &text, &styled, &style, f64::INFINITY);
+        assert_eq!(wide.line_count(), 1);
+        let narrow = rich_layout(piet.text(), &text, &styled, &style, 30.0 - 8.0);
+        assert!(narrow.line_count() > 1);
+        assert!(narrow.size().width < wide.size().width);
+
+        // And a node with that width draws them
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&(), Some(&mut piet)).unwrap();
+        piet.finish().unwrap();
+    }
+}
@@ -0,0 +1,333 @@
+#![forbid(unsafe_code)]
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Debug,
+    rc::Rc,
+};
+
+use druid_shell::kurbo::{Point, Rect, Vec2};
+
+use crate::prelude::*;
+use crate::selection::SelectMode;
+use crate::style::FlexDirection;
+
+// ---------- Rubber Band ----------
+/// Select items by dragging a rectangle over them, like in a file manager. Items that the rectangle touches are selected,
+/// and clicking selects the item under the pointer, or clears the selection when there's none.
+/// Holding Shift adds to the selection, and holding Ctrl or Cmd toggles items in and out of it.
+///
+/// Pass the keys of the selectable items, which must be descendants of `content`, and the current selection.
+/// `on_select` is called with the new selection whenever it changes during a drag.
+/// Items that handle their own clicks can call `EventCtx::stop_propagation()` to keep the drag from starting.
+///
+/// The widget captures the pointer while dragging, so the drag goes on until the button is released, even outside of it.
+/// While dragging, the rectangle is drawn by a node with the class `band` over the content, in its `color`.
+#[derive(Debug)]
+pub struct RubberBand {
+    pub key: Key,
+    data: Rc<Data>,
+}
+
+#[derive(Debug)]
+struct Data {
+    band_key: Key,
+    // The items and selection passed to the last view
+    items: RefCell<Vec<Key>>,
+    selection: RefCell<Vec<Key>>,
+    // The selection when the drag started, and the last one reported
+    initial: RefCell<Vec<Key>>,
+    reported: RefCell<Vec<Key>>,
+    mode: Cell<SelectMode>,
+    // Where the drag started and where the pointer is now, relative to the widget
+    origin: Cell<Option<Point>>,
+    current: Cell<Point>,
+    // Where the band node is, relative to the widget
+    band_offset: Cell<Vec2>,
+    content_height: Cell<f32>,
+}
+
+impl Default for RubberBand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RubberBand {
+    pub fn new() -> Self {
+        Self {
+            key: Key::new(),
+            data: Rc::new(Data {
+                band_key: Key::new(),
+                items: RefCell::new(Vec::new()),
+                selection: RefCell::new(Vec::new()),
+                initial: RefCell::new(Vec::new()),
+                reported: RefCell::new(Vec::new()),
+                mode: Cell::new(SelectMode::Replace),
+                origin: Cell::new(None),
+                current: Cell::new(Point::ZERO),
+                band_offset: Cell::new(Vec2::ZERO),
+                content_height: Cell::new(0.0),
+            }),
+        }
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.data.origin.get().is_some()
+    }
+
+    /// The selection rectangle relative to the widget, while dragging.
+    pub fn rect(&self) -> Option<Rect> {
+        self.data.rect()
+    }
+
+    pub fn view<S, H>(
+        &self,
+        content: View<S, H>,
+        items: &[Key],
+        selection: &[Key],
+        on_select: impl Fn(&mut S, Vec<Key>) -> Phase + 'static,
+    ) -> View<S, H> {
+        self.data.items.replace(items.to_vec());
+        self.data.selection.replace(selection.to_vec());
+
+        let on_select = Rc::new(on_select);
+        let on_select2 = on_select.clone();
+        let weak1 = Rc::downgrade(&self.data);
+        let weak2 = Rc::downgrade(&self.data);
+        let weak3 = Rc::downgrade(&self.data);
+        let weak4 = Rc::downgrade(&self.data);
+        let weak5 = Rc::downgrade(&self.data);
+        let weak6 = Rc::downgrade(&self.data);
+
+        let key = self.key;
+        let result = ui!([{
+            .key(key)
+            .on_style(|_, style: &mut Style| {
+                style.flex_direction = FlexDirection::Column;
+            })
+            .event(On::PointerDown, move |s, ctx: &mut EventCtx<S, H>| {
+                let this = weak1.upgrade()?;
+                let info = ctx.pointer()?;
+                if !info.button.is_left() {
+                    return Some(Phase::Idle);
+                }
+
+                let pos = Point::new(info.pos_x, info.pos_y);
+                this.mode.set(SelectMode::from_modifiers(info.mods));
+                this.origin.set(Some(pos));
+                this.current.set(pos);
+                this.initial.replace(this.selection.borrow().clone());
+                this.reported.replace(this.selection.borrow().clone());
+                ctx.capture_pointer(key);
+
+                let mut phase = this.select(s, ctx, &*on_select);
+                phase.update(Phase::Build);
+                Some(phase)
+            })
+            .event(On::PointerMove, move |s, ctx: &mut EventCtx<S, H>| {
+                let this = weak2.upgrade()?;
+                let info = ctx.pointer()?;
+                this.origin.get()?;
+
+                if !info.buttons.has_left() {
+                    this.origin.set(None);
+                    return Some(Phase::Build);
+                }
+
+                this.current.set(Point::new(info.pos_x, info.pos_y));
+                if let Some(band) = ctx.node_rect(this.band_key) {
+                    this.band_offset.set(band.origin().to_vec2());
+                }
+
+                let mut phase = this.select(s, ctx, &*on_select2);
+                phase.update(Phase::Draw);
+                Some(phase)
+            })
+            .event(On::PointerUp, move |_, _| {
+                weak3.upgrade()?.origin.take()?;
+                Some(Phase::Build)
+            })
+        }
+        [{
+            .on_style(|_, style: &mut Style| {
+                style.flex_direction = FlexDirection::Column;
+                style.flex_grow = 1.0;
+            })
+            .on_layout(move |_, size| {
+                if let Some(this) = weak4.upgrade() {
+                    this.content_height.set(size.height);
+                }
+            })
+        }
+        (content)]
+        ]);
+
+        if self.data.origin.get().is_none() {
+            return result;
+        }
+
+        // Lay the band over the content with a negative margin, so it's drawn on top
+        result.add_child(ui!("band" [{
+            .key(self.data.band_key)
+            .on_style(move |_, style: &mut Style| {
+                if let Some(this) = weak5.upgrade() {
+                    let height = this.content_height.get();
+                    style.flex_shrink = 0.0;
+                    style.height = Some(height);
+                    style.margin_top = Some(-height);
+                }
+            })
+            .on_draw(false, move |_, ctx: &mut DrawCtx| {
+                let this = if let Some(this) = weak6.upgrade() { this } else { return };
+                if let Some(rect) = this.rect() {
+                    let rect = rect - this.band_offset.get();
+                    let color = ctx.style.color.clone();
+                    ctx.fill_rect(rect, &color.with_alpha(0.2));
+                    ctx.stroke_rect(rect, &color, 1.0);
+                }
+            })
+        }]))
+    }
+}
+
+impl Data {
+    fn rect(&self) -> Option<Rect> {
+        Some(Rect::from_points(self.origin.get()?, self.current.get()))
+    }
+
+    // Report the selection that the rectangle makes, if it changed
+    fn select<S, H>(&self, state: &mut S, ctx: &EventCtx<S, H>, on_select: &dyn Fn(&mut S, Vec<Key>) -> Phase) -> Phase {
+        let rect = match self.rect() {
+            Some(rect) => rect,
+            None => return Phase::Idle,
+        };
+
+        // Items are half-open, so a click on the edge between two items only selects one
+        let hits: Vec<Key> = self
+            .items
+            .borrow()
+            .iter()
+            .copied()
+            .filter(|&key| {
+                ctx.node_rect(key).map_or(false, |item| {
+                    item.x0 <= rect.x1 && rect.x0 < item.x1 && item.y0 <= rect.y1 && rect.y0 < item.y1
+                })
+            })
+            .collect();
+
+        let selection = self.mode.get().apply(&self.initial.borrow(), &hits);
+        if selection == *self.reported.borrow() {
+            return Phase::Idle;
+        }
+        self.reported.replace(selection.clone());
+        on_select(state, selection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callbacks::{PointerButton, RawPointerEvent};
+
+    struct State {
+        band: RubberBand,
+        items: Vec<Key>,
+        selection: RefCell<Vec<Key>>,
+    }
+
+    fn view(state: &State) -> View<State, ()> {
+        let mut content = View::default().inline_style("flex-direction: column");
+        for &item in &state.items {
+            content = content.add_child(View::default().key(item).inline_style("width: 100px; height: 20px"));
+        }
+        let selection = state.selection.borrow().clone();
+        View::default().add_child(state.band.view(content, &state.items, &selection, |s: &mut State, selection| {
+            s.selection.replace(selection);
+            Phase::Draw
+        }))
+    }
+
+    #[test]
+    fn selects_the_items_under_the_band() {
+        let mut state = State {
+            band: RubberBand::new(),
+            items: vec![Key::new(), Key::new(), Key::new()],
+            selection: RefCell::new(Vec::new()),
+        };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (200.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        let down = RawPointerEvent::at(50.0, 5.0).press(PointerButton::Left);
+        viewport.pointer_down(&mut state, down);
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(*state.selection.borrow(), state.items[..1]);
+
+        let drag = |x, y| RawPointerEvent {
+            window_pos_x: x,
+            window_pos_y: y,
+            ..down
+        };
+        viewport.pointer_move(&mut state, drag(50.0, 30.0));
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(*state.selection.borrow(), state.items[..2]);
+
+        // The drag goes on outside of the window
+        viewport.pointer_move(&mut state, drag(-10.0, 30.0));
+        viewport.draw(&state, None).unwrap();
+        assert!(state.band.is_dragging());
+        assert_eq!(*state.selection.borrow(), state.items[..2]);
+        viewport.pointer_move(&mut state, drag(-10.0, 45.0));
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(*state.selection.borrow(), state.items);
+
+        viewport.pointer_up(&mut state, drag(-10.0, 45.0).release(PointerButton::Left));
+        assert!(!state.band.is_dragging());
+    }
+
+    #[test]
+    fn items_can_keep_the_drag_from_starting() {
+        fn view(state: &State) -> View<State, ()> {
+            let mut content = View::default().inline_style("flex-direction: column");
+            for &item in &state.items {
+                let node = View::default()
+                    .key(item)
+                    .inline_style("width: 100px; height: 20px")
+                    .event(On::PointerDown, |_, ctx| {
+                        ctx.stop_propagation();
+                        Some(Phase::Idle)
+                    });
+                content = content.add_child(node);
+            }
+            View::default().add_child(state.band.view(content, &state.items, &[], |s: &mut State, selection| {
+                s.selection.replace(selection);
+                Phase::Draw
+            }))
+        }
+
+        let mut state = State {
+            band: RubberBand::new(),
+            items: vec![Key::new(), Key::new()],
+            selection: RefCell::new(Vec::new()),
+        };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (200.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        viewport.pointer_down(&mut state, RawPointerEvent::at(50.0, 5.0).press(PointerButton::Left));
+        assert!(!state.band.is_dragging());
+        assert!(state.band.rect().is_none());
+        assert!(state.selection.borrow().is_empty());
+
+        // Below the items, the drag starts as usual
+        viewport.pointer_down(&mut state, RawPointerEvent::at(50.0, 80.0).press(PointerButton::Left));
+        assert!(state.band.is_dragging());
+    }
+
+    #[test]
+    fn modes_combine_with_the_initial_selection() {
+        let (a, b, c) = (1, 2, 3);
+        assert_eq!(SelectMode::Replace.apply(&[a, b], &[c]), [c]);
+        assert_eq!(SelectMode::Extend.apply(&[a, b], &[b, c]), [a, b, c]);
+        assert_eq!(SelectMode::Toggle.apply(&[a, b], &[b, c]), [a, c]);
+    }
+}
@@ -1,15 +1,58 @@
 #![forbid(unsafe_code)]
 
-use std::{cell::Cell, fmt::Debug, rc::Rc};
+use std::{cell::Cell, cmp::Ordering, fmt::Debug, rc::Rc};
 
 use druid_shell::{
-    kurbo::{Circle, Line, Point},
+    kurbo::{Circle, Line, Point, Vec2},
     piet::{Color, RenderContext},
+    KeyState,
 };
 
 use crate::prelude::*;
 
+// Each multiple of the step up to 1.0, followed by 1.0 if it isn't one
+fn stops(step: f64) -> Vec<f64> {
+    // Allow for rounding, so a step like 0.1 still reaches 1.0
+    let count = (1.0 / step + 1e-9).floor() as usize;
+    let mut stops: Vec<f64> = (0..=count).map(|i| (i as f64 * step).min(1.0)).collect();
+    if stops.last().map_or(true, |last| 1.0 - last > 1e-9) {
+        stops.push(1.0);
+    }
+    stops
+}
+
+// The nearest stop to `value`
+fn snapped(step: f64, value: f64) -> f64 {
+    let value = value.clamp(0.0, 1.0);
+    stops(step)
+        .into_iter()
+        .min_by(|a, b| (a - value).abs().partial_cmp(&(b - value).abs()).unwrap_or(Ordering::Equal))
+        .unwrap_or(value)
+}
+
+// The point `t` of the way along the middle of a track that fills the node
+fn track_point(horizontal: bool, width: f64, height: f64, t: f64) -> Point {
+    if horizontal {
+        Point::new(t * width, height / 2.0)
+    } else {
+        Point::new(width / 2.0, t * height)
+    }
+}
+
+// A tick mark across the track at each stop
+fn draw_ticks(ctx: &mut DrawCtx, step: f64, horizontal: bool) {
+    let across = if horizontal { Vec2::new(0.0, 8.0) } else { Vec2::new(8.0, 0.0) };
+    for stop in stops(step) {
+        let point = track_point(horizontal, ctx.width, ctx.height, stop);
+        ctx.piet.stroke(Line::new(point - across, point + across), &Color::BLACK, 1.0);
+    }
+}
+
 // ---------- Slider ----------
+/// A slider for a value from 0.0 to 1.0. Changes are reported with `On::Change`, and the arrow keys move it by one step.
+///
+/// With a step, tick marks can be drawn at each stop, and snapping keeps the value on a stop.
+/// While snapping, the thumb follows the pointer and then slides to the nearest stop when it's released.
 #[derive(Debug)]
 pub struct Slider {
     pub key: Key,
@@ -19,7 +62,13 @@ pub struct Slider {
 #[derive(Debug)]
 struct Data {
     value: Cell<f64>,
+    // Where the thumb is drawn, which trails the value while snapping
+    thumb: Cell<f64>,
     horizontal: Cell<bool>,
+    step: Cell<f64>,
+    ticks: Cell<bool>,
+    snap: Cell<bool>,
+    animating: Cell<bool>,
     changed: Cell<bool>,
 }
 
@@ -29,14 +78,45 @@ impl Slider {
             key: Key::new(),
             data: Rc::new(Data {
                 value: Cell::new(value),
+                thumb: Cell::new(value),
                 horizontal: Cell::new(horizontal),
+                step: Cell::new(0.01),
+                ticks: Cell::new(false),
+                snap: Cell::new(false),
+                animating: Cell::new(false),
                 changed: Cell::new(false),
             }),
         }
     }
 
+    /// Set the distance between stops, which is how far the arrow keys move the value.
+    /// The stops are at each multiple of `step` up to 1.0, and at 1.0 itself.
+    ///
+    /// Panics if `step` isn't greater than 0.0 and at most 1.0.
+    pub fn with_step(self, step: f64) -> Self {
+        assert!(
+            step > 0.0 && step <= 1.0,
+            "[Rosin] Slider step must be greater than 0.0 and at most 1.0"
+        );
+        self.data.step.set(step);
+        self
+    }
+
+    /// Draw a tick mark at each stop.
+    pub fn with_ticks(self, ticks: bool) -> Self {
+        self.data.ticks.set(ticks);
+        self
+    }
+
+    /// Keep the value on the nearest stop.
+    pub fn with_snap(self, snap: bool) -> Self {
+        self.data.snap.set(snap);
+        self
+    }
+
     pub fn set(&self, new_value: f64) -> Phase {
         self.data.value.replace(new_value);
+        self.data.thumb.replace(new_value);
         self.data.changed.replace(true);
         Phase::Draw
     }
@@ -46,23 +126,29 @@ impl Slider {
     }
 
     pub fn view<S, H>(&self) -> View<S, H> {
+        let key = self.key;
         let weak1 = Rc::downgrade(&self.data);
         let weak2 = Rc::downgrade(&self.data);
         let weak3 = Rc::downgrade(&self.data);
+        let weak4 = Rc::downgrade(&self.data);
+        let weak5 = Rc::downgrade(&self.data);
 
         ui!([
-            .key(self.key)
+            .key(key)
             .event(On::PointerDown, move |_, ctx: &mut EventCtx<S, H>| {
                 let this = if let Some(this) = weak1.upgrade() { this } else { return Some(Phase::Idle) };
                 let info = ctx.pointer()?;
 
                 if info.button.is_left() {
                     if this.horizontal.get() {
-                        this.value.set(info.pos_x / ctx.width());
+                        this.drag_to(info.pos_x / ctx.width());
                     } else {
-                        this.value.set(info.pos_y / ctx.height());
+                        this.drag_to(info.pos_y / ctx.height());
                     }
 
+                    ctx.focus_on(key);
+                    // Keep dragging when the pointer moves past the end of the track
+                    ctx.capture_pointer(key);
                     ctx.emit_change();
                     Some(Phase::Draw)
                 } else {
@@ -75,9 +161,9 @@ impl Slider {
 
                 if info.buttons.has_left() {
                     if this.horizontal.get() {
-                        this.value.set(info.pos_x / ctx.width());
+                        this.drag_to(info.pos_x / ctx.width());
                     } else {
-                        this.value.set(info.pos_y / ctx.height());
+                        this.drag_to(info.pos_y / ctx.height());
                     }
 
                     ctx.emit_change();
@@ -86,25 +172,578 @@ impl Slider {
                     Some(Phase::Idle)
                 }
             })
+            .event(On::PointerUp, move |_, ctx: &mut EventCtx<S, H>| {
+                let this = if let Some(this) = weak3.upgrade() { this } else { return Some(Phase::Idle) };
+                this.settle(ctx);
+                ctx.release_pointer();
+                Some(Phase::Idle)
+            })
+            .event(On::Keyboard, move |_, ctx: &mut EventCtx<S, H>| {
+                let this = if let Some(this) = weak4.upgrade() { this } else { return Some(Phase::Idle) };
+                let event = ctx.keyboard()?;
+                if event.state != KeyState::Down {
+                    return Some(Phase::Idle);
+                }
+
+                let step = this.step.get();
+                let delta = match event.key {
+                    KbKey::ArrowLeft | KbKey::ArrowDown => -step,
+                    KbKey::ArrowRight | KbKey::ArrowUp => step,
+                    _ => return Some(Phase::Idle),
+                };
+                let value = (this.value.get() + delta).clamp(0.0, 1.0);
+                let value = if this.snap.get() { this.snapped(value) } else { value };
+                this.value.set(value);
+                this.thumb.set(value);
+                this.changed.set(true);
+
+                ctx.emit_change();
+                Some(Phase::Draw)
+            })
             .on_draw(true, move |_, ctx: &mut DrawCtx| {
                 // If the underlying data is gone, then just return since there's nothing to draw.
                 // TODO: Maybe log something?
                 //       Could also draw the cache, if available.
-                let this = if let Some(this) = weak3.upgrade() { this } else { return };
+                let this = if let Some(this) = weak5.upgrade() { this } else { return };
                 if !this.changed.get() && !ctx.must_draw { return }
                 this.changed.set(false);
 
-                let track = Line::new(
-                    Point { x: 0.0, y: ctx.height/2.0},
-                    Point { x: ctx.width as f64, y: ctx.height/2.0},
-                );
+                let (width, height) = (ctx.width, ctx.height);
+                let horizontal = this.horizontal.get();
+                let point = |t: f64| track_point(horizontal, width, height, t);
 
-                ctx.piet.stroke(track, &Color::BLACK, 5.0);
+                ctx.piet.stroke(Line::new(point(0.0), point(1.0)), &Color::BLACK, 5.0);
 
-                let control = Circle::new(Point { x: this.value.get() * ctx.width, y: ctx.height/2.0 }, 10.0);
+                if this.ticks.get() {
+                    draw_ticks(ctx, this.step.get(), horizontal);
+                }
 
-                ctx.piet.fill(control, &Color::BLACK);
+                ctx.piet.fill(Circle::new(point(this.thumb.get()), 10.0), &Color::BLACK);
             })
         ])
     }
 }
+
+impl Data {
+    fn snapped(&self, value: f64) -> f64 {
+        snapped(self.step.get(), value)
+    }
+
+    // Move the thumb to the pointer, and keep the value on a stop if snapping
+    fn drag_to(&self, pos: f64) {
+        let pos = pos.clamp(0.0, 1.0);
+        self.thumb.set(pos);
+        self.value.set(if self.snap.get() { self.snapped(pos) } else { pos });
+        self.changed.set(true);
+    }
+
+    // Slide the thumb to the snapped value after a drag
+    fn settle<S, H>(self: &Rc<Self>, ctx: &mut EventCtx<S, H>) {
+        if self.thumb.get() == self.value.get() || self.animating.replace(true) {
+            return;
+        }
+
+        let weak = Rc::downgrade(self);
+        ctx.start_animation(move |_, dt| {
+            let this = if let Some(this) = weak.upgrade() {
+                this
+            } else {
+                return (Phase::Idle, ShouldStop::Yes);
+            };
+
+            // Cover a full step in a tenth of a second
+            let distance = this.step.get() * dt.as_secs_f64() * 10.0;
+            let (thumb, value) = (this.thumb.get(), this.value.get());
+            let thumb = if value > thumb {
+                (thumb + distance).min(value)
+            } else {
+                (thumb - distance).max(value)
+            };
+            this.thumb.set(thumb);
+            this.changed.set(true);
+
+            if thumb == value {
+                this.animating.set(false);
+                (Phase::Draw, ShouldStop::Yes)
+            } else {
+                (Phase::Draw, ShouldStop::No)
+            }
+        });
+    }
+}
+
+// ---------- Range Slider ----------
+/// A slider with two thumbs that select a range. Changes are reported with `On::Change`.
+/// Clicking moves the nearest thumb, and the thumbs can't cross each other.
+/// The thumb that was used last can be adjusted with the arrow keys, which move it by one step.
+/// With a step, tick marks can be drawn at each stop, and snapping keeps the thumbs on the stops while they're dragged.
+#[derive(Debug)]
+pub struct RangeSlider {
+    pub key: Key,
+    data: Rc<RangeData>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Thumb {
+    Low,
+    High,
+}
+
+#[derive(Debug)]
+struct RangeData {
+    low: Cell<f64>,
+    high: Cell<f64>,
+    horizontal: Cell<bool>,
+    step: Cell<f64>,
+    ticks: Cell<bool>,
+    snap: Cell<bool>,
+    // The thumb that was used last
+    active: Cell<Thumb>,
+    dragging: Cell<bool>,
+    changed: Cell<bool>,
+}
+
+impl RangeSlider {
+    pub fn new(low: f64, high: f64, horizontal: bool) -> Self {
+        let low = low.clamp(0.0, 1.0);
+        Self {
+            key: Key::new(),
+            data: Rc::new(RangeData {
+                low: Cell::new(low),
+                high: Cell::new(high.clamp(low, 1.0)),
+                horizontal: Cell::new(horizontal),
+                step: Cell::new(0.01),
+                ticks: Cell::new(false),
+                snap: Cell::new(false),
+                active: Cell::new(Thumb::Low),
+                dragging: Cell::new(false),
+                changed: Cell::new(false),
+            }),
+        }
+    }
+
+    /// Set the distance between stops, which is how far the arrow keys move a thumb.
+    ///
+    /// Panics if `step` isn't greater than 0.0 and at most 1.0.
+    pub fn with_step(self, step: f64) -> Self {
+        assert!(
+            step > 0.0 && step <= 1.0,
+            "[Rosin] Slider step must be greater than 0.0 and at most 1.0"
+        );
+        self.data.step.set(step);
+        self
+    }
+
+    /// Draw a tick mark at each stop.
+    pub fn with_ticks(self, ticks: bool) -> Self {
+        self.data.ticks.set(ticks);
+        self
+    }
+
+    /// Keep both thumbs on the nearest stop.
+    pub fn with_snap(self, snap: bool) -> Self {
+        self.data.snap.set(snap);
+        self
+    }
+
+    pub fn set(&self, new_low: f64, new_high: f64) -> Phase {
+        let new_low = new_low.clamp(0.0, 1.0);
+        self.data.low.replace(new_low);
+        self.data.high.replace(new_high.clamp(new_low, 1.0));
+        self.data.changed.replace(true);
+        Phase::Draw
+    }
+
+    pub fn get(&self) -> (f64, f64) {
+        (self.data.low.get(), self.data.high.get())
+    }
+
+    pub fn view<S, H>(&self) -> View<S, H> {
+        let key = self.key;
+        let weak1 = Rc::downgrade(&self.data);
+        let weak2 = Rc::downgrade(&self.data);
+        let weak3 = Rc::downgrade(&self.data);
+        let weak4 = Rc::downgrade(&self.data);
+        let weak5 = Rc::downgrade(&self.data);
+
+        ui!([
+            .key(key)
+            .event(On::PointerDown, move |_, ctx: &mut EventCtx<S, H>| {
+                let this = if let Some(this) = weak1.upgrade() { this } else { return Some(Phase::Idle) };
+                let info = ctx.pointer()?;
+
+                if info.button.is_left() {
+                    let pos = this.position(info.pos_x, info.pos_y, ctx.width(), ctx.height());
+                    this.active.set(this.nearest(pos));
+                    this.dragging.set(true);
+                    this.move_active(pos);
+
+                    ctx.focus_on(key);
+                    // Keep dragging when the pointer moves past the end of the track
+                    ctx.capture_pointer(key);
+                    ctx.emit_change();
+                    Some(Phase::Draw)
+                } else {
+                    Some(Phase::Idle)
+                }
+            })
+            .event(On::PointerMove, move |_, ctx: &mut EventCtx<S, H>| {
+                let this = if let Some(this) = weak2.upgrade() { this } else { return Some(Phase::Idle) };
+                let info = ctx.pointer()?;
+
+                if this.dragging.get() && info.buttons.has_left() {
+                    let pos = this.position(info.pos_x, info.pos_y, ctx.width(), ctx.height());
+                    this.move_active(pos);
+
+                    ctx.emit_change();
+                    Some(Phase::Draw)
+                } else {
+                    this.dragging.set(false);
+                    Some(Phase::Idle)
+                }
+            })
+            .event(On::PointerUp, move |_, ctx: &mut EventCtx<S, H>| {
+                let this = if let Some(this) = weak3.upgrade() { this } else { return Some(Phase::Idle) };
+                this.dragging.set(false);
+                ctx.release_pointer();
+                Some(Phase::Idle)
+            })
+            .event(On::Keyboard, move |_, ctx: &mut EventCtx<S, H>| {
+                let this = if let Some(this) = weak4.upgrade() { this } else { return Some(Phase::Idle) };
+                let event = ctx.keyboard()?;
+                if event.state != KeyState::Down {
+                    return Some(Phase::Idle);
+                }
+
+                let step = this.step.get();
+                let delta = match event.key {
+                    KbKey::ArrowLeft | KbKey::ArrowDown => -step,
+                    KbKey::ArrowRight | KbKey::ArrowUp => step,
+                    _ => return Some(Phase::Idle),
+                };
+                let current = match this.active.get() {
+                    Thumb::Low => this.low.get(),
+                    Thumb::High => this.high.get(),
+                };
+                this.move_active(current + delta);
+
+                ctx.emit_change();
+                Some(Phase::Draw)
+            })
+            .on_draw(true, move |_, ctx: &mut DrawCtx| {
+                // If the underlying data is gone, then just return since there's nothing to draw.
+                let this = if let Some(this) = weak5.upgrade() { this } else { return };
+                if !this.changed.get() && !ctx.must_draw { return }
+                this.changed.set(false);
+
+                let (width, height) = (ctx.width, ctx.height);
+                let horizontal = this.horizontal.get();
+                let point = |t: f64| track_point(horizontal, width, height, t);
+                let (low, high) = (point(this.low.get()), point(this.high.get()));
+
+                ctx.piet.stroke(Line::new(point(0.0), point(1.0)), &Color::grey(0.75), 5.0);
+                if this.ticks.get() {
+                    draw_ticks(ctx, this.step.get(), horizontal);
+                }
+                ctx.piet.stroke(Line::new(low, high), &Color::BLACK, 5.0);
+                ctx.piet.fill(Circle::new(low, 10.0), &Color::BLACK);
+                ctx.piet.fill(Circle::new(high, 10.0), &Color::BLACK);
+            })
+        ])
+    }
+}
+
+impl RangeData {
+    // The pointer position along the track, from 0.0 to 1.0
+    fn position(&self, x: f64, y: f64, width: f64, height: f64) -> f64 {
+        let pos = if self.horizontal.get() { x / width } else { y / height };
+        pos.clamp(0.0, 1.0)
+    }
+
+    // The thumb closest to `pos`
+    fn nearest(&self, pos: f64) -> Thumb {
+        if (pos - self.low.get()).abs() <= (self.high.get() - pos).abs() {
+            Thumb::Low
+        } else {
+            Thumb::High
+        }
+    }
+
+    // Move the active thumb, without letting it cross the other one
+    fn move_active(&self, pos: f64) {
+        let pos = if self.snap.get() { snapped(self.step.get(), pos) } else { pos };
+        let (low, high) = (self.low.get(), self.high.get());
+
+        // When the thumbs overlap, move whichever one is on the side of the pointer
+        if low == high {
+            if pos < low {
+                self.active.set(Thumb::Low);
+            } else if pos > high {
+                self.active.set(Thumb::High);
+            }
+        }
+
+        match self.active.get() {
+            Thumb::Low => self.low.set(pos.clamp(0.0, high)),
+            Thumb::High => self.high.set(pos.clamp(low, 1.0)),
+        }
+        self.changed.set(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callbacks::{PointerButton, RawPointerEvent};
+    use druid_shell::piet::{Device, ImageFormat};
+    use druid_shell::KeyEvent;
+
+    // The sliders share a fixture, which shows one in a view
+    trait Widget: Sized + 'static {
+        fn view(&self) -> View<State<Self>, ()>;
+    }
+
+    impl Widget for Slider {
+        fn view(&self) -> View<State<Self>, ()> {
+            Slider::view(self)
+        }
+    }
+
+    impl Widget for RangeSlider {
+        fn view(&self) -> View<State<Self>, ()> {
+            RangeSlider::view(self)
+        }
+    }
+
+    struct State<W> {
+        slider: W,
+        changes: u32,
+    }
+
+    fn view<W: Widget>(state: &State<W>) -> View<State<W>, ()> {
+        View::default()
+            .event(On::Change, |s: &mut State<W>, _| {
+                s.changes += 1;
+                Some(Phase::Idle)
+            })
+            .add_child(state.slider.view())
+    }
+
+    // Fill a 100x100 viewport with `slider`, and count its changes
+    fn setup<W: Widget>(slider: W) -> (State<W>, Viewport<State<W>, ()>) {
+        let state = State { slider, changes: 0 };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view::<W>, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+        (state, viewport)
+    }
+
+    fn key_down(key: KbKey) -> KeyEvent {
+        KeyEvent {
+            state: KeyState::Down,
+            key,
+            ..Default::default()
+        }
+    }
+
+    fn render<W: Widget>(state: &State<W>, viewport: &mut Viewport<State<W>, ()>) -> Vec<u8> {
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(100, 100, 1.0).unwrap();
+        let mut piet = target.render_context();
+        viewport.draw(state, Some(&mut piet)).unwrap();
+        piet.finish().unwrap();
+        drop(piet);
+        target.to_image_buf(ImageFormat::RgbaPremul).unwrap().raw_pixels().to_vec()
+    }
+
+    fn pixel(pixels: &[u8], x: usize, y: usize) -> &[u8] {
+        let i = (y * 100 + x) * 4;
+        &pixels[i..i + 4]
+    }
+
+    fn assert_stops(step: f64, expected: &[f64]) {
+        let stops = stops(step);
+        assert_eq!(stops.len(), expected.len(), "{:?}", stops);
+        for (stop, expected) in stops.iter().zip(expected) {
+            assert!((stop - expected).abs() < 1e-9, "{:?}", stops);
+        }
+    }
+
+    #[test]
+    fn stops_end_at_one() {
+        assert_stops(0.25, &[0.0, 0.25, 0.5, 0.75, 1.0]);
+        assert_stops(0.1, &[0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0]);
+        assert_stops(0.3, &[0.0, 0.3, 0.6, 0.9, 1.0]);
+        assert_stops(0.6, &[0.0, 0.6, 1.0]);
+        assert_stops(1.0, &[0.0, 1.0]);
+    }
+
+    fn assert_snaps(slider: &Slider, value: f64, expected: f64) {
+        let snapped = slider.data.snapped(value);
+        assert!((snapped - expected).abs() < 1e-9, "{} snapped to {}", value, snapped);
+    }
+
+    #[test]
+    fn snaps_to_ticks() {
+        let slider = Slider::new(0.0, true).with_step(0.3);
+        assert_snaps(&slider, 0.94, 0.9);
+        assert_snaps(&slider, 0.96, 1.0);
+        assert_snaps(&slider, 1.5, 1.0);
+
+        let slider = Slider::new(0.0, true).with_step(0.75);
+        assert_snaps(&slider, 0.8, 0.75);
+        assert_snaps(&slider, 0.9, 1.0);
+        assert_snaps(&slider, 0.3, 0.0);
+    }
+
+    #[test]
+    fn dragging_snaps_to_the_nearest_tick() {
+        let (mut state, mut viewport) = setup(Slider::new(0.0, true).with_step(0.25).with_ticks(true).with_snap(true));
+
+        let down = RawPointerEvent::at(30.0, 50.0).press(PointerButton::Left);
+        viewport.pointer_down(&mut state, down);
+        assert_eq!(state.slider.get(), 0.25);
+
+        // Between the ticks at 0.25 and 0.5, but closer to 0.5
+        let between = RawPointerEvent {
+            window_pos_x: 40.0,
+            ..down
+        };
+        viewport.pointer_move(&mut state, between);
+        assert_eq!(state.slider.get(), 0.5);
+
+        // The pointer is captured, so the drag continues outside of the slider
+        let outside = RawPointerEvent {
+            window_pos_x: -20.0,
+            ..down
+        };
+        viewport.pointer_move(&mut state, outside);
+        assert_eq!(state.slider.get(), 0.0);
+        viewport.pointer_up(&mut state, outside.release(PointerButton::Left));
+        assert_eq!(state.changes, 3);
+    }
+
+    #[test]
+    fn arrow_keys_move_by_one_step() {
+        let (mut state, mut viewport) = setup(Slider::new(0.1, true).with_step(0.25));
+
+        // Focus the slider
+        let down = RawPointerEvent::at(10.0, 50.0).press(PointerButton::Left);
+        viewport.pointer_down(&mut state, down);
+        viewport.pointer_up(&mut state, down.release(PointerButton::Left));
+
+        viewport.key_event(&mut state, key_down(KbKey::ArrowRight));
+        assert!((state.slider.get() - 0.35).abs() < 1e-9);
+        viewport.key_event(&mut state, key_down(KbKey::ArrowUp));
+        assert!((state.slider.get() - 0.6).abs() < 1e-9);
+        viewport.key_event(&mut state, key_down(KbKey::ArrowLeft));
+        assert!((state.slider.get() - 0.35).abs() < 1e-9);
+
+        // The value stops at the ends of the track
+        for _ in 0..4 {
+            viewport.key_event(&mut state, key_down(KbKey::ArrowDown));
+        }
+        assert_eq!(state.slider.get(), 0.0);
+    }
+
+    #[test]
+    fn vertical_sliders_are_drawn_down_the_middle() {
+        let (state, mut viewport) = setup(Slider::new(0.25, false).with_step(0.5).with_ticks(true));
+        let pixels = render(&state, &mut viewport);
+
+        // The thumb is a quarter of the way down, and the ticks cross the track sideways
+        assert_eq!(pixel(&pixels, 50, 25)[3], 255);
+        assert_eq!(pixel(&pixels, 25, 50)[3], 0);
+        assert!(pixel(&pixels, 56, 50)[3] > 0);
+        assert_eq!(pixel(&pixels, 56, 40)[3], 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_zero_step() {
+        let _ = Slider::new(0.0, true).with_step(0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_nan_step() {
+        let _ = Slider::new(0.0, true).with_step(f64::NAN);
+    }
+
+    fn assert_range(slider: &RangeSlider, low: f64, high: f64) {
+        let (l, h) = slider.get();
+        assert!((l - low).abs() < 1e-9 && (h - high).abs() < 1e-9, "got {:?}", (l, h));
+    }
+
+    #[test]
+    fn range_thumbs_cannot_cross() {
+        let (mut state, mut viewport) = setup(RangeSlider::new(0.3, 0.7, true));
+
+        // Clicking moves the nearest thumb, and dragging it stops at the other one
+        let down = RawPointerEvent::at(20.0, 50.0).press(PointerButton::Left);
+        viewport.pointer_down(&mut state, down);
+        assert_range(&state.slider, 0.2, 0.7);
+        let past = RawPointerEvent {
+            window_pos_x: 90.0,
+            ..down
+        };
+        viewport.pointer_move(&mut state, past);
+        assert_range(&state.slider, 0.7, 0.7);
+        viewport.pointer_up(&mut state, past.release(PointerButton::Left));
+
+        // Once they overlap, the arrow keys move whichever thumb is on their side
+        viewport.key_event(&mut state, key_down(KbKey::ArrowRight));
+        assert_range(&state.slider, 0.7, 0.71);
+        viewport.key_event(&mut state, key_down(KbKey::ArrowLeft));
+        assert_range(&state.slider, 0.7, 0.7);
+        viewport.key_event(&mut state, key_down(KbKey::ArrowLeft));
+        assert_range(&state.slider, 0.69, 0.7);
+        assert_eq!(state.changes, 5);
+    }
+
+    #[test]
+    fn range_thumbs_snap_and_keep_dragging_past_the_track() {
+        let (mut state, mut viewport) = setup(RangeSlider::new(0.5, 0.75, true).with_step(0.25).with_snap(true));
+
+        // Clicking snaps the nearest thumb to a stop
+        let down = RawPointerEvent::at(30.0, 50.0).press(PointerButton::Left);
+        viewport.pointer_down(&mut state, down);
+        assert_range(&state.slider, 0.25, 0.75);
+
+        // The pointer is captured, so the drag continues outside of the slider
+        let outside = RawPointerEvent {
+            window_pos_x: -30.0,
+            ..down
+        };
+        viewport.pointer_move(&mut state, outside);
+        assert_range(&state.slider, 0.0, 0.75);
+        viewport.pointer_up(&mut state, outside.release(PointerButton::Left));
+
+        // The arrow keys move the active thumb by one step
+        viewport.key_event(&mut state, key_down(KbKey::ArrowRight));
+        viewport.key_event(&mut state, key_down(KbKey::ArrowRight));
+        assert_range(&state.slider, 0.5, 0.75);
+    }
+
+    #[test]
+    fn vertical_range_sliders_are_drawn_down_the_middle() {
+        let (state, mut viewport) = setup(RangeSlider::new(0.25, 0.75, false).with_step(0.5).with_ticks(true));
+        let pixels = render(&state, &mut viewport);
+
+        // The thumbs are a quarter of the way from each end, and the ticks cross the track sideways
+        assert_eq!(pixel(&pixels, 50, 25)[3], 255);
+        assert_eq!(pixel(&pixels, 50, 75)[3], 255);
+        assert_eq!(pixel(&pixels, 25, 50)[3], 0);
+        assert!(pixel(&pixels, 56, 50)[3] > 0);
+        assert_eq!(pixel(&pixels, 56, 40)[3], 0);
+    }
+
+    #[test]
+    fn range_set_keeps_the_thumbs_in_order() {
+        let slider = RangeSlider::new(0.8, 0.2, true);
+        assert_range(&slider, 0.8, 0.8);
+        assert_eq!(slider.set(-1.0, 0.5), Phase::Draw);
+        assert_range(&slider, 0.0, 0.5);
+        assert_eq!(slider.set(0.6, 2.0), Phase::Draw);
+        assert_range(&slider, 0.6, 1.0);
+    }
+}
@@ -0,0 +1,181 @@
+#![forbid(unsafe_code)]
+
+use std::{
+    cell::Cell,
+    f64::consts::{PI, TAU},
+    fmt::Debug,
+    rc::Rc,
+};
+
+use druid_shell::{
+    kurbo::{Arc, Point, Vec2},
+    piet::RenderContext,
+};
+
+use crate::prelude::*;
+
+// Turns per second
+const SPEED: f64 = 1.2;
+
+// ---------- Spinner ----------
+/// A spinning arc, for work that doesn't report its progress. The arc is drawn with the `color` property, and fits the node's smaller side.
+///
+/// The spinner animates between `start()` and `stop()`. It also stops by itself when it's left out of the tree,
+/// since it only keeps animating while it's being drawn.
+#[derive(Debug)]
+pub struct Spinner {
+    pub key: Key,
+    data: Rc<Data>,
+}
+
+#[derive(Debug)]
+struct Data {
+    // The rotation, in turns
+    angle: Cell<f64>,
+    spinning: Cell<bool>,
+    animating: Cell<bool>,
+    // Whether the spinner was drawn since the last animation frame
+    drawn: Cell<bool>,
+}
+
+impl Default for Spinner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Spinner {
+    pub fn new() -> Self {
+        Self {
+            key: Key::new(),
+            data: Rc::new(Data {
+                angle: Cell::new(0.0),
+                spinning: Cell::new(false),
+                animating: Cell::new(false),
+                drawn: Cell::new(true),
+            }),
+        }
+    }
+
+    pub fn is_spinning(&self) -> bool {
+        self.data.spinning.get()
+    }
+
+    pub fn start<S, H>(&self, ctx: &mut EventCtx<S, H>) -> Phase {
+        self.data.spinning.set(true);
+        self.data.drawn.set(true);
+        if self.data.animating.replace(true) {
+            return Phase::Draw;
+        }
+
+        let weak = Rc::downgrade(&self.data);
+        ctx.start_animation(move |_, dt| {
+            let this = if let Some(this) = weak.upgrade() {
+                this
+            } else {
+                return (Phase::Idle, ShouldStop::Yes);
+            };
+
+            if !this.spinning.get() || !this.drawn.replace(false) {
+                this.spinning.set(false);
+                this.animating.set(false);
+                return (Phase::Draw, ShouldStop::Yes);
+            }
+
+            this.angle.set((this.angle.get() + dt.as_secs_f64() * SPEED).fract());
+            (Phase::Draw, ShouldStop::No)
+        });
+
+        Phase::Draw
+    }
+
+    pub fn stop(&self) -> Phase {
+        self.data.spinning.set(false);
+        Phase::Draw
+    }
+
+    pub fn view<S, H>(&self) -> View<S, H> {
+        let weak = Rc::downgrade(&self.data);
+
+        ui!([
+            .key(self.key)
+            .on_draw(false, move |_, ctx: &mut DrawCtx| {
+                // If the underlying data is gone, then just return since there's nothing to draw.
+                let this = if let Some(this) = weak.upgrade() { this } else { return };
+                this.drawn.set(true);
+                if !this.spinning.get() { return }
+
+                let color = ctx.style.color.clone();
+                let size = ctx.width.min(ctx.height);
+                let thickness = (size / 8.0).max(1.0);
+                let radius = (size - thickness) / 2.0;
+                if radius <= 0.0 { return }
+
+                let arc = Arc {
+                    center: Point::new(ctx.width / 2.0, ctx.height / 2.0),
+                    radii: Vec2::new(radius, radius),
+                    start_angle: this.angle.get() * TAU,
+                    sweep_angle: PI * 1.5,
+                    x_rotation: 0.0,
+                };
+                ctx.piet.stroke(arc, &color, thickness);
+            })
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::callbacks::{PointerButton, RawPointerEvent};
+
+    struct State {
+        spinner: Spinner,
+    }
+
+    fn view(state: &State) -> View<State, ()> {
+        View::default()
+            .event(On::PointerDown, |s: &mut State, ctx| Some(s.spinner.start(ctx)))
+            .add_child(state.spinner.view())
+    }
+
+    fn click(viewport: &mut Viewport<State, ()>, state: &mut State) {
+        viewport.pointer_down(state, RawPointerEvent::at(5.0, 5.0).press(PointerButton::Left));
+    }
+
+    #[test]
+    fn stops_when_it_is_no_longer_drawn() {
+        let mut state = State { spinner: Spinner::new() };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        click(&mut viewport, &mut state);
+        assert!(state.spinner.is_spinning());
+        viewport.animation_frame(&mut state, Duration::from_millis(500));
+        assert!((state.spinner.data.angle.get() - 0.6).abs() < 1e-9);
+
+        // Without a draw in between, it acts like a spinner that was left out of the tree
+        viewport.animation_frame(&mut state, Duration::from_millis(500));
+        assert!(!state.spinner.is_spinning());
+        assert!(!viewport.has_anim_tasks());
+    }
+
+    #[test]
+    fn starting_twice_keeps_one_animation() {
+        let mut state = State { spinner: Spinner::new() };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        click(&mut viewport, &mut state);
+        click(&mut viewport, &mut state);
+        viewport.animation_frame(&mut state, Duration::from_millis(250));
+        assert!((state.spinner.data.angle.get() - 0.3).abs() < 1e-9);
+
+        assert_eq!(state.spinner.stop(), Phase::Draw);
+        viewport.animation_frame(&mut state, Duration::from_millis(250));
+        assert!(!viewport.has_anim_tasks());
+        assert!((state.spinner.data.angle.get() - 0.3).abs() < 1e-9);
+    }
+}
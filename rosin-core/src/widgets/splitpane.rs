@@ -0,0 +1,286 @@
+#![forbid(unsafe_code)]
+
+use std::{
+    cell::Cell,
+    fmt::Debug,
+    rc::{Rc, Weak},
+};
+
+use crate::prelude::*;
+use crate::style::{Cursor, FlexDirection};
+
+// ---------- Split Pane ----------
+/// Two panes side by side, or stacked if `vertical` is true, with a divider that can be dragged to resize them.
+/// The panes have the class `pane` and the divider has the class `divider`. Split panes can be nested.
+#[derive(Debug)]
+pub struct SplitPane {
+    pub key: Key,
+    data: Rc<Data>,
+}
+
+#[derive(Debug)]
+struct Data {
+    // The fraction of the available space given to the first pane
+    ratio: Cell<f64>,
+    vertical: bool,
+    divider_size: Cell<f32>,
+    min_size: Cell<f32>,
+    collapsible: Cell<bool>,
+    // The size of the whole widget after the last layout
+    size: Cell<(f32, f32)>,
+    dragging: Cell<bool>,
+}
+
+impl SplitPane {
+    pub fn new(ratio: f64, vertical: bool) -> Self {
+        Self {
+            key: Key::new(),
+            data: Rc::new(Data {
+                ratio: Cell::new(ratio.clamp(0.0, 1.0)),
+                vertical,
+                divider_size: Cell::new(6.0),
+                min_size: Cell::new(0.0),
+                collapsible: Cell::new(false),
+                size: Cell::new((0.0, 0.0)),
+                dragging: Cell::new(false),
+            }),
+        }
+    }
+
+    /// Set the thickness of the divider.
+    pub fn with_divider_size(self, divider_size: f32) -> Self {
+        self.data.divider_size.set(divider_size);
+        self
+    }
+
+    /// Don't let dragging make a pane smaller than `min_size`.
+    pub fn with_min_size(self, min_size: f32) -> Self {
+        self.data.min_size.set(min_size);
+        self
+    }
+
+    /// Let a pane collapse completely when the divider is dragged most of the way past its minimum size.
+    pub fn with_collapse(self, collapsible: bool) -> Self {
+        self.data.collapsible.set(collapsible);
+        self
+    }
+
+    pub fn get(&self) -> f64 {
+        self.data.ratio.get()
+    }
+
+    pub fn set(&self, new_ratio: f64) -> Phase {
+        self.data.ratio.set(new_ratio.clamp(0.0, 1.0));
+        Phase::Style
+    }
+
+    pub fn view<S, H>(&self, first: View<S, H>, second: View<S, H>) -> View<S, H> {
+        let weak1 = Rc::downgrade(&self.data);
+        let weak2 = Rc::downgrade(&self.data);
+        let weak3 = Rc::downgrade(&self.data);
+        let weak4 = Rc::downgrade(&self.data);
+        let weak5 = Rc::downgrade(&self.data);
+        let weak6 = Rc::downgrade(&self.data);
+        let weak7 = Rc::downgrade(&self.data);
+        let vertical = self.data.vertical;
+        let key = self.key;
+
+        ui!([{
+            .key(self.key)
+            .on_style(move |_, style: &mut Style| {
+                style.flex_direction = if vertical { FlexDirection::Column } else { FlexDirection::Row };
+            })
+            .on_layout(move |_, size| {
+                if let Some(this) = weak1.upgrade() {
+                    this.size.set((size.width, size.height));
+                }
+            })
+            .event(On::PointerMove, move |_, ctx: &mut EventCtx<S, H>| {
+                let this = weak2.upgrade()?;
+                let info = ctx.pointer()?;
+
+                if !this.dragging.get() {
+                    return Some(Phase::Idle);
+                }
+                if !info.buttons.has_left() {
+                    this.dragging.set(false);
+                    return Some(Phase::Idle);
+                }
+
+                let pos = if vertical { info.pos_y } else { info.pos_x };
+                this.drag_to(pos as f32);
+                ctx.emit_change();
+                Some(Phase::Style)
+            })
+            .event(On::PointerUp, move |_, _| {
+                weak3.upgrade()?.dragging.set(false);
+                Some(Phase::Idle)
+            })
+        }
+        "pane" [{ .on_style(pane_style(weak4, true)) } (first)]
+        "divider" [{
+            .on_style(move |_, style: &mut Style| {
+                if let Some(this) = weak5.upgrade() {
+                    style.flex_grow = 0.0;
+                    style.flex_shrink = 0.0;
+                    style.flex_basis = Some(this.divider_size.get());
+                    style.cursor = if vertical { Cursor::RowResize } else { Cursor::ColResize };
+                }
+            })
+            .event(On::PointerDown, move |_, ctx: &mut EventCtx<S, H>| {
+                if ctx.pointer()?.button.is_left() {
+                    weak6.upgrade()?.dragging.set(true);
+                    // Keep dragging when the pointer moves past the edge of the split pane
+                    ctx.capture_pointer(key);
+                }
+                Some(Phase::Idle)
+            })
+        }]
+        "pane" [{ .on_style(pane_style(weak7, false)) } (second)]
+        ])
+    }
+}
+
+// Size a pane by its share of the space left over after the divider
+fn pane_style<S: 'static>(weak: Weak<Data>, first: bool) -> impl Fn(&S, &mut Style) + 'static {
+    move |_, style| {
+        if let Some(this) = weak.upgrade() {
+            let ratio = this.ratio.get() as f32;
+            style.flex_grow = if first { ratio } else { 1.0 - ratio };
+            style.flex_shrink = 1.0;
+            style.flex_basis = Some(0.0);
+        }
+    }
+}
+
+impl Data {
+    // Move the divider so its center is at `pos`, keeping both panes within their limits
+    fn drag_to(&self, pos: f32) {
+        let (width, height) = self.size.get();
+        let length = if self.vertical { height } else { width };
+        let available = length - self.divider_size.get();
+        if available <= 0.0 {
+            return;
+        }
+
+        let first = pos - self.divider_size.get() / 2.0;
+        let min_size = self.min_size.get().min(available / 2.0);
+        let first = if self.collapsible.get() && first < min_size / 2.0 {
+            0.0
+        } else if self.collapsible.get() && first > available - min_size / 2.0 {
+            available
+        } else {
+            first.clamp(min_size, available - min_size)
+        };
+
+        self.ratio.set((first / available) as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callbacks::{PointerButton, RawPointerEvent};
+
+    struct State {
+        split: SplitPane,
+    }
+
+    fn view(state: &State) -> View<State, ()> {
+        View::default().add_child(state.split.view(View::default(), View::default()))
+    }
+
+    #[test]
+    fn divider_sets_cursor() {
+        let mut state = State {
+            split: SplitPane::new(0.5, false),
+        };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (106.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        viewport.pointer_move(&mut state, RawPointerEvent::at(53.0, 50.0));
+        assert_eq!(viewport.cursor(), Cursor::ColResize);
+
+        viewport.pointer_move(&mut state, RawPointerEvent::at(20.0, 50.0));
+        assert_eq!(viewport.cursor(), Cursor::Default);
+    }
+
+    #[test]
+    fn drag_continues_past_the_widget() {
+        let mut state = State {
+            split: SplitPane::new(0.5, false),
+        };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (106.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        let down = RawPointerEvent::at(53.0, 50.0).press(PointerButton::Left);
+        viewport.pointer_down(&mut state, down);
+        viewport.pointer_move(
+            &mut state,
+            RawPointerEvent {
+                window_pos_x: 28.0,
+                ..down
+            },
+        );
+        assert!((state.split.get() - 0.25).abs() < 1e-6);
+
+        // The pointer is outside the window, so it isn't over any node
+        viewport.pointer_move(
+            &mut state,
+            RawPointerEvent {
+                window_pos_x: -20.0,
+                ..down
+            },
+        );
+        assert_eq!(state.split.get(), 0.0);
+
+        // Releasing the button ends the drag
+        viewport.pointer_up(
+            &mut state,
+            RawPointerEvent {
+                window_pos_x: -20.0,
+                ..down
+            }
+            .release(PointerButton::Left),
+        );
+        viewport.pointer_move(&mut state, RawPointerEvent::at(80.0, 50.0));
+        assert_eq!(state.split.get(), 0.0);
+    }
+
+    // Drag the divider of a 106px wide split pane from its center, and return the ratio after each move
+    fn drag(split: SplitPane, moves: &[f64]) -> Vec<f64> {
+        let mut state = State { split };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (106.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        let down = RawPointerEvent::at(53.0, 50.0).press(PointerButton::Left);
+        viewport.pointer_down(&mut state, down);
+        moves
+            .iter()
+            .map(|&x| {
+                viewport.pointer_move(&mut state, RawPointerEvent { window_pos_x: x, ..down });
+                state.split.get()
+            })
+            .collect()
+    }
+
+    fn assert_ratios(ratios: Vec<f64>, expected: &[f64]) {
+        assert_eq!(ratios.len(), expected.len());
+        for (ratio, expected) in ratios.iter().zip(expected) {
+            assert!((ratio - expected).abs() < 1e-6, "{:?}", ratios);
+        }
+    }
+
+    #[test]
+    fn panes_stop_at_their_min_size() {
+        let ratios = drag(SplitPane::new(0.5, false).with_min_size(20.0), &[-20.0, 10.0, 200.0, 100.0]);
+        assert_ratios(ratios, &[0.2, 0.2, 0.8, 0.8]);
+    }
+
+    #[test]
+    fn panes_collapse_past_half_their_min_size() {
+        let split = SplitPane::new(0.5, false).with_min_size(20.0).with_collapse(true);
+        let ratios = drag(split, &[-20.0, 15.0, 10.0, 200.0, 96.0, 90.0]);
+        assert_ratios(ratios, &[0.0, 0.2, 0.0, 1.0, 1.0, 0.8]);
+    }
+}
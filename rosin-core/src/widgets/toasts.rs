@@ -0,0 +1,273 @@
+#![forbid(unsafe_code)]
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Debug,
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::prelude::*;
+use crate::style::FlexDirection;
+use crate::widgets::*;
+
+// How long a toast takes to slide in or out
+const SLIDE_TIME: f64 = 0.15;
+
+/// How important a toast is. Each toast gets a class named after its level, so they can be styled differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastLevel {
+    fn class(&self) -> &'static str {
+        match self {
+            ToastLevel::Info => "info",
+            ToastLevel::Success => "success",
+            ToastLevel::Warning => "warning",
+            ToastLevel::Error => "error",
+        }
+    }
+}
+
+// ---------- Toasts ----------
+/// A stack of short notifications that dismiss themselves after a while, or when they're clicked.
+/// Toasts slide open when they're shown, and the rest of the stack closes the gap when one expires.
+///
+/// Each toast has the class `toast` and the class of its level, such as `warning`.
+/// The stack is laid out like any other node, so put it where the toasts should appear, like the bottom of the window.
+#[derive(Debug)]
+pub struct Toasts {
+    pub key: Key,
+    data: Rc<Data>,
+}
+
+#[derive(Debug)]
+struct Toast {
+    id: u64,
+    text: Arc<str>,
+    level: ToastLevel,
+    // Time left before the toast starts closing
+    remaining: Duration,
+    // How far open the toast is, from 0.0 to 1.0
+    progress: Cell<f64>,
+    // The natural height of the toast after the last layout
+    height: Rc<Cell<f32>>,
+}
+
+#[derive(Debug)]
+struct Data {
+    toasts: RefCell<Vec<Toast>>,
+    next_id: Cell<u64>,
+    animating: Cell<bool>,
+}
+
+impl Default for Toasts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        Self {
+            key: Key::new(),
+            data: Rc::new(Data {
+                toasts: RefCell::new(Vec::new()),
+                next_id: Cell::new(0),
+                animating: Cell::new(false),
+            }),
+        }
+    }
+
+    /// Show a toast for `duration`, after which it closes by itself.
+    pub fn show<S, H>(&self, ctx: &mut EventCtx<S, H>, text: impl Into<Arc<str>>, duration: Duration, level: ToastLevel) -> Phase {
+        let id = self.data.next_id.get();
+        self.data.next_id.set(id + 1);
+        self.data.toasts.borrow_mut().push(Toast {
+            id,
+            text: text.into(),
+            level,
+            remaining: duration,
+            progress: Cell::new(0.0),
+            height: Rc::new(Cell::new(0.0)),
+        });
+        self.data.animate(ctx);
+        Phase::Build
+    }
+
+    /// Remove all toasts immediately.
+    pub fn clear(&self) -> Phase {
+        self.data.toasts.borrow_mut().clear();
+        Phase::Build
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.toasts.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.toasts.borrow().is_empty()
+    }
+
+    pub fn view<S, H>(&self) -> View<S, H> {
+        let mut result = ui!([{
+            .key(self.key)
+            .on_style(|_, style: &mut Style| {
+                style.flex_direction = FlexDirection::Column;
+            })
+        }]);
+
+        for toast in self.data.toasts.borrow().iter() {
+            let id = toast.id;
+            let weak1 = Rc::downgrade(&self.data);
+            let weak2 = Rc::downgrade(&self.data);
+
+            let progress = move || {
+                let this = if let Some(this) = weak1.upgrade() { this } else { return 1.0 };
+                let toasts = this.toasts.borrow();
+                toasts.iter().find(|t| t.id == id).map_or(1.0, |toast| toast.progress.get())
+            };
+            let node = reveal(label(toast.text.clone()), progress, toast.height.clone()).event(
+                On::PointerDown,
+                move |_, ctx: &mut EventCtx<S, H>| {
+                    let this = weak2.upgrade()?;
+                    this.dismiss(id, ctx);
+                    Some(Phase::Idle)
+                },
+            );
+
+            result = result.add_child(node.add_classes("toast").add_classes(toast.level.class()));
+        }
+
+        result
+    }
+}
+
+impl Data {
+    // Start closing a toast early
+    fn dismiss<S, H>(self: &Rc<Self>, id: u64, ctx: &mut EventCtx<S, H>) {
+        if let Some(toast) = self.toasts.borrow_mut().iter_mut().find(|t| t.id == id) {
+            toast.remaining = Duration::ZERO;
+        }
+        self.animate(ctx);
+    }
+
+    // Count down the toasts, slide them open and closed, and rebuild when one is removed
+    fn animate<S, H>(self: &Rc<Self>, ctx: &mut EventCtx<S, H>) {
+        if self.animating.replace(true) {
+            return;
+        }
+
+        let weak = Rc::downgrade(self);
+        ctx.start_animation(move |_, dt| {
+            let this = if let Some(this) = weak.upgrade() {
+                this
+            } else {
+                return (Phase::Idle, ShouldStop::Yes);
+            };
+
+            let step = dt.as_secs_f64() / SLIDE_TIME;
+            let mut phase = Phase::Idle;
+            let mut toasts = this.toasts.borrow_mut();
+            for toast in toasts.iter_mut() {
+                let progress = toast.progress.get();
+                if !toast.remaining.is_zero() {
+                    toast.remaining = toast.remaining.saturating_sub(dt);
+                    if progress < 1.0 {
+                        toast.progress.set((progress + step).min(1.0));
+                        phase = Phase::Style;
+                    }
+                } else {
+                    toast.progress.set((progress - step).max(0.0));
+                    phase = Phase::Style;
+                }
+            }
+
+            let count = toasts.len();
+            toasts.retain(|t| !t.remaining.is_zero() || t.progress.get() > 0.0);
+            if toasts.len() != count {
+                phase = Phase::Build;
+            }
+
+            if toasts.is_empty() {
+                this.animating.set(false);
+                (phase, ShouldStop::Yes)
+            } else {
+                (phase, ShouldStop::No)
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callbacks::{PointerButton, RawPointerEvent};
+
+    struct State {
+        toasts: Toasts,
+    }
+
+    // Clicking anywhere shows a toast for a second
+    fn view(state: &State) -> View<State, ()> {
+        View::default()
+            .event(On::PointerDown, |s: &mut State, ctx| {
+                Some(s.toasts.show(ctx, "Saved", Duration::from_secs(1), ToastLevel::Success))
+            })
+            .add_child(state.toasts.view())
+    }
+
+    fn frame(viewport: &mut Viewport<State, ()>, state: &mut State, millis: u64) {
+        viewport.animation_frame(state, Duration::from_millis(millis));
+        viewport.draw(state, None).unwrap();
+    }
+
+    #[test]
+    fn toasts_slide_in_wait_and_slide_out() {
+        let mut state = State { toasts: Toasts::new() };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        viewport.pointer_down(&mut state, RawPointerEvent::at(5.0, 5.0).press(PointerButton::Left));
+        viewport.draw(&state, None).unwrap();
+        assert_eq!(state.toasts.len(), 1);
+
+        frame(&mut viewport, &mut state, 150);
+        assert_eq!(state.toasts.data.toasts.borrow()[0].progress.get(), 1.0);
+
+        // The toast stays open until its time is up, then slides out and is removed
+        frame(&mut viewport, &mut state, 900);
+        assert_eq!(state.toasts.len(), 1);
+        assert_eq!(state.toasts.data.toasts.borrow()[0].progress.get(), 1.0);
+        frame(&mut viewport, &mut state, 75);
+        assert_eq!(state.toasts.data.toasts.borrow()[0].progress.get(), 0.5);
+        frame(&mut viewport, &mut state, 75);
+        assert!(state.toasts.is_empty());
+        assert!(!viewport.has_anim_tasks());
+    }
+
+    #[test]
+    fn later_toasts_get_their_own_time() {
+        let mut state = State { toasts: Toasts::new() };
+        let mut viewport = Viewport::new(ResourceLoader::default(), view, (100.0, 100.0), ());
+        viewport.draw(&state, None).unwrap();
+
+        viewport.pointer_down(&mut state, RawPointerEvent::at(5.0, 5.0).press(PointerButton::Left));
+        frame(&mut viewport, &mut state, 600);
+        viewport.pointer_down(&mut state, RawPointerEvent::at(5.0, 5.0).press(PointerButton::Left));
+        frame(&mut viewport, &mut state, 600);
+        frame(&mut viewport, &mut state, 150);
+        assert_eq!(state.toasts.len(), 1);
+
+        assert_eq!(state.toasts.clear(), Phase::Build);
+        assert!(state.toasts.is_empty());
+        frame(&mut viewport, &mut state, 16);
+        assert!(!viewport.has_anim_tasks());
+    }
+}
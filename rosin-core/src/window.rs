@@ -0,0 +1,182 @@
+#![forbid(unsafe_code)]
+
+use std::{fmt, fmt::Debug, time::Duration};
+
+use druid_shell::WindowLevel;
+
+use crate::prelude::*;
+
+/// A handle to a function that will be called to construct a view tree. Create a ViewFn with the `new_viewfn!()` macro.
+pub struct ViewFn<S: 'static, H: 'static> {
+    pub name: &'static [u8],
+    pub func: ViewCallback<S, H>,
+}
+
+impl<S, H> Debug for ViewFn<S, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}()", self.name)
+    }
+}
+
+#[doc(hidden)]
+impl<S, H> ViewFn<S, H> {
+    pub fn new(name: &'static [u8], func: ViewCallback<S, H>) -> Self {
+        Self { name, func }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct WindowId(u32);
+
+/// A description of a window. Build one with the `with_*()` methods, and the platform layer reads the fields when it opens the window.
+pub struct WindowDesc<S: 'static, H: 'static> {
+    pub view: ViewFn<S, H>,
+    id: WindowId,
+    pub title: Option<String>,
+    pub size: (f32, f32),
+    pub pixel_snap: bool,
+    pub scale_override: Option<f32>,
+    pub transparent: bool,
+    pub level: WindowLevel,
+    pub decorations: bool,
+    pub color_scheme: ColorScheme,
+    pub anim_tasks: Vec<Box<dyn AnimCallback<S>>>,
+    pub shortcuts: Vec<(Modifiers, KbKey, Box<dyn ShortcutCallback<S>>)>,
+    pub focus_callback: Option<Box<dyn WindowCallback<S, bool>>>,
+    pub resize_callback: Option<Box<dyn WindowCallback<S, (f32, f32)>>>,
+    pub scale_callback: Option<Box<dyn WindowCallback<S, (f32, f32)>>>,
+}
+
+impl<S, H> WindowDesc<S, H> {
+    pub fn new(view: ViewFn<S, H>) -> Self {
+        Self {
+            view,
+            id: WindowId(0), // TODO - create a useful id
+            title: None,
+            size: (100.0, 100.0),
+            pixel_snap: false,
+            scale_override: None,
+            transparent: false,
+            level: WindowLevel::AppWindow,
+            decorations: true,
+            color_scheme: ColorScheme::default(),
+            anim_tasks: Vec::new(),
+            shortcuts: Vec::new(),
+            focus_callback: None,
+            resize_callback: None,
+            scale_callback: None,
+        }
+    }
+
+    pub fn with_title<T: Into<String>>(mut self, title: T) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn with_size(mut self, width: f32, height: f32) -> Self {
+        self.size = (width, height);
+        self
+    }
+
+    /// Snap layout to device pixels, keeping thin borders crisp on HiDPI screens.
+    pub fn with_pixel_snap(mut self, pixel_snap: bool) -> Self {
+        self.pixel_snap = pixel_snap;
+        self
+    }
+
+    /// Render at a fixed scale factor instead of the display's, for example 2.0 to get consistent screenshots.
+    /// Layout, text, and pointer positions all follow the forced scale, so content takes more or fewer device pixels.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale_override = Some(scale);
+        self
+    }
+
+    /// Let the desktop show through parts of the window that aren't painted, or are painted with a translucent color.
+    pub fn with_transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// Show the native title bar and border. Without them, use `View::window_drag_region()` to make a custom title bar.
+    pub fn with_decorations(mut self, decorations: bool) -> Self {
+        self.decorations = decorations;
+        self
+    }
+
+    /// Keep the window above normal app windows, like a floating palette. This uses the `Modal` level, since GTK shows
+    /// `Tooltip` and `DropDown` windows as popups without a title bar. On macOS the window floats above all app windows,
+    /// with GTK the window manager treats it as a dialog and usually only keeps it above its app, and Windows ignores it.
+    pub fn with_always_on_top(self, always_on_top: bool) -> Self {
+        let level = if always_on_top {
+            WindowLevel::Modal
+        } else {
+            WindowLevel::AppWindow
+        };
+        self.with_level(level)
+    }
+
+    /// Set where the window sits in the window stack. On macOS `Tooltip` and `DropDown` are floating levels and `Modal` is above them.
+    /// Other platforms treat the level as a hint, or ignore it. Call `set_level()` on `EventCtx::platform_handle` to change it later.
+    pub fn with_level(mut self, level: WindowLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Set the appearance used to match `@media (prefers-color-scheme)` rules. It defaults to `ColorScheme::Light`,
+    /// since the platform's setting isn't read.
+    pub fn with_color_scheme(mut self, color_scheme: ColorScheme) -> Self {
+        self.color_scheme = color_scheme;
+        self
+    }
+
+    pub fn add_anim_task(mut self, callback: impl Fn(&mut S, Duration) -> (Phase, ShouldStop) + 'static) -> Self {
+        self.anim_tasks.push(Box::new(callback));
+        self
+    }
+
+    /// Register a keyboard shortcut, like Ctrl+K to open a command palette.
+    pub fn add_shortcut(mut self, modifiers: Modifiers, key: KbKey, callback: impl Fn(&mut S) -> Phase + 'static) -> Self {
+        self.shortcuts.push((modifiers, key, Box::new(callback)));
+        self
+    }
+
+    /// Called when the window gains (`true`) or loses (`false`) focus.
+    pub fn on_focus(mut self, callback: impl Fn(&mut S, bool) -> Phase + 'static) -> Self {
+        self.focus_callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Called with the new logical size of the window after it's resized.
+    pub fn on_resize(mut self, callback: impl Fn(&mut S, (f32, f32)) -> Phase + 'static) -> Self {
+        self.resize_callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Called with the new scale factor when the window moves to a display with a different scale.
+    pub fn on_scale(mut self, callback: impl Fn(&mut S, (f32, f32)) -> Phase + 'static) -> Self {
+        self.scale_callback = Some(Box::new(callback));
+        self
+    }
+
+    pub fn get_id(&self) -> WindowId {
+        // TODO
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(_: &()) -> View<(), ()> {
+        View::default()
+    }
+
+    #[test]
+    fn always_on_top_uses_a_titled_level() {
+        let desc = WindowDesc::new(ViewFn::new(b"view", view)).with_always_on_top(true);
+        assert!(matches!(desc.level, WindowLevel::Modal));
+        let desc = desc.with_always_on_top(false);
+        assert!(matches!(desc.level, WindowLevel::AppWindow));
+    }
+}
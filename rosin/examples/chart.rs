@@ -0,0 +1,86 @@
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+
+use druid_shell::kurbo::{BezPath, Point, Rect};
+use druid_shell::piet::{Color, RenderContext};
+use rosin::prelude::*;
+use rosin::widgets::*;
+
+pub struct State {
+    style: Stylesheet,
+    values: Vec<f64>,
+}
+
+pub fn main_view(state: &State) -> View<State, WindowHandle> {
+    ui!(state.style.clone(), "root" [
+        "title" (label("Click the chart to add a point"))
+        "chart" (canvas(draw_chart)
+            .event(On::PointerDown, |s: &mut State, _| {
+                // A cheap pseudo-random walk, so each click adds a different point
+                let last = *s.values.last().unwrap_or(&0.5);
+                let step = ((s.values.len() as f64 * 12.9898).sin() * 43758.5453).fract() - 0.5;
+                s.values.push((last + step * 0.4).clamp(0.0, 1.0));
+                Some(Phase::Draw)
+            }))
+    ])
+}
+
+// Draw the values as a line chart, filling the canvas
+fn draw_chart(s: &State, ctx: &mut DrawCtx) {
+    let color = ctx.style.color.clone();
+    let (width, height) = (ctx.width, ctx.height);
+
+    // Grid lines at each quarter
+    for i in 0..=4 {
+        let y = height * i as f64 / 4.0;
+        ctx.draw_line((0.0, y), (width, y), &color.clone().with_alpha(0.2), 1.0);
+    }
+
+    // Legend
+    let legend = Rect::new(8.0, 8.0, 110.0, 32.0);
+    ctx.fill_rounded_rect(legend, 4.0, &Color::rgba8(0, 0, 0, 120));
+    ctx.stroke_rect(legend, &color, 1.0);
+    ctx.fill_rect(Rect::new(16.0, 18.0, 28.0, 22.0), &color);
+    ctx.draw_text(&format!("{} points", s.values.len()), (34.0, 12.0));
+
+    if s.values.len() < 2 {
+        return;
+    }
+
+    let points: Vec<Point> = s
+        .values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| Point::new(width * i as f64 / (s.values.len() - 1) as f64, height * (1.0 - value)))
+        .collect();
+
+    let mut path = BezPath::new();
+    path.move_to(points[0]);
+    for point in &points[1..] {
+        path.line_to(*point);
+    }
+    ctx.piet.stroke(&path, &color, 2.0);
+
+    for point in points {
+        ctx.fill_circle(point, 3.0, &Color::WHITE);
+    }
+}
+
+#[rustfmt::skip]
+fn main() {
+    let view = new_viewfn!(main_view);
+
+    let window = WindowDesc::new(view)
+        .with_title("Rosin Chart")
+        .with_size(600.0, 400.0);
+
+    let mut rl = ResourceLoader::default();
+
+    let state = State {
+        style: load_css!(rl, "examples/chart.css"),
+        values: vec![0.2, 0.5, 0.4, 0.7, 0.6, 0.9],
+    };
+
+    AppLauncher::new(rl, window)
+        .run(state)
+        .expect("Failed to launch");
+}
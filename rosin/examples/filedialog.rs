@@ -0,0 +1,60 @@
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+
+use rosin::prelude::*;
+use rosin::widgets::*;
+
+pub struct State {
+    style: Stylesheet,
+    path: DynLabel,
+}
+
+pub fn main_view(state: &State) -> View<State, WindowHandle> {
+    ui!(state.style.clone(), "root" [
+        "path" (state.path.view())
+        "buttons" [
+            "button" (button("Open...", |_, ctx: &mut EventCtx<State, WindowHandle>| {
+                let options = FileDialogOptions::new()
+                    .allowed_types(vec![FileSpec::TEXT, FileSpec::new("Rust", &["rs"])])
+                    .multi_selection();
+
+                // The dialog doesn't block, so the chosen paths arrive in this callback after the event is handled
+                ctx.open_file_dialog(options, |s: &mut State, paths| {
+                    if paths.is_empty() {
+                        return s.path.set_text("Cancelled");
+                    }
+                    let text: Vec<String> = paths.iter().map(|path| path.display().to_string()).collect();
+                    s.path.set_text(&text.join("\n"))
+                });
+                Some(Phase::Idle)
+            }))
+            "button" (button("Save As...", |_, ctx: &mut EventCtx<State, WindowHandle>| {
+                let options = FileDialogOptions::new().default_name("untitled.txt");
+                ctx.save_file_dialog(options, |s: &mut State, paths| match paths.first() {
+                    Some(path) => s.path.set_text(&path.display().to_string()),
+                    None => s.path.set_text("Cancelled"),
+                });
+                Some(Phase::Idle)
+            }))
+        ]
+    ])
+}
+
+#[rustfmt::skip]
+fn main() {
+    let view = new_viewfn!(main_view);
+
+    let window = WindowDesc::new(view)
+        .with_title("File Dialogs")
+        .with_size(600.0, 300.0);
+
+    let mut rl = ResourceLoader::default();
+
+    let state = State {
+        style: load_css!(rl, "examples/filedialog.css"),
+        path: DynLabel::new("No file chosen"),
+    };
+
+    AppLauncher::new(rl, window)
+        .run(state)
+        .expect("Failed to launch");
+}
@@ -0,0 +1,48 @@
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+
+use druid_shell::piet::Color;
+use rosin::prelude::*;
+
+pub struct State {
+    style: Stylesheet,
+    values: Vec<f64>,
+}
+
+pub fn main_view(state: &State) -> View<State, WindowHandle> {
+    ui!(state.style.clone(), "root" [
+        for i in (0..state.values.len()) {
+            "cell" [{
+                // Color each cell by its value. This runs after the stylesheet, so it overrides `.cell`
+                .on_style(move |s: &State, style: &mut Style| {
+                    let cold = Color::rgb8(40, 70, 160);
+                    let hot = Color::rgb8(230, 60, 40);
+                    style.background_color = cold.mix(&hot, s.values[i]);
+                })
+                .event(On::PointerDown, move |s: &mut State, _ctx| {
+                    s.values[i] = (s.values[i] + 0.25) % 1.25;
+                    Some(Phase::Style)
+                })
+            }]
+        }
+    ])
+}
+
+#[rustfmt::skip]
+fn main() {
+    let view = new_viewfn!(main_view);
+
+    let window = WindowDesc::new(view)
+        .with_title("Rosin Heatmap")
+        .with_size(500.0, 500.0);
+
+    let mut rl = ResourceLoader::default();
+
+    let state = State {
+        style: load_css!(rl, "examples/heatmap.css"),
+        values: (0..16).map(|i| (i % 5) as f64 * 0.25).collect(),
+    };
+
+    AppLauncher::new(rl, window)
+        .run(state)
+        .expect("Failed to launch");
+}
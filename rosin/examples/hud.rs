@@ -0,0 +1,38 @@
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+
+use rosin::prelude::*;
+use rosin::widgets::*;
+
+pub struct State {
+    style: Stylesheet,
+}
+
+#[rustfmt::skip]
+pub fn main_view(state: &State) -> View<State, WindowHandle> {
+    ui!(state.style.clone(), "root" [
+        "panel" [
+            "title" (label("HUD"))
+            "line" (label("The desktop shows through this window"))
+        ]
+    ])
+}
+
+#[rustfmt::skip]
+fn main() {
+    let view = new_viewfn!(main_view);
+
+    let window = WindowDesc::new(view)
+        .with_title("Rosin HUD")
+        .with_size(400.0, 200.0)
+        .with_transparent(true);
+
+    let mut rl = ResourceLoader::default();
+
+    let state = State {
+        style: load_css!(rl, "examples/hud.css"),
+    };
+
+    AppLauncher::new(rl, window)
+        .run(state)
+        .expect("Failed to launch");
+}
@@ -0,0 +1,58 @@
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+
+use rosin::prelude::*;
+use rosin::widgets::*;
+
+pub struct State {
+    style: Stylesheet,
+    count: u32,
+    label: DynLabel,
+    inspector_label: DynLabel,
+}
+
+pub fn main_view(state: &State) -> View<State, WindowHandle> {
+    ui!(state.style.clone(), "root" [
+        "text" (state.label.view())
+        "bump" (button("+", |s: &mut State, ctx| {
+            s.count += 1;
+            let phase = s.label.set_text(&s.count.to_string());
+            s.inspector_label.set_text(&format!("count = {}", s.count));
+
+            // The inspector window shows the same state, so let it know it needs to redraw
+            ctx.broadcast(Phase::Draw);
+            Some(phase)
+        }))
+    ])
+}
+
+#[rustfmt::skip]
+pub fn inspector_view(state: &State) -> View<State, WindowHandle> {
+    ui!(state.style.clone(), "inspector" [
+        "text" (state.inspector_label.view())
+    ])
+}
+
+#[rustfmt::skip]
+fn main() {
+    let window = WindowDesc::new(new_viewfn!(main_view))
+        .with_title("Rosin Window")
+        .with_size(500.0, 500.0);
+
+    let inspector = WindowDesc::new(new_viewfn!(inspector_view))
+        .with_title("Inspector")
+        .with_size(300.0, 150.0);
+
+    let mut rl = ResourceLoader::default();
+
+    let state = State {
+        style: load_css!(rl, "examples/inspector.css"),
+        count: 0,
+        label: DynLabel::new("0"),
+        inspector_label: DynLabel::new("count = 0"),
+    };
+
+    AppLauncher::new(rl, window)
+        .add_window(inspector)
+        .run(state)
+        .expect("Failed to launch");
+}
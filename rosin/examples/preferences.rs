@@ -0,0 +1,55 @@
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+
+use rosin::prelude::*;
+use rosin::widgets::*;
+
+pub struct State {
+    style: Stylesheet,
+    font_size: u32,
+    label: DynLabel,
+}
+
+#[rustfmt::skip]
+pub fn main_view(state: &State) -> View<State, WindowHandle> {
+    ui!(state.style.clone(), "root" [
+        "open" (button("Preferences", |_: &mut State, ctx| {
+            // Each click opens another window, which can be closed without closing this one
+            let window = WindowDesc::new(new_viewfn!(preferences_view))
+                .with_title("Preferences")
+                .with_size(300.0, 200.0);
+            ctx.open_window(window);
+            None
+        }))
+    ])
+}
+
+#[rustfmt::skip]
+pub fn preferences_view(state: &State) -> View<State, WindowHandle> {
+    ui!(state.style.clone(), "preferences" [
+        "text" (state.label.view())
+        "bump" (button("+", |s: &mut State, ctx| {
+            s.font_size += 1;
+            ctx.broadcast(Phase::Draw);
+            Some(s.label.set_text(&format!("Font size: {}", s.font_size)))
+        }))
+    ])
+}
+
+#[rustfmt::skip]
+fn main() {
+    let window = WindowDesc::new(new_viewfn!(main_view))
+        .with_title("Rosin Window")
+        .with_size(500.0, 500.0);
+
+    let mut rl = ResourceLoader::default();
+
+    let state = State {
+        style: load_css!(rl, "examples/preferences.css"),
+        font_size: 12,
+        label: DynLabel::new("Font size: 12"),
+    };
+
+    AppLauncher::new(rl, window)
+        .run(state)
+        .expect("Failed to launch");
+}
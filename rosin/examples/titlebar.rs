@@ -0,0 +1,45 @@
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+
+use rosin::prelude::*;
+use rosin::widgets::*;
+
+pub struct State {
+    style: Stylesheet,
+}
+
+#[rustfmt::skip]
+pub fn main_view(state: &State) -> View<State, WindowHandle> {
+    ui!(state.style.clone(), "root" [
+        // Dragging the title bar moves the window, but the close button still gets clicks
+        "titlebar" [{
+                .window_drag_region()
+            }
+            "title" (label("Custom Title Bar"))
+            "close" (button("x", |_: &mut State, ctx| {
+                ctx.quit();
+                None
+            }))
+        ]
+        "content" (label("Drag the bar above, or double-click it to maximize"))
+    ])
+}
+
+#[rustfmt::skip]
+fn main() {
+    let view = new_viewfn!(main_view);
+
+    let window = WindowDesc::new(view)
+        .with_title("Rosin Title Bar")
+        .with_size(500.0, 300.0)
+        .with_decorations(false);
+
+    let mut rl = ResourceLoader::default();
+
+    let state = State {
+        style: load_css!(rl, "examples/titlebar.css"),
+    };
+
+    AppLauncher::new(rl, window)
+        .run(state)
+        .expect("Failed to launch");
+}
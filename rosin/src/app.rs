@@ -8,14 +8,20 @@ use std::{
     time::Duration,
 };
 
-use crate::{libloader::*, prelude::*, window::Window};
+use crate::{
+    libloader::*,
+    prelude::*,
+    window::{open_window, WindowList},
+};
 
-use druid_shell::{Application, WindowBuilder, WindowHandle};
+use druid_shell::{Application, WindowHandle};
 use rosin_core::prelude::*;
 
 pub struct AppLauncher<S: 'static> {
     resource_loader: ResourceLoader,
     windows: Vec<WindowDesc<S, WindowHandle>>,
+    close_hook: Option<Rc<dyn Fn(&mut S) -> bool>>,
+    commands: Rc<Commands<S>>,
 }
 
 impl<S> AppLauncher<S> {
@@ -23,6 +29,8 @@ impl<S> AppLauncher<S> {
         Self {
             resource_loader,
             windows: vec![window],
+            close_hook: None,
+            commands: Rc::new(Commands::new()),
         }
     }
 
@@ -31,8 +39,24 @@ impl<S> AppLauncher<S> {
         self
     }
 
+    /// Register a function that's called before a window closes. The app quits when its last window is closed.
+    /// Use it to save state, or return `false` to keep the window open. When a callback calls `EventCtx::quit()`,
+    /// it's called once before all of the windows close, and returning `false` keeps them all open.
+    pub fn on_close(mut self, callback: impl Fn(&mut S) -> bool + 'static) -> Self {
+        self.close_hook = Some(Rc::new(callback));
+        self
+    }
+
+    /// Set the app's commands. Every window handles their shortcuts, and callbacks run them with `EventCtx::dispatch()`.
+    pub fn with_commands(mut self, commands: Commands<S>) -> Self {
+        self.commands = Rc::new(commands);
+        self
+    }
+
     pub fn run(self, state: S) -> Result<(), Box<dyn std::error::Error>> {
+        // Every window gets the same state, and can redraw the others with `EventCtx::broadcast()`
         let state = Rc::new(RefCell::new(state));
+        let windows: WindowList = Rc::new(RefCell::new(Vec::new()));
 
         // Set up libloader
         #[cfg(not(all(debug_assertions, feature = "hot-reload")))]
@@ -81,27 +105,16 @@ impl<S> AppLauncher<S> {
         let druid_app = Application::new().unwrap();
 
         for desc in self.windows {
-            let mut builder = WindowBuilder::new(druid_app.clone());
-
-            let handler = Window::new(
+            open_window(
+                druid_app.clone(),
                 self.resource_loader.clone(),
-                desc.view,
-                desc.size,
+                desc,
                 state.clone(),
+                self.close_hook.clone(),
+                self.commands.clone(),
+                windows.clone(),
                 libloader.clone(),
-                desc.anim_tasks,
-            );
-            builder.set_handler(Box::new(handler));
-
-            if let Some(title) = desc.title {
-                builder.set_title(title);
-            }
-
-            builder.set_size((desc.size.0 as f64, desc.size.1 as f64).into());
-
-            let window = builder.build().unwrap();
-
-            window.show();
+            )?;
         }
 
         // Run the app
@@ -12,8 +12,7 @@ pub mod widgets {
 pub mod prelude {
     pub use crate::app::*;
     pub use crate::new_viewfn;
-    pub use crate::viewfn::*;
     pub use crate::window::*;
-    pub use druid_shell::WindowHandle;
+    pub use druid_shell::{WindowHandle, WindowLevel};
     pub use rosin_core::prelude::*;
 }
@@ -2,105 +2,244 @@
 
 use std::{
     any::Any,
-    cell::RefCell,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    path::PathBuf,
     rc::Rc,
     sync::{Arc, Mutex},
-    time::{Duration, Instant},
+    time::Instant,
 };
 
 use druid_shell::{
-    kurbo, piet::Piet, Application, Cursor, FileDialogToken, FileInfo, IdleToken, KeyEvent, MouseButton, MouseButtons, MouseEvent, Region,
-    Scale, TimerToken, WinHandler, WindowHandle,
+    kurbo,
+    piet::{Color, Piet, RenderContext},
+    Application, Cursor, FileDialogToken, FileInfo, IdleToken, KeyEvent, MouseButton, MouseButtons, MouseEvent, Region, Scale, TimerToken,
+    WinHandler, WindowBuilder, WindowHandle, WindowState,
 };
-use rosin_core::alloc::Alloc;
+use rosin_core::{alloc::Alloc, style::Cursor as StyleCursor};
 
 use crate::{libloader::LibLoader, prelude::*};
 
-#[derive(Clone, Copy)]
-pub struct WindowId(u32);
+// The open windows of an app, with the phase each one has been asked to update by the others.
+// Windows are only touched from the main thread, so this doesn't need to be thread-safe.
+pub(crate) type WindowList = Rc<RefCell<Vec<(WindowHandle, Rc<Cell<Phase>>)>>>;
 
-/// A description of a window.
-pub struct WindowDesc<S: 'static, H: 'static> {
-    pub(crate) view: ViewFn<S, H>,
-    pub(crate) id: WindowId,
-    pub(crate) title: Option<String>,
-    pub(crate) size: (f32, f32),
-    pub(crate) anim_tasks: Vec<Box<dyn AnimCallback<S>>>,
-}
+// Scheduled by the resource loader's thread when an asset finishes loading
+const ASSET_LOADED: IdleToken = IdleToken::new(1);
 
-impl<S, H> WindowDesc<S, H> {
-    pub fn new(view: ViewFn<S, H>) -> Self {
-        Self {
-            view,
-            id: WindowId(0), // TODO - create a useful id
-            title: None,
-            size: (100.0, 100.0),
-            anim_tasks: Vec::new(),
-        }
-    }
+// Create a platform window for `desc` and show it
+pub(crate) fn open_window<S>(
+    app: Application,
+    resource_loader: ResourceLoader,
+    mut desc: WindowDesc<S, WindowHandle>,
+    state: Rc<RefCell<S>>,
+    close_hook: Option<Rc<dyn Fn(&mut S) -> bool>>,
+    commands: Rc<Commands<S>>,
+    windows: WindowList,
+    libloader: Option<Arc<Mutex<LibLoader>>>,
+) -> Result<(), druid_shell::Error> {
+    let mut builder = WindowBuilder::new(app);
 
-    pub fn with_title<T: Into<String>>(mut self, title: T) -> Self {
-        self.title = Some(title.into());
-        self
+    if let Some(title) = desc.title.take() {
+        builder.set_title(title);
     }
 
-    pub fn with_size(mut self, width: f32, height: f32) -> Self {
-        self.size = (width, height);
-        self
-    }
+    builder.set_size((desc.size.0 as f64, desc.size.1 as f64).into());
+    builder.set_transparent(desc.transparent);
+    builder.set_level(desc.level);
+    builder.show_titlebar(desc.decorations);
 
-    pub fn add_anim_task(mut self, callback: impl Fn(&mut S, Duration) -> (Phase, ShouldStop) + 'static) -> Self {
-        self.anim_tasks.push(Box::new(callback));
-        self
-    }
+    let handler = Window::new(resource_loader, desc, state, close_hook, commands, windows, libloader);
+    builder.set_handler(Box::new(handler));
 
-    pub fn get_id(&self) -> WindowId {
-        // TODO
-        self.id
-    }
+    let window = builder.build()?;
+
+    window.show();
+
+    Ok(())
 }
 
 #[allow(dead_code)]
 pub(crate) struct Window<S: 'static> {
     handle: WindowHandle,
+    resource_loader: ResourceLoader,
     viewport: Viewport<S, WindowHandle>,
     viewfn: ViewFn<S, WindowHandle>,
     state: Rc<RefCell<S>>,
     libloader: Option<Arc<Mutex<LibLoader>>>,
     last_frame: Option<Instant>,
+    close_hook: Option<Rc<dyn Fn(&mut S) -> bool>>,
+    commands: Rc<Commands<S>>,
+    file_dialogs: HashMap<FileDialogToken, FileDialog<S>>,
+    windows: WindowList,
+    pending_phase: Rc<Cell<Phase>>,
+    // Wakes the window when an asset finishes loading, until it's destroyed
+    waker: Option<WakerToken>,
+    // Set once the window's close callbacks have run, so they don't run again when it's destroyed
+    closed: bool,
+    transparent: bool,
+    scale_override: Option<f32>,
+    display_scale: Scale,
+    window_size: kurbo::Size,
+    drag_origin: Option<kurbo::Point>,
+    focus_callback: Option<Box<dyn WindowCallback<S, bool>>>,
+    resize_callback: Option<Box<dyn WindowCallback<S, (f32, f32)>>>,
+    scale_callback: Option<Box<dyn WindowCallback<S, (f32, f32)>>>,
 }
 
 impl<S> Window<S> {
     pub fn new(
         resource_loader: ResourceLoader,
-        viewfn: ViewFn<S, WindowHandle>,
-        size: (f32, f32),
+        desc: WindowDesc<S, WindowHandle>,
         state: Rc<RefCell<S>>,
+        close_hook: Option<Rc<dyn Fn(&mut S) -> bool>>,
+        commands: Rc<Commands<S>>,
+        windows: WindowList,
         libloader: Option<Arc<Mutex<LibLoader>>>,
-        anim_tasks: Vec<Box<dyn AnimCallback<S>>>,
     ) -> Self {
+        let viewfn = desc.view;
         let handle = WindowHandle::default();
         let mut rosin = if let Some(libloader) = libloader.clone() {
             let view_func = *libloader.lock().unwrap().get(viewfn.name).unwrap();
-            let rosin = Viewport::new(resource_loader, view_func, size, handle.clone());
+            let rosin = Viewport::new(resource_loader.clone(), view_func, desc.size, handle.clone());
             let func: fn(Option<Rc<Alloc>>) = *libloader.lock().unwrap().get(b"set_thread_local_alloc").unwrap();
             func(Some(rosin.get_alloc()));
             rosin
         } else {
-            Viewport::new(resource_loader, viewfn.func, size, handle.clone())
+            Viewport::new(resource_loader.clone(), viewfn.func, desc.size, handle.clone())
         };
 
-        for anim in anim_tasks {
+        rosin.set_pixel_snap(desc.pixel_snap);
+        rosin.set_color_scheme(desc.color_scheme);
+        for anim in desc.anim_tasks {
             rosin.add_anim_task(anim);
         }
+        for (modifiers, key, callback) in desc.shortcuts {
+            rosin.add_shortcut(modifiers, key, callback);
+        }
+        rosin.set_commands(commands.clone());
 
         Self {
             handle,
+            resource_loader,
             viewport: rosin,
             viewfn,
             state,
             libloader,
             last_frame: None,
+            close_hook,
+            commands,
+            file_dialogs: HashMap::new(),
+            windows,
+            pending_phase: Rc::new(Cell::new(Phase::Idle)),
+            waker: None,
+            closed: false,
+            transparent: desc.transparent,
+            scale_override: desc.scale_override,
+            display_scale: Scale::default(),
+            window_size: kurbo::Size::new(desc.size.0 as f64, desc.size.1 as f64),
+            drag_origin: None,
+            focus_callback: desc.focus_callback,
+            resize_callback: desc.resize_callback,
+            scale_callback: desc.scale_callback,
+        }
+    }
+
+    // How much larger content is drawn than the window shell expects, when the scale is overridden
+    fn zoom(&self) -> (f64, f64) {
+        if let Some(scale) = self.scale_override {
+            (scale as f64 / self.display_scale.x(), scale as f64 / self.display_scale.y())
+        } else {
+            (1.0, 1.0)
+        }
+    }
+
+    // Redraw if an event changed anything, and close if a callback asked to quit
+    fn update(&mut self) {
+        let broadcast = self.viewport.take_broadcast();
+        if broadcast != Phase::Idle {
+            for (handle, pending_phase) in self.windows.borrow().iter() {
+                if !Rc::ptr_eq(pending_phase, &self.pending_phase) {
+                    pending_phase.set(pending_phase.get().max(broadcast));
+                    handle.invalidate();
+                    handle.request_anim_frame();
+                }
+            }
+        }
+
+        // Open windows requested by callbacks
+        for desc in self.viewport.take_new_windows() {
+            let result = open_window(
+                Application::global(),
+                self.resource_loader.clone(),
+                desc,
+                self.state.clone(),
+                self.close_hook.clone(),
+                self.commands.clone(),
+                self.windows.clone(),
+                self.libloader.clone(),
+            );
+            if let Err(err) = result {
+                eprintln!("[Rosin] Failed to open window: {}", err);
+            }
+        }
+
+        // Show file dialogs requested by callbacks. The results arrive later in `open_files()` or `save_as()`
+        for dialog in self.viewport.take_file_dialogs() {
+            let token = match dialog.kind {
+                FileDialogKind::Open => self.handle.open_file(dialog.options.clone()),
+                FileDialogKind::Save => self.handle.save_as(dialog.options.clone()),
+            };
+            if let Some(token) = token {
+                self.file_dialogs.insert(token, dialog);
+            } else {
+                // The dialog couldn't be shown, so treat it as cancelled
+                let phase = dialog.finish(&mut self.state.borrow_mut(), Vec::new());
+                self.viewport.update_phase(phase);
+            }
+        }
+
+        if self.viewport.take_quit_request() {
+            self.quit();
+        } else if !self.viewport.is_idle() {
+            self.handle.invalidate();
+            self.handle.request_anim_frame();
+        }
+    }
+
+    // Close every window of the app, which quits it once the last one is destroyed
+    fn quit(&mut self) {
+        {
+            let mut state = self.state.borrow_mut();
+            self.viewport.close(&mut state);
+
+            // The close hook runs once for the whole app, and can still cancel quitting
+            if let Some(close_hook) = &self.close_hook {
+                if !close_hook(&mut state) {
+                    if !self.viewport.is_idle() {
+                        self.handle.invalidate();
+                        self.handle.request_anim_frame();
+                    }
+                    return;
+                }
+            }
+        }
+        self.closed = true;
+
+        // The other windows run their close callbacks when they're destroyed.
+        // Destroying a window removes it from the list, so don't hold a borrow while closing them
+        let handles: Vec<WindowHandle> = self.windows.borrow().iter().map(|(handle, _)| handle.clone()).collect();
+        for handle in handles {
+            handle.close();
+        }
+    }
+
+    // Pass the result of a file dialog to the callback that asked for it
+    fn finish_file_dialog(&mut self, token: FileDialogToken, paths: Vec<PathBuf>) {
+        if let Some(dialog) = self.file_dialogs.remove(&token) {
+            let phase = dialog.finish(&mut self.state.borrow_mut(), paths);
+            self.viewport.update_phase(phase);
+            self.update();
         }
     }
 }
@@ -109,11 +248,24 @@ impl<S> WinHandler for Window<S> {
     fn connect(&mut self, handle: &WindowHandle) {
         self.handle = handle.clone();
         self.viewport.set_handle(handle.clone());
+        self.windows.borrow_mut().push((handle.clone(), self.pending_phase.clone()));
+
+        // Rebuild when an asset from `ResourceLoader::load_async` is ready
+        if let Some(idle) = handle.get_idle_handle() {
+            let idle = Mutex::new(idle);
+            let waker = self
+                .resource_loader
+                .add_waker(move || idle.lock().unwrap().schedule_idle(ASSET_LOADED));
+            self.waker = Some(waker);
+        }
     }
 
     fn prepare_paint(&mut self) {}
 
     fn paint(&mut self, piet: &mut Piet, _invalid: &Region) {
+        // Catch up on changes that other windows have broadcast
+        self.viewport.update_phase(self.pending_phase.replace(Phase::Idle));
+
         // TODO - don't rebuild when not needed
         #[cfg(debug_assertions)]
         {
@@ -144,8 +296,23 @@ impl<S> WinHandler for Window<S> {
                 .animation_frame(&mut self.state.borrow_mut(), now.duration_since(last_frame));
         }
         self.last_frame = Some(now);
+
+        // Clear the previous frame, otherwise translucent areas would build up over time
+        if self.transparent {
+            piet.clear(None, Color::TRANSPARENT);
+        }
+        if self.scale_override.is_some() {
+            let (x, y) = self.zoom();
+            piet.transform(kurbo::Affine::scale_non_uniform(x, y));
+        }
         self.viewport.draw(&self.state.borrow(), Some(piet)).unwrap();
 
+        // Nodes may have moved under a stationary pointer, so update which ones are hovered
+        self.viewport.refresh_pointer(&mut self.state.borrow_mut());
+        if !self.viewport.is_idle() {
+            self.handle.invalidate();
+        }
+
         if self.viewport.has_anim_tasks() {
             self.handle.request_anim_frame();
         }
@@ -156,127 +323,186 @@ impl<S> WinHandler for Window<S> {
     }
 
     fn size(&mut self, size: kurbo::Size) {
-        self.viewport.size((size.width as f32, size.height as f32))
+        self.window_size = size;
+        let (x, y) = self.zoom();
+        let size = ((size.width / x) as f32, (size.height / y) as f32);
+        self.viewport.size(size);
+
+        if let Some(callback) = &self.resize_callback {
+            let phase = callback(&mut self.state.borrow_mut(), size);
+            self.viewport.update_phase(phase);
+        }
     }
 
     fn scale(&mut self, scale: Scale) {
-        self.viewport.scale((scale.x() as f32, scale.y() as f32));
+        self.display_scale = scale;
+        let scale = (scale.x() as f32, scale.y() as f32);
+        if let Some(scale_override) = self.scale_override {
+            self.viewport.scale((scale_override, scale_override));
+            self.size(self.window_size);
+        } else {
+            self.viewport.scale(scale);
+        }
+
+        if let Some(callback) = &self.scale_callback {
+            let phase = callback(&mut self.state.borrow_mut(), scale);
+            self.viewport.update_phase(phase);
+        }
     }
 
     fn rebuild_resources(&mut self) {}
 
     fn command(&mut self, _id: u32) {}
 
-    fn save_as(&mut self, _token: FileDialogToken, _file: Option<FileInfo>) {}
+    fn save_as(&mut self, token: FileDialogToken, file: Option<FileInfo>) {
+        self.finish_file_dialog(token, file.into_iter().map(|file| file.path).collect());
+    }
 
-    fn open_file(&mut self, _token: FileDialogToken, _file: Option<FileInfo>) {}
+    fn open_file(&mut self, token: FileDialogToken, file: Option<FileInfo>) {
+        self.finish_file_dialog(token, file.into_iter().map(|file| file.path).collect());
+    }
+
+    fn open_files(&mut self, token: FileDialogToken, files: Vec<FileInfo>) {
+        self.finish_file_dialog(token, files.into_iter().map(|file| file.path).collect());
+    }
 
     fn key_down(&mut self, event: KeyEvent) -> bool {
-        let mut state = self.state.borrow_mut();
-        let result = self.viewport.key_event(&mut state, event);
-        if !self.viewport.is_idle() {
-            self.handle.invalidate();
-            self.handle.request_anim_frame();
-        }
+        let result = self.viewport.key_event(&mut self.state.borrow_mut(), event);
+        self.update();
         result
     }
 
     fn key_up(&mut self, event: KeyEvent) {
-        let mut state = self.state.borrow_mut();
-        self.viewport.key_event(&mut state, event);
-        if !self.viewport.is_idle() {
-            self.handle.invalidate();
-            self.handle.request_anim_frame();
-        }
+        self.viewport.key_event(&mut self.state.borrow_mut(), event);
+        self.update();
     }
 
     fn wheel(&mut self, event: &MouseEvent) {
-        let pointer_event = convert_event(event);
+        let pointer_event = convert_event(event, self.zoom());
         self.viewport.pointer_wheel(&mut self.state.borrow_mut(), pointer_event);
-        if !self.viewport.is_idle() {
-            self.handle.invalidate();
-            self.handle.request_anim_frame();
-        }
+        self.update();
     }
 
     fn zoom(&mut self, _delta: f64) {}
 
     fn mouse_move(&mut self, event: &MouseEvent) {
-        let pointer_event = convert_event(event);
-        self.handle.set_cursor(&Cursor::Arrow);
-        self.viewport.pointer_move(&mut self.state.borrow_mut(), pointer_event);
-        if !self.viewport.is_idle() {
-            self.handle.invalidate();
-            self.handle.request_anim_frame();
+        // Move the window so the point that was grabbed stays under the pointer
+        if let Some(origin) = self.drag_origin {
+            self.handle
+                .set_position(drag_position(self.handle.get_position(), origin, event.pos));
+            return;
         }
+
+        let pointer_event = convert_event(event, self.zoom());
+        self.viewport.pointer_move(&mut self.state.borrow_mut(), pointer_event);
+        self.handle.set_cursor(&convert_cursor(self.viewport.cursor()));
+        self.update();
     }
 
     fn mouse_down(&mut self, event: &MouseEvent) {
-        let pointer_event = convert_event(event);
-        let mut state = self.state.borrow_mut();
-        self.viewport.pointer_down(&mut state, pointer_event);
-        if !self.viewport.is_idle() {
-            self.handle.invalidate();
-            self.handle.request_anim_frame();
+        let pointer_event = convert_event(event, self.zoom());
+        self.viewport.pointer_down(&mut self.state.borrow_mut(), pointer_event);
+
+        // Handle dragging and double-clicking custom title bars
+        if event.button.is_left() && self.viewport.is_over_drag_region() {
+            if event.count == 2 {
+                let state = match self.handle.get_window_state() {
+                    WindowState::MAXIMIZED => WindowState::RESTORED,
+                    _ => WindowState::MAXIMIZED,
+                };
+                self.handle.set_window_state(state);
+            } else if cfg!(target_os = "windows") {
+                // Let the OS drag the window, like it does for a native title bar
+                self.handle.handle_titlebar(true);
+            } else {
+                // druid-shell only hands the drag to the OS on Windows, so move the window by hand elsewhere
+                self.drag_origin = Some(event.pos);
+            }
         }
+        self.update();
     }
 
     fn mouse_up(&mut self, event: &MouseEvent) {
-        let pointer_event = convert_event(event);
-        let mut state = self.state.borrow_mut();
-        self.viewport.pointer_up(&mut state, pointer_event);
-        if !self.viewport.is_idle() {
-            self.handle.invalidate();
-            self.handle.request_anim_frame();
-        }
+        self.drag_origin = None;
+        let pointer_event = convert_event(event, self.zoom());
+        self.viewport.pointer_up(&mut self.state.borrow_mut(), pointer_event);
+        self.update();
     }
 
     fn mouse_leave(&mut self) {
-        let mut state = self.state.borrow_mut();
-        self.viewport.pointer_leave(&mut state);
-        if !self.viewport.is_idle() {
-            self.handle.invalidate();
-            self.handle.request_anim_frame();
-        }
+        self.viewport.pointer_leave(&mut self.state.borrow_mut());
+        self.update();
     }
 
     fn timer(&mut self, _token: TimerToken) {}
 
     fn got_focus(&mut self) {
-        let mut state = self.state.borrow_mut();
-        self.viewport.got_focus(&mut state);
-        if !self.viewport.is_idle() {
-            self.handle.invalidate();
-            self.handle.request_anim_frame();
+        self.viewport.got_focus(&mut self.state.borrow_mut());
+        if let Some(callback) = &self.focus_callback {
+            let phase = callback(&mut self.state.borrow_mut(), true);
+            self.viewport.update_phase(phase);
         }
+        self.update();
     }
 
     fn lost_focus(&mut self) {
-        let mut state = self.state.borrow_mut();
-        self.viewport.lost_focus(&mut state);
-        if !self.viewport.is_idle() {
-            self.handle.invalidate();
-            self.handle.request_anim_frame();
+        self.viewport.lost_focus(&mut self.state.borrow_mut());
+        if let Some(callback) = &self.focus_callback {
+            let phase = callback(&mut self.state.borrow_mut(), false);
+            self.viewport.update_phase(phase);
         }
+        self.update();
     }
 
     fn request_close(&mut self) {
         let mut state = self.state.borrow_mut();
         self.viewport.close(&mut state);
+
+        // The close hook can keep the window open, for example to ask about unsaved changes
+        if let Some(close_hook) = &self.close_hook {
+            if !close_hook(&mut state) {
+                if !self.viewport.is_idle() {
+                    self.handle.invalidate();
+                    self.handle.request_anim_frame();
+                }
+                return;
+            }
+        }
+        self.closed = true;
         self.handle.close();
     }
 
     fn destroy(&mut self) {
-        Application::global().quit()
+        if let Some(waker) = self.waker.take() {
+            self.resource_loader.remove_waker(waker);
+        }
+
+        // A window closed by another window's `quit()` hasn't run its close callbacks yet
+        if !self.closed {
+            self.closed = true;
+            self.viewport.close(&mut self.state.borrow_mut());
+        }
+
+        // Quit once the last window is gone
+        let mut windows = self.windows.borrow_mut();
+        windows.retain(|(_, pending_phase)| !Rc::ptr_eq(pending_phase, &self.pending_phase));
+        if windows.is_empty() {
+            Application::global().quit()
+        }
     }
 
-    fn idle(&mut self, _token: IdleToken) {}
+    fn idle(&mut self, token: IdleToken) {
+        if token == ASSET_LOADED {
+            self.viewport.update_phase(Phase::Build);
+            self.update();
+        }
+    }
 }
 
-fn convert_event(event: &MouseEvent) -> RawPointerEvent {
+fn convert_event(event: &MouseEvent, zoom: (f64, f64)) -> RawPointerEvent {
     RawPointerEvent {
-        window_pos_x: event.pos.x,
-        window_pos_y: event.pos.y,
+        window_pos_x: event.pos.x / zoom.0,
+        window_pos_y: event.pos.y / zoom.1,
         wheel_x: event.wheel_delta.x,
         wheel_y: event.wheel_delta.y,
         button: convert_button(event.button),
@@ -323,6 +549,204 @@ fn convert_buttons(buttons: MouseButtons) -> PointerButtons {
     result
 }
 
+// The window shell only has a few cursors, so the rest fall back to the arrow
+// Where to move a window so the point grabbed at `origin` stays under the pointer at `pos`.
+// The pointer and the window's position are both in display points, so the display's scale doesn't matter.
+fn drag_position(window: kurbo::Point, origin: kurbo::Point, pos: kurbo::Point) -> kurbo::Point {
+    window + (pos - origin)
+}
+
+fn convert_cursor(cursor: StyleCursor) -> Cursor {
+    match cursor {
+        StyleCursor::Pointer => Cursor::Pointer,
+        StyleCursor::Text | StyleCursor::VerticalText => Cursor::IBeam,
+        StyleCursor::Crosshair | StyleCursor::Cell => Cursor::Crosshair,
+        StyleCursor::Grab | StyleCursor::Grabbing | StyleCursor::Move | StyleCursor::AllScroll => Cursor::OpenHand,
+        StyleCursor::NotAllowed | StyleCursor::NoDrop => Cursor::NotAllowed,
+        StyleCursor::E_Resize | StyleCursor::W_Resize | StyleCursor::WE_Resize | StyleCursor::ColResize => Cursor::ResizeLeftRight,
+        StyleCursor::N_Resize | StyleCursor::S_Resize | StyleCursor::NS_Resize | StyleCursor::RowResize => Cursor::ResizeUpDown,
+        _ => Cursor::Arrow,
+    }
+}
+
 fn convert_mods(mods: druid_shell::Modifiers) -> Modifiers {
     Modifiers::from_bits_truncate(mods.raw().bits())
 }
+
+#[cfg(test)]
+mod tests {
+    use druid_shell::piet::{Device, ImageFormat};
+
+    use super::*;
+
+    type Events = Vec<String>;
+
+    fn first_view(_: &Events) -> View<Events, WindowHandle> {
+        View::default().event(On::WindowClose, |s: &mut Events, _| {
+            s.push("first closed".into());
+            Some(Phase::Idle)
+        })
+    }
+
+    fn second_view(_: &Events) -> View<Events, WindowHandle> {
+        View::default().event(On::WindowClose, |s: &mut Events, _| {
+            s.push("second closed".into());
+            Some(Phase::Idle)
+        })
+    }
+
+    fn box_view(_: &Events) -> View<Events, WindowHandle> {
+        View::default().add_child(View::default().inline_style("width: 10px; height: 10px; flex-grow: 0; background-color: red"))
+    }
+
+    // Create a window without a platform window, and add it to the list like `connect()` does
+    fn window(desc: WindowDesc<Events, WindowHandle>, state: &Rc<RefCell<Events>>, windows: &WindowList) -> Window<Events> {
+        let close_hook: Rc<dyn Fn(&mut Events) -> bool> = Rc::new(|s: &mut Events| {
+            s.push("hook".into());
+            true
+        });
+        let mut window = Window::new(
+            ResourceLoader::default(),
+            desc,
+            state.clone(),
+            Some(close_hook),
+            Rc::new(Commands::new()),
+            windows.clone(),
+            None,
+        );
+        windows.borrow_mut().push((WindowHandle::default(), window.pending_phase.clone()));
+        window.viewport.draw(&state.borrow(), None).unwrap();
+        window
+    }
+
+    // Paint a window over a background color into a 100x100 bitmap, and return its pixels
+    fn paint(window: &mut Window<Events>, background: Color) -> Vec<u8> {
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(100, 100, 1.0).unwrap();
+        let mut piet = target.render_context();
+        piet.clear(None, background);
+        window.paint(&mut piet, &Region::EMPTY);
+        piet.finish().unwrap();
+        drop(piet);
+        target.to_image_buf(ImageFormat::RgbaPremul).unwrap().raw_pixels().to_vec()
+    }
+
+    fn pixel(pixels: &[u8], x: usize, y: usize) -> &[u8] {
+        let i = (y * 100 + x) * 4;
+        &pixels[i..i + 4]
+    }
+
+    #[test]
+    fn quitting_runs_every_windows_close_callbacks() {
+        let state = Rc::new(RefCell::new(Vec::new()));
+        let windows: WindowList = Rc::new(RefCell::new(Vec::new()));
+        let mut first = window(WindowDesc::new(ViewFn::new(b"first", first_view)), &state, &windows);
+        let mut second = window(WindowDesc::new(ViewFn::new(b"second", second_view)), &state, &windows);
+
+        // Another window stays open, so destroying these doesn't quit the app
+        windows
+            .borrow_mut()
+            .push((WindowHandle::default(), Rc::new(Cell::new(Phase::Idle))));
+
+        // The platform destroys every window after `quit()` closes them
+        first.quit();
+        second.destroy();
+        first.destroy();
+        assert_eq!(*state.borrow(), ["first closed", "hook", "second closed"]);
+        assert_eq!(windows.borrow().len(), 1);
+    }
+
+    #[test]
+    fn window_callbacks_get_the_state() {
+        let desc = WindowDesc::new(ViewFn::new(b"first", first_view))
+            .with_scale(2.0)
+            .on_focus(|s: &mut Events, focused| {
+                s.push(format!("focus {}", focused));
+                Phase::Idle
+            })
+            .on_resize(|s: &mut Events, (width, height)| {
+                s.push(format!("resize {}x{}", width, height));
+                Phase::Layout
+            })
+            .on_scale(|s: &mut Events, (x, _)| {
+                s.push(format!("scale {}", x));
+                Phase::Draw
+            });
+        let state = Rc::new(RefCell::new(Vec::new()));
+        let windows: WindowList = Rc::new(RefCell::new(Vec::new()));
+        let mut window = window(desc, &state, &windows);
+
+        // The callbacks get the logical size, which shrinks as the forced scale zooms in
+        window.size(kurbo::Size::new(200.0, 100.0));
+        window.scale(Scale::new(2.0, 2.0));
+        window.got_focus();
+        window.request_close();
+        assert_eq!(
+            *state.borrow(),
+            ["resize 100x50", "resize 200x100", "scale 2", "focus true", "first closed", "hook"]
+        );
+    }
+
+    #[test]
+    fn forcing_the_scale_zooms_the_drawing() {
+        let state = Rc::new(RefCell::new(Vec::new()));
+        let windows: WindowList = Rc::new(RefCell::new(Vec::new()));
+        let mut forced = window(WindowDesc::new(ViewFn::new(b"box", box_view)).with_scale(2.0), &state, &windows);
+        let mut display = window(WindowDesc::new(ViewFn::new(b"box", box_view)), &state, &windows);
+        for window in [&mut forced, &mut display] {
+            window.scale(Scale::new(1.0, 1.0));
+            window.size(kurbo::Size::new(100.0, 100.0));
+        }
+
+        // Forcing twice the display's scale doubles the box's size in device pixels
+        let pixels = paint(&mut forced, Color::WHITE);
+        assert_eq!(pixel(&pixels, 15, 15), [255, 0, 0, 255]);
+        assert_eq!(pixel(&pixels, 25, 25), [255, 255, 255, 255]);
+
+        // Without the override, the box is drawn at the display's scale
+        let pixels = paint(&mut display, Color::WHITE);
+        assert_eq!(pixel(&pixels, 5, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(&pixels, 15, 15), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn transparent_windows_clear_to_transparent() {
+        let state = Rc::new(RefCell::new(Vec::new()));
+        let windows: WindowList = Rc::new(RefCell::new(Vec::new()));
+        let desc = WindowDesc::new(ViewFn::new(b"box", box_view)).with_transparent(true);
+        let mut transparent = window(desc, &state, &windows);
+        let mut opaque = window(WindowDesc::new(ViewFn::new(b"box", box_view)), &state, &windows);
+
+        // The previous frame shows through where an opaque window doesn't paint
+        let pixels = paint(&mut opaque, Color::rgb8(0, 0, 255));
+        assert_eq!(pixel(&pixels, 50, 50), [0, 0, 255, 255]);
+
+        // A transparent window clears it, so the desktop shows through instead
+        let pixels = paint(&mut transparent, Color::rgb8(0, 0, 255));
+        assert_eq!(pixel(&pixels, 5, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(&pixels, 50, 50), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn cursors_match_the_platform() {
+        assert!(matches!(convert_cursor(StyleCursor::Pointer), Cursor::Pointer));
+        assert!(matches!(convert_cursor(StyleCursor::ColResize), Cursor::ResizeLeftRight));
+        assert!(matches!(convert_cursor(StyleCursor::RowResize), Cursor::ResizeUpDown));
+        assert!(matches!(convert_cursor(StyleCursor::Default), Cursor::Arrow));
+    }
+
+    #[test]
+    fn dragging_keeps_the_grabbed_point_under_the_pointer() {
+        let window = kurbo::Point::new(100.0, 200.0);
+        let origin = kurbo::Point::new(10.0, 5.0);
+        assert_eq!(
+            drag_position(window, origin, kurbo::Point::new(30.0, 25.0)),
+            kurbo::Point::new(120.0, 220.0)
+        );
+        assert_eq!(
+            drag_position(window, origin, kurbo::Point::new(0.0, 0.0)),
+            kurbo::Point::new(90.0, 195.0)
+        );
+        assert_eq!(drag_position(window, origin, origin), window);
+    }
+}